@@ -96,109 +96,146 @@ pub fn create_texture(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
 ) -> Result<(wgpu::Texture, wgpu::TextureViewDimension), CreateTextureError> {
-    let size = wgpu::Extent3d {
-        width: nutexb.footer.width,
-        height: nutexb.footer.height,
-        depth_or_array_layers: std::cmp::max(nutexb.footer.layer_count, nutexb.footer.depth),
-    };
+    TextureData::from_nutexb(nutexb).map(|data| data.create_texture(device, queue))
+}
 
-    // TODO: Show what dimension is zero?
-    if size.width == 0 || size.height == 0 || size.depth_or_array_layers == 0 {
-        return Err(CreateTextureError::ZeroSizedDimension);
-    }
+/// The validated and deswizzled image data for a [wgpu::Texture].
+///
+/// Preparing the data doesn't require a [wgpu::Device],
+/// so multiple textures can be prepared in parallel before calling [TextureData::create_texture].
+pub struct TextureData {
+    label: String,
+    size: wgpu::Extent3d,
+    mip_level_count: u32,
+    dimension: wgpu::TextureDimension,
+    view_dimension: wgpu::TextureViewDimension,
+    format: wgpu::TextureFormat,
+    data: Vec<u8>,
+}
 
-    if nutexb.footer.mipmap_count == 0 {
-        return Err(CreateTextureError::ZeroMipmapCount);
-    }
+impl TextureData {
+    pub fn from_nutexb(nutexb: &NutexbFile) -> Result<Self, CreateTextureError> {
+        let size = wgpu::Extent3d {
+            width: nutexb.footer.width,
+            height: nutexb.footer.height,
+            depth_or_array_layers: std::cmp::max(nutexb.footer.layer_count, nutexb.footer.depth),
+        };
 
-    if nutexb.footer.layer_count == 0 {
-        return Err(CreateTextureError::ZeroLayers);
-    }
+        // TODO: Show what dimension is zero?
+        if size.width == 0 || size.height == 0 || size.depth_or_array_layers == 0 {
+            return Err(CreateTextureError::ZeroSizedDimension);
+        }
 
-    let max_dimension = if nutexb.footer.depth > 1 {
-        Limits::default().max_texture_dimension_3d
-    } else {
-        Limits::default().max_texture_dimension_2d
-    };
+        if nutexb.footer.mipmap_count == 0 {
+            return Err(CreateTextureError::ZeroMipmapCount);
+        }
 
-    // TODO: Show dimensions?
-    if nutexb.footer.width > max_dimension
-        || nutexb.footer.height > max_dimension
-        || nutexb.footer.depth > max_dimension
-    {
-        return Err(CreateTextureError::DimensionExceedsLimit);
-    }
+        if nutexb.footer.layer_count == 0 {
+            return Err(CreateTextureError::ZeroLayers);
+        }
 
-    if nutexb.footer.layer_count > Limits::default().max_texture_array_layers {
-        return Err(CreateTextureError::LayerCountExceedsLimit);
-    }
+        let max_dimension = if nutexb.footer.depth > 1 {
+            Limits::default().max_texture_dimension_3d
+        } else {
+            Limits::default().max_texture_dimension_2d
+        };
 
-    let format = wgpu_format(nutexb.footer.image_format);
-    let (block_width, block_height) = format.describe().block_dimensions;
-    if size.width % block_width as u32 != 0 {
-        return Err(CreateTextureError::UnalignedWidth {
-            width: size.width,
-            block_width: block_width as u32,
-        });
-    }
-    if size.height % block_height as u32 != 0 {
-        return Err(CreateTextureError::UnalignedHeight {
-            height: size.height,
-            block_height: block_height as u32,
-        });
-    }
+        // TODO: Show dimensions?
+        if nutexb.footer.width > max_dimension
+            || nutexb.footer.height > max_dimension
+            || nutexb.footer.depth > max_dimension
+        {
+            return Err(CreateTextureError::DimensionExceedsLimit);
+        }
 
-    let dimension = if nutexb.footer.depth > 1 {
-        wgpu::TextureDimension::D3
-    } else {
-        wgpu::TextureDimension::D2
-    };
+        if nutexb.footer.layer_count > Limits::default().max_texture_array_layers {
+            return Err(CreateTextureError::LayerCountExceedsLimit);
+        }
+
+        let format = wgpu_format(nutexb.footer.image_format);
+        let (block_width, block_height) = format.describe().block_dimensions;
+        if size.width % block_width as u32 != 0 {
+            return Err(CreateTextureError::UnalignedWidth {
+                width: size.width,
+                block_width: block_width as u32,
+            });
+        }
+        if size.height % block_height as u32 != 0 {
+            return Err(CreateTextureError::UnalignedHeight {
+                height: size.height,
+                block_height: block_height as u32,
+            });
+        }
 
-    let label = nutexb.footer.string.to_string();
+        let dimension = if nutexb.footer.depth > 1 {
+            wgpu::TextureDimension::D3
+        } else {
+            wgpu::TextureDimension::D2
+        };
 
-    let max_mips = size.max_mips(dimension);
-    if nutexb.footer.mipmap_count > max_mips {
-        warn!(
-            "Mipmap count {} exceeds the maximum of {} for Nutexb {:?}.",
-            nutexb.footer.mipmap_count, max_mips, label,
-        );
-    }
+        let label = nutexb.footer.string.to_string();
+
+        let max_mips = size.max_mips(dimension);
+        if nutexb.footer.mipmap_count > max_mips {
+            warn!(
+                "Mipmap count {} exceeds the maximum of {} for Nutexb {:?}.",
+                nutexb.footer.mipmap_count, max_mips, label,
+            );
+        }
 
-    // TODO: Preserve error information?
-    let data = nutexb
-        .deswizzled_data()
-        .map_err(|_| CreateTextureError::SwizzleError)?;
+        // TODO: Preserve error information?
+        let data = nutexb
+            .deswizzled_data()
+            .map_err(|_| CreateTextureError::SwizzleError)?;
+
+        // TODO: Are there other dimensions for nutexb?
+        let view_dimension = if nutexb.footer.depth > 1 {
+            wgpu::TextureViewDimension::D3
+        } else if nutexb.footer.layer_count == 6 {
+            wgpu::TextureViewDimension::Cube
+        } else {
+            wgpu::TextureViewDimension::D2
+        };
 
-    let texture = device.create_texture_with_data(
-        queue,
-        &wgpu::TextureDescriptor {
-            label: Some(&label),
+        Ok(Self {
+            label,
             size,
             // TODO: Should this be an error?
             // TODO: How does in game handle this case?
             mip_level_count: std::cmp::min(nutexb.footer.mipmap_count, max_mips),
-            sample_count: 1,
             dimension,
+            view_dimension,
             format,
-            usage: wgpu::TextureUsages::COPY_SRC
-                | wgpu::TextureUsages::COPY_DST
-                | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        },
-        &data,
-    );
+            data,
+        })
+    }
 
-    // Return the dimensions since this isn't accessible from the texture itself.
-    // TODO: Are there other dimensions for nutexb?
-    let dim = if nutexb.footer.depth > 1 {
-        wgpu::TextureViewDimension::D3
-    } else if nutexb.footer.layer_count == 6 {
-        wgpu::TextureViewDimension::Cube
-    } else {
-        wgpu::TextureViewDimension::D2
-    };
+    /// Creates the texture on the GPU.
+    pub fn create_texture(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> (wgpu::Texture, wgpu::TextureViewDimension) {
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some(&self.label),
+                size: self.size,
+                mip_level_count: self.mip_level_count,
+                sample_count: 1,
+                dimension: self.dimension,
+                format: self.format,
+                usage: wgpu::TextureUsages::COPY_SRC
+                    | wgpu::TextureUsages::COPY_DST
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            &self.data,
+        );
 
-    Ok((texture, dim))
+        // Return the dimensions since this isn't accessible from the texture itself.
+        (texture, self.view_dimension)
+    }
 }
 
 fn wgpu_format(format: nutexb::NutexbFormat) -> wgpu::TextureFormat {
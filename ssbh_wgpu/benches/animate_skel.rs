@@ -7,6 +7,7 @@ use ssbh_data::skel_data::BillboardType;
 use ssbh_data::skel_data::BoneData;
 use ssbh_wgpu::animation::animate_skel;
 use ssbh_wgpu::animation::AnimationTransforms;
+use ssbh_wgpu::animation::ValidatedSkel;
 
 fn identity_bone(name: &str, parent_index: Option<usize>) -> BoneData {
     BoneData {
@@ -33,6 +34,8 @@ fn animate_skel_roots_benchmark(c: &mut Criterion) {
             bones: vec![identity_bone("A", None); count],
         };
 
+        let skel = ValidatedSkel::new(&skel);
+
         let anim = AnimData {
             major_version: 2,
             minor_version: 0,
@@ -66,6 +69,8 @@ fn animate_skel_chain_benchmark(c: &mut Criterion) {
             bones,
         };
 
+        let skel = ValidatedSkel::new(&skel);
+
         let anim = AnimData {
             major_version: 2,
             minor_version: 0,
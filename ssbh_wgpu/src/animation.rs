@@ -1,22 +1,36 @@
-use self::constraints::{apply_aim_constraint, apply_orient_constraint};
-use crate::{shader::skinning::AnimatedWorldTransforms, RenderMesh};
+use self::constraints::{
+    apply_aim_constraint, apply_orient_constraint, constraint_dependencies, ConstraintDependency,
+};
+use crate::{texture::matches_material_path, RenderMesh};
 use indexmap::IndexSet;
 use ssbh_data::{
-    anim_data::{GroupType, TrackValues, TransformFlags},
-    matl_data::MatlEntryData,
+    anim_data::{GroupType, TrackValues, TransformFlags, UvTransform},
+    matl_data::{MatlEntryData, ParamId},
     prelude::*,
     skel_data::BoneData,
     Vector3, Vector4,
 };
+use std::{
+    collections::{HashMap, VecDeque},
+    str::FromStr,
+};
 
+mod bake;
 pub mod camera;
 mod constraints;
+mod ik;
 pub mod lighting;
+mod motion_path;
+mod retarget;
+mod skel;
 
-/// The maximum number of bones supported by the shader's uniform buffer.
-pub const MAX_BONE_COUNT: usize = 512;
+pub use bake::bake_anim;
+pub use ik::{apply_two_bone_ik, TwoBoneIk};
+pub use motion_path::bone_motion_paths;
+pub use retarget::retarget_anim;
+pub use skel::{SkelValidationError, ValidatedSkel, MAX_SKEL_BONE_COUNT};
 
-// Animation process is Skel, Anim -> Vec<AnimatedBone> -> [Mat4; 512], [Mat4; 512] -> Buffers.
+// Animation process is Skel, Anim -> Vec<AnimatedBone> -> Vec<Mat4>, Vec<Mat4> -> Buffers.
 // Evaluate the "tree" of Vec<AnimatedBone> to compute the final world transforms.
 #[derive(Debug, Clone)]
 pub struct AnimatedBone<'a> {
@@ -28,45 +42,131 @@ pub struct AnimatedBone<'a> {
 
 impl<'a> AnimatedBone<'a> {
     fn animated_transform(&self, scale_compensation: glam::Vec3) -> glam::Mat4 {
-        self.anim_transform
-            .as_ref()
-            .map(|t| {
-                // Decompose the default "rest" pose from the skeleton.
-                // Transform flags allow some parts of the transform to be set externally.
-                // For example, suppose Mario throws a different fighter like Bowser.
-                // Mario's "thrown" anim needs to use some transforms from Bowser's skel.
-                let (skel_scale, skel_rot, scale_trans) =
-                    glam::Mat4::from_cols_array_2d(&self.bone.transform)
-                        .to_scale_rotation_translation();
-
-                let adjusted_transform = AnimTransform {
-                    translation: if self.flags.override_translation {
-                        scale_trans
-                    } else {
-                        t.translation
-                    },
-                    rotation: if self.flags.override_rotation {
-                        skel_rot
-                    } else {
-                        t.rotation
-                    },
-                    scale: if self.flags.override_scale {
-                        skel_scale
-                    } else {
-                        t.scale
-                    },
-                };
-
-                adjusted_transform.to_mat4(scale_compensation)
-            })
+        self.adjusted_transform()
+            .map(|t| t.to_mat4(scale_compensation))
             .unwrap_or_else(|| glam::Mat4::from_cols_array_2d(&self.bone.transform))
     }
 
+    fn adjusted_transform(&self) -> Option<AnimTransform> {
+        self.anim_transform.as_ref().map(|t| {
+            // Decompose the default "rest" pose from the skeleton.
+            // Transform flags allow some parts of the transform to be set externally.
+            // For example, suppose Mario throws a different fighter like Bowser.
+            // Mario's "thrown" anim needs to use some transforms from Bowser's skel.
+            let skel_transform = self.rest_transform();
+
+            AnimTransform {
+                translation: if self.flags.override_translation {
+                    skel_transform.translation
+                } else {
+                    t.translation
+                },
+                rotation: if self.flags.override_rotation {
+                    skel_transform.rotation
+                } else {
+                    t.rotation
+                },
+                scale: if self.flags.override_scale {
+                    skel_transform.scale
+                } else {
+                    t.scale
+                },
+            }
+        })
+    }
+
+    fn rest_transform(&self) -> AnimTransform {
+        let (scale, rotation, translation) =
+            glam::Mat4::from_cols_array_2d(&self.bone.transform).to_scale_rotation_translation();
+        AnimTransform {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    fn blend(&self, other: &Self, weight: f32) -> Self {
+        // Unanimated bones blend from the rest pose.
+        // Resolve the transform flags first since flags can't be blended.
+        let from = self
+            .adjusted_transform()
+            .unwrap_or_else(|| self.rest_transform());
+        let to = other
+            .adjusted_transform()
+            .unwrap_or_else(|| other.rest_transform());
+
+        Self {
+            bone: self.bone,
+            anim_transform: Some(from.blend(&to, weight)),
+            compensate_scale: if weight < 0.5 {
+                self.compensate_scale
+            } else {
+                other.compensate_scale
+            },
+            flags: TransformFlags::default(),
+        }
+    }
+
+    fn apply_override(&self, pose_override: &PoseOverride) -> Self {
+        // Resolve the transform flags first like with blending.
+        let transform = self
+            .adjusted_transform()
+            .unwrap_or_else(|| self.rest_transform());
+
+        let transform = match *pose_override {
+            PoseOverride::Absolute {
+                translation,
+                rotation,
+                scale,
+            } => AnimTransform {
+                translation,
+                rotation,
+                scale,
+            },
+            PoseOverride::Additive {
+                translation,
+                rotation,
+                scale,
+            } => AnimTransform {
+                translation: transform.translation + translation,
+                rotation: (transform.rotation * rotation).normalize(),
+                scale: transform.scale * scale,
+            },
+        };
+
+        Self {
+            bone: self.bone,
+            anim_transform: Some(transform),
+            compensate_scale: self.compensate_scale,
+            flags: TransformFlags::default(),
+        }
+    }
+
     fn transform(&self) -> glam::Mat4 {
         glam::Mat4::from_cols_array_2d(&self.bone.transform)
     }
 }
 
+/// A manual transform for a bone relative to its parent.
+///
+/// Overrides are applied after sampling animations and before applying constraints.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PoseOverride {
+    /// Replace the animated transform.
+    Absolute {
+        translation: glam::Vec3,
+        rotation: glam::Quat,
+        scale: glam::Vec3,
+    },
+    /// Add the translation, apply the rotation after the animated rotation,
+    /// and multiply the scale with the animated transform.
+    Additive {
+        translation: glam::Vec3,
+        rotation: glam::Quat,
+        scale: glam::Vec3,
+    },
+}
+
 #[derive(Debug, Clone, Copy)]
 struct AnimTransform {
     translation: glam::Vec3,
@@ -93,8 +193,83 @@ impl AnimTransform {
         // The order is reversed here since glam is column-major.
         translation * glam::Mat4::from_scale(scale_compensation) * rotation * scale
     }
+
+    fn blend(&self, other: &Self, weight: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(other.translation, weight),
+            rotation: self.rotation.slerp(other.rotation, weight).normalize(),
+            scale: self.scale.lerp(other.scale, weight),
+        }
+    }
+}
+
+/// An animation sampled at `frame` and blended with the previous layers using `weight`.
+///
+/// Layers only affect the bones they animate, so partial animations like face or hand
+/// animations can be layered on top of a body animation.
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationLayer<'a> {
+    pub anim: &'a AnimData,
+    /// The frame to sample for this layer's animation.
+    pub frame: f32,
+    /// The blend weight from `0.0` for no effect to `1.0` to fully replace the previous layers.
+    pub weight: f32,
+}
+
+impl<'a> AnimationLayer<'a> {
+    /// A layer that fully replaces the previous layers for the bones in `anim`.
+    pub fn new(anim: &'a AnimData, frame: f32) -> Self {
+        Self {
+            anim,
+            frame,
+            weight: 1.0,
+        }
+    }
+
+    /// The layers for transitioning from `from` to `to` over `duration` frames.
+    /// The `elapsed` frames since the start of the transition determine the weight of `to`.
+    pub fn cross_fade(from: Self, to: Self, elapsed: f32, duration: f32) -> [Self; 2] {
+        let factor = if duration > 0.0 {
+            (elapsed / duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        [
+            from,
+            Self {
+                weight: to.weight * factor,
+                ..to
+            },
+        ]
+    }
+}
+
+/// The transforms used for skinning in the skinning shader.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimatedWorldTransforms {
+    /// `bone_world.inv() * animated_bone_world` for each bone.
+    pub transforms: Vec<glam::Mat4>,
+    /// The inverse transpose of each transform to use for normals and tangents.
+    pub transforms_inv_transpose: Vec<glam::Mat4>,
+}
+
+/// The evaluated transforms for a single bone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoneTransforms {
+    /// The animated transform of the bone in world space.
+    /// Only [RenderModel::bone_transforms](crate::RenderModel::bone_transforms)
+    /// includes the [RenderModel::root_transform](crate::RenderModel::root_transform).
+    pub world: glam::Mat4,
+    /// The animated transform of the bone relative to its parent.
+    /// This includes any scale compensation and constraints.
+    /// Bones with a zero scale parent use the transform from the skel instead.
+    pub local: glam::Mat4,
+    /// The transform used for skinning equal to `bone_world.inv() * animated_bone_world`.
+    pub skinning: glam::Mat4,
 }
 
+#[derive(Debug, Clone)]
 pub struct AnimationTransforms {
     // TODO: Use a better name to indicate that this is relative to the resting pose.
     /// The animated world transform of each bone relative to its resting pose.
@@ -102,46 +277,99 @@ pub struct AnimationTransforms {
     pub animated_world_transforms: AnimatedWorldTransforms,
     /// The world transform of each bone in the skeleton.
     // TODO: This name is confusing since it's still animated rather than using the rest pose.
-    pub world_transforms: [glam::Mat4; MAX_BONE_COUNT],
+    pub world_transforms: Vec<glam::Mat4>,
 }
 
 impl AnimationTransforms {
+    /// Transforms for a skeleton with no bones.
     pub fn identity() -> Self {
-        // We can just use the identity transform to represent no animation.
-        // Mesh objects parented to a parent bone will likely be positioned at the origin.
         Self {
             animated_world_transforms: AnimatedWorldTransforms {
-                transforms: [glam::Mat4::IDENTITY; MAX_BONE_COUNT],
-                transforms_inv_transpose: [glam::Mat4::IDENTITY; MAX_BONE_COUNT],
+                transforms: Vec::new(),
+                transforms_inv_transpose: Vec::new(),
             },
-            world_transforms: [glam::Mat4::IDENTITY; MAX_BONE_COUNT],
+            world_transforms: Vec::new(),
         }
     }
 
-    pub fn from_skel(skel: &SkelData) -> Self {
+    pub fn from_skel(skel: &ValidatedSkel) -> Self {
         // Calculate the transforms to use before animations are applied.
         // Calculate the world transforms for parenting mesh objects to bones.
         // The skel pose should already match the "pose" in the mesh geometry.
-        let mut world_transforms = [glam::Mat4::IDENTITY; MAX_BONE_COUNT];
-
         // TODO: Add tests to make sure this is transposed correctly?
-        for (i, bone) in skel.bones.iter().enumerate().take(MAX_BONE_COUNT) {
-            // TODO: Return an error instead?
-            let bone_world = skel
-                .calculate_world_transform(bone)
-                .map(|t| glam::Mat4::from_cols_array_2d(&t))
-                .unwrap_or(glam::Mat4::IDENTITY);
-
-            world_transforms[i] = bone_world;
-        }
+        // Validation removes cycles, so this always succeeds.
+        let world_transforms = skel
+            .bones()
+            .iter()
+            .map(|bone| {
+                skel.skel()
+                    .calculate_world_transform(bone)
+                    .map(|t| glam::Mat4::from_cols_array_2d(&t))
+                    .unwrap_or(glam::Mat4::IDENTITY)
+            })
+            .collect();
 
-        Self {
-            animated_world_transforms: AnimatedWorldTransforms {
-                transforms: [glam::Mat4::IDENTITY; MAX_BONE_COUNT],
-                transforms_inv_transpose: [glam::Mat4::IDENTITY; MAX_BONE_COUNT],
-            },
+        let mut transforms = Self {
             world_transforms,
-        }
+            ..Self::identity()
+        };
+        transforms.resize(skel.bones().len());
+        transforms
+    }
+
+    /// The transforms for the bone at `index` in `skel` or `None` if the bone has no transforms.
+    pub fn bone_transforms(&self, skel: &SkelData, index: usize) -> Option<BoneTransforms> {
+        let world = *self.world_transforms.get(index)?;
+        let skinning = *self.animated_world_transforms.transforms.get(index)?;
+
+        let bone = skel.bones.get(index)?;
+        let parent_world = bone
+            .parent_index
+            .and_then(|i| self.world_transforms.get(i))
+            .copied()
+            .unwrap_or(glam::Mat4::IDENTITY);
+
+        // Parents with zero scale can't be inverted and would produce NaN.
+        let local = if parent_world.determinant() == 0.0 {
+            glam::Mat4::from_cols_array_2d(&bone.transform)
+        } else {
+            parent_world.inverse() * world
+        };
+
+        Some(BoneTransforms {
+            world,
+            local,
+            skinning,
+        })
+    }
+
+    /// The transforms for the bone with name `bone_name` in `skel`
+    /// or `None` if the bone is not found.
+    pub fn bone_transforms_by_name(
+        &self,
+        skel: &SkelData,
+        bone_name: &str,
+    ) -> Option<BoneTransforms> {
+        let index = skel.bones.iter().position(|b| b.name == bone_name)?;
+        self.bone_transforms(skel, index)
+    }
+
+    /// The number of bones with transforms.
+    pub fn bone_count(&self) -> usize {
+        self.world_transforms.len()
+    }
+
+    /// Adds or removes transforms to have exactly `bone_count` bones.
+    /// New bones use the identity transform to represent no animation.
+    pub fn resize(&mut self, bone_count: usize) {
+        self.animated_world_transforms
+            .transforms
+            .resize(bone_count, glam::Mat4::IDENTITY);
+        self.animated_world_transforms
+            .transforms_inv_transpose
+            .resize(bone_count, glam::Mat4::IDENTITY);
+        self.world_transforms
+            .resize(bone_count, glam::Mat4::IDENTITY);
     }
 }
 
@@ -177,18 +405,49 @@ impl Visibility for (String, bool) {
 // TODO: Benchmarks for criterion.rs that test performance scaling with bone and constraint count.
 pub fn animate_skel<'a>(
     result: &mut AnimationTransforms,
-    skel: &SkelData,
+    skel: &ValidatedSkel,
     anims: impl Iterator<Item = &'a AnimData>,
     hlpb: Option<&HlpbData>,
     current_frame: f32,
+) {
+    animate_skel_layers(
+        result,
+        skel,
+        anims.map(|anim| AnimationLayer::new(anim, current_frame)),
+        hlpb,
+    );
+}
+
+/// Blends the transforms for each [AnimationLayer] in order
+/// before calculating the world transforms like [animate_skel].
+///
+/// Translation and scale are blended linearly, and rotations use spherical interpolation.
+pub fn animate_skel_layers<'a>(
+    result: &mut AnimationTransforms,
+    skel: &ValidatedSkel,
+    layers: impl Iterator<Item = AnimationLayer<'a>>,
+    hlpb: Option<&HlpbData>,
+) {
+    animate_skel_with_overrides(result, skel, layers, &HashMap::new(), hlpb);
+}
+
+/// Blends the transforms for each [AnimationLayer] like [animate_skel_layers]
+/// and then applies the [PoseOverride] for each bone name in `overrides`.
+///
+/// Overrides are applied before calculating world transforms and constraints,
+/// so constrained bones and children of overridden bones update as well.
+pub fn animate_skel_with_overrides<'a>(
+    result: &mut AnimationTransforms,
+    skel: &ValidatedSkel,
+    layers: impl Iterator<Item = AnimationLayer<'a>>,
+    overrides: &HashMap<String, PoseOverride>,
+    hlpb: Option<&HlpbData>,
 ) {
     // TODO: Avoid allocating here?
-    // TODO: Just take the bones or groups directly?
     let mut bones: Vec<_> = skel
-        .bones
+        .bones()
         .iter()
         .enumerate()
-        .take(MAX_BONE_COUNT)
         .map(|(i, b)| {
             (
                 i,
@@ -203,11 +462,56 @@ pub fn animate_skel<'a>(
         .collect();
 
     // TODO: Is it faster to use a separate array for animation info?
+    for layer in layers {
+        apply_transforms(&mut bones, layer.anim, layer.frame, layer.weight);
+    }
+
+    if !overrides.is_empty() {
+        for (_, bone) in &mut bones {
+            if let Some(pose_override) = overrides.get(&bone.bone.name) {
+                *bone = bone.apply_override(pose_override);
+            }
+        }
+    }
+
+    animate_skel_inner(result, &mut bones, skel.bones(), hlpb);
+}
+
+/// The scale, rotation, and translation of each bone relative to its parent
+/// after applying `anims` without scale compensation or constraints.
+fn sampled_local_transforms<'a>(
+    skel: &ValidatedSkel,
+    anims: impl Iterator<Item = &'a AnimData>,
+    frame: f32,
+) -> Vec<(glam::Vec3, glam::Quat, glam::Vec3)> {
+    let mut bones: Vec<_> = skel
+        .bones()
+        .iter()
+        .enumerate()
+        .map(|(i, b)| {
+            (
+                i,
+                AnimatedBone {
+                    bone: b,
+                    compensate_scale: false,
+                    anim_transform: None,
+                    flags: TransformFlags::default(),
+                },
+            )
+        })
+        .collect();
+
     for anim in anims {
-        apply_transforms(&mut bones, anim, current_frame);
+        apply_transforms(&mut bones, anim, frame, 1.0);
     }
 
-    animate_skel_inner(result, &mut bones, &skel.bones, hlpb);
+    bones
+        .iter()
+        .map(|(_, b)| {
+            let t = b.adjusted_transform().unwrap_or_else(|| b.rest_transform());
+            (t.scale, t.rotation, t.translation)
+        })
+        .collect()
 }
 
 pub fn animate_skel_inner(
@@ -216,13 +520,18 @@ pub fn animate_skel_inner(
     skel_bones: &[BoneData],
     hlpb: Option<&HlpbData>,
 ) {
-    let evaluation_order = evaluation_order(bones);
+    let constraints = hlpb
+        .map(|hlpb| constraint_dependencies(skel_bones, hlpb))
+        .unwrap_or_default();
+    let (evaluation_order, cyclic_constraints) = evaluation_order(bones, &constraints);
+
+    result.resize(bones.len());
 
-    // Assume parents always appear before their children.
+    // Parents always appear before their children in the evaluation order.
     // This partial order respects dependencies, so bones can be iterated exactly once.
     // TODO: Can this be safely combined with the loop below?
-    // TODO: Limit the amount of stack space this function needs.
-    let mut bone_inv_world = [glam::Mat4::IDENTITY; MAX_BONE_COUNT];
+    // TODO: Avoid allocating here?
+    let mut bone_inv_world = vec![glam::Mat4::IDENTITY; bones.len()];
     for i in &evaluation_order {
         let bone = &bones[*i];
         if let Some(parent_index) = bone.1.bone.parent_index {
@@ -235,16 +544,20 @@ pub fn animate_skel_inner(
         *transform = transform.inverse();
     }
 
-    // Evaluate the world transforms first without constraints.
-    // This solves some issues where the constraint source bone hasn't been evaluated yet.
-    // TODO: Do constraints impact the evaluation order in game?
-    // TODO: How to handle cyclic dependencies due to constraining bones to each other?
-    for i in &evaluation_order {
-        let bone = &bones[*i];
-        let (parent_world, current) = calculate_world_transform(bones, &bone.1, result);
-        result.world_transforms[bone.0] = parent_world * current;
+    // Constraints in a cycle can't have their source evaluated before their target.
+    // Evaluate the world transforms without constraints first to give these sources a value.
+    // TODO: How does the game handle circular dependencies from hlpb constraints?
+    if !cyclic_constraints.is_empty() {
+        for i in &evaluation_order {
+            let bone = &bones[*i];
+            let (parent_world, current) = calculate_world_transform(bones, &bone.1, result);
+            result.world_transforms[bone.0] = parent_world * current;
+        }
     }
 
+    // Constraint sources appear before their targets in the evaluation order.
+    // Children of constrained bones appear after their parent,
+    // so constraints also affect the world transforms of any descendants.
     for i in &evaluation_order {
         let bone = &bones[*i];
         let (parent_world, mut current) = calculate_world_transform(bones, &bone.1, result);
@@ -256,11 +569,8 @@ pub fn animate_skel_inner(
         result.world_transforms[bone.0] = parent_world * current;
     }
 
-    // TODO: Does constraining a bone affects the world transforms of its children?
-    // TODO: Can we apply constraints after world transforms and avoid updating affected children?
-    // TODO: How does the game handle circular dependencies from hlpb constraints?
-    for i in (0..bones.len()).take(MAX_BONE_COUNT) {
-        let anim_transform = result.world_transforms[i] * bone_inv_world[i];
+    for (i, inv_world) in bone_inv_world.iter().enumerate() {
+        let anim_transform = result.world_transforms[i] * *inv_world;
 
         result.animated_world_transforms.transforms[i] = anim_transform;
         result.animated_world_transforms.transforms_inv_transpose[i] =
@@ -268,34 +578,103 @@ pub fn animate_skel_inner(
     }
 }
 
-fn evaluation_order(bones: &[(usize, AnimatedBone)]) -> IndexSet<usize> {
-    // The parent-child relationship determines the evaluation order.
-    // The order is partial since only a parent and child bone are comparable.
+/// Finds the names of the constraints in `hlpb` that depend on their own target bone
+/// through parenting or other constraints.
+///
+/// These constraints use the unconstrained world transform of their source bone.
+pub fn constraint_cycles(skel: &ValidatedSkel, hlpb: &HlpbData) -> Vec<String> {
+    let constraints = constraint_dependencies(skel.bones(), hlpb);
+    let (_, cyclic_constraints) = dependency_graph(
+        skel.bones().iter().map(|b| b.parent_index).enumerate(),
+        skel.bones().len(),
+        &constraints,
+    );
+    cyclic_constraints
+        .into_iter()
+        .map(|c| c.name.to_string())
+        .collect()
+}
+
+fn evaluation_order<'a>(
+    bones: &[(usize, AnimatedBone)],
+    constraints: &'a [ConstraintDependency<'a>],
+) -> (IndexSet<usize>, Vec<&'a ConstraintDependency<'a>>) {
+    // The parent-child relationship and constraints determine the evaluation order.
+    // The order is partial since only bones that depend on each other are comparable.
     // We need a topological sort instead of a regular sort to enforce these dependencies.
-    let mut topo_sort = topological_sort::TopologicalSort::<usize>::new();
+    let (dependents, cyclic_constraints) = dependency_graph(
+        bones.iter().map(|(i, b)| (*i, b.bone.parent_index)),
+        bones.len(),
+        constraints,
+    );
+
+    let mut dependency_counts = vec![0usize; dependents.len()];
+    for i in dependents.iter().flatten() {
+        dependency_counts[*i] += 1;
+    }
+
+    // Bones with no dependencies can be evaluated first.
+    let mut queue: VecDeque<_> = (0..dependents.len())
+        .filter(|i| dependency_counts[*i] == 0)
+        .collect();
+
+    // Bones in a parent cycle are never added.
+    // ValidatedSkel removes cycles, so this only happens when calling animate_skel_inner directly.
     let mut evaluation_order = IndexSet::new();
-    for (i, bone) in bones.iter() {
-        if let Some(p) = bone.bone.parent_index {
-            topo_sort.add_dependency(p, *i);
-        } else {
-            // Root bones with no children won't be part of the dependency graph.
-            // Add them manually to ensure all bones get evaluated.
-            // Use a set to avoid duplicates here.
-            evaluation_order.insert(*i);
+    while let Some(i) = queue.pop_front() {
+        evaluation_order.insert(i);
+        for dependent in &dependents[i] {
+            dependency_counts[*dependent] -= 1;
+            if dependency_counts[*dependent] == 0 {
+                queue.push_back(*dependent);
+            }
         }
     }
 
-    // TODO: Cycle checking?
-    loop {
-        let parts = topo_sort.pop_all();
-        if parts.is_empty() {
-            break;
+    (evaluation_order, cyclic_constraints)
+}
+
+/// Finds the bones that depend on the world transform of each bone.
+/// Constraints that would create a cycle are skipped and returned separately.
+fn dependency_graph<'a>(
+    parent_indices: impl Iterator<Item = (usize, Option<usize>)>,
+    bone_count: usize,
+    constraints: &'a [ConstraintDependency<'a>],
+) -> (Vec<Vec<usize>>, Vec<&'a ConstraintDependency<'a>>) {
+    let mut dependents = vec![Vec::new(); bone_count];
+    for (i, parent_index) in parent_indices {
+        if let Some(parent_dependents) = parent_index.and_then(|p| dependents.get_mut(p)) {
+            parent_dependents.push(i);
         }
+    }
 
-        evaluation_order.extend(parts);
+    // Add constraints one at a time to only skip the constraints that close a cycle.
+    let mut cyclic_constraints = Vec::new();
+    for constraint in constraints {
+        if depends_on(&dependents, constraint.source, constraint.target) {
+            cyclic_constraints.push(constraint);
+        } else {
+            dependents[constraint.source].push(constraint.target);
+        }
     }
 
-    evaluation_order
+    (dependents, cyclic_constraints)
+}
+
+fn depends_on(dependents: &[Vec<usize>], bone: usize, dependency: usize) -> bool {
+    // Search for a path from the dependency to the bone.
+    let mut visited = vec![false; dependents.len()];
+    let mut stack = vec![dependency];
+    while let Some(i) = stack.pop() {
+        if i == bone {
+            return true;
+        }
+        if !visited[i] {
+            visited[i] = true;
+            stack.extend(&dependents[i]);
+        }
+    }
+    false
 }
 
 fn apply_constraints(
@@ -335,7 +714,7 @@ fn calculate_world_transform(
     result: &AnimationTransforms,
 ) -> (glam::Mat4, glam::Mat4) {
     if let Some(parent_index) = bone.bone.parent_index {
-        // TODO: Avoid potential indexing panics.
+        // ValidatedSkel ensures the parent index refers to a bone.
         let parent_transform = result.world_transforms[parent_index];
 
         // TODO: How to handle !inherit_scale && !compensate_scale?
@@ -368,7 +747,13 @@ fn apply_transforms<'a>(
     bones: &mut [(usize, AnimatedBone)],
     anim: &AnimData,
     frame: f32,
+    weight: f32,
 ) -> Option<AnimatedBone<'a>> {
+    // Layers with no weight have no effect.
+    if weight <= 0.0 {
+        return None;
+    }
+
     for group in &anim.groups {
         if group.group_type == GroupType::Transform {
             for node in &group.nodes {
@@ -377,7 +762,15 @@ fn apply_transforms<'a>(
                     // TODO: Multiple transform tracks per bone?
                     if let Some(track) = node.tracks.first() {
                         if let TrackValues::Transform(values) = &track.values {
-                            *bone = create_animated_bone(frame, bone.bone, track, values);
+                            if let Some(animated_bone) =
+                                create_animated_bone(frame, bone.bone, track, values)
+                            {
+                                *bone = if weight >= 1.0 {
+                                    animated_bone
+                                } else {
+                                    bone.blend(&animated_bone, weight)
+                                };
+                            }
                         }
                     }
                 }
@@ -404,8 +797,9 @@ pub fn animate_visibility<V: Visibility>(anim: &AnimData, frame: f32, meshes: &m
                             .filter(|m| m.name().starts_with(&node.name))
                         {
                             // TODO: Share this between tracks?
-                            let value = frame_value(values, frame);
-                            mesh.set_visibility(value);
+                            if let Some(value) = frame_value(values, frame) {
+                                mesh.set_visibility(value);
+                            }
                         }
                     }
                 }
@@ -414,91 +808,176 @@ pub fn animate_visibility<V: Visibility>(anim: &AnimData, frame: f32, meshes: &m
     }
 }
 
-// TODO: Add tests for this.
+/// A material with the values from a material animation applied.
+#[derive(Debug, Clone)]
+pub struct AnimatedMaterial {
+    /// The material with animated parameters and texture pattern changes applied.
+    pub material: MatlEntryData,
+    /// The animated UV transforms for texture or UV transform parameters like [ParamId::Texture0].
+    pub uv_transforms: Vec<(ParamId, UvTransform)>,
+}
+
+impl AnimatedMaterial {
+    /// The unanimated values for `material`.
+    pub fn new(material: &MatlEntryData) -> Self {
+        Self {
+            material: material.clone(),
+            uv_transforms: Vec::new(),
+        }
+    }
+}
+
+/// Applies `anim` to `materials` like [apply_material_animation].
 pub fn animate_materials(
     anim: &AnimData,
     frame: f32,
     materials: &[MatlEntryData],
-) -> Vec<MatlEntryData> {
+    texture_names: &[&str],
+) -> Vec<AnimatedMaterial> {
     // Avoid modifying the original materials.
     // TODO: Iterate instead to avoid allocating?
     // TODO: Is this approach significantly slower than modifying in place?
-    let mut changed_materials = materials.to_vec();
+    let mut changed_materials: Vec<_> = materials.iter().map(AnimatedMaterial::new).collect();
+    apply_material_animation(anim, frame, &mut changed_materials, texture_names);
+    changed_materials
+}
 
+/// Applies `anim` to the `materials` with matching labels.
+/// This allows combining the changes from multiple animations.
+///
+/// Texture pattern animations only assign textures with a file name in `texture_names`.
+pub fn apply_material_animation(
+    anim: &AnimData,
+    frame: f32,
+    materials: &mut [AnimatedMaterial],
+    texture_names: &[&str],
+) {
     for group in &anim.groups {
         if group.group_type == GroupType::Material {
             for node in &group.nodes {
-                if let Some(material) = changed_materials
+                if let Some(material) = materials
                     .iter_mut()
-                    .find(|m| m.material_label == node.name)
+                    .find(|m| m.material.material_label == node.name)
                 {
-                    apply_material_track(node, frame, material);
+                    apply_material_track(node, frame, material, texture_names);
                 }
             }
         }
     }
-
-    changed_materials
 }
 
 fn apply_material_track(
     node: &ssbh_data::anim_data::NodeData,
     frame: f32,
-    changed_material: &mut MatlEntryData,
+    changed_material: &mut AnimatedMaterial,
+    texture_names: &[&str],
 ) {
+    let material = &mut changed_material.material;
     for track in &node.tracks {
-        // TODO: Update material parameters based on the type.
         match &track.values {
-            TrackValues::Transform(_) => todo!(),
-            TrackValues::UvTransform(_) => {
-                // TODO: UV transforms?
+            TrackValues::Transform(_) => {
+                // Transform tracks only apply to bones.
             }
-            TrackValues::Float(v) => {
-                if let Some(param) = changed_material
-                    .floats
-                    .iter_mut()
-                    .find(|p| track.name == p.param_id.to_string())
+            TrackValues::UvTransform(v) => {
+                if let (Ok(param_id), Some(value)) =
+                    (ParamId::from_str(&track.name), frame_value(v, frame))
                 {
-                    param.data = frame_value(v, frame);
+                    let uv_transforms = &mut changed_material.uv_transforms;
+                    match uv_transforms.iter_mut().find(|(p, _)| *p == param_id) {
+                        Some((_, uv_transform)) => *uv_transform = value,
+                        None => uv_transforms.push((param_id, value)),
+                    }
+                }
+            }
+            TrackValues::Float(v) => {
+                if let (Some(param), Some(value)) = (
+                    material
+                        .floats
+                        .iter_mut()
+                        .find(|p| track.name == p.param_id.to_string()),
+                    frame_value(v, frame),
+                ) {
+                    param.data = value;
+                }
+            }
+            TrackValues::PatternIndex(v) => {
+                // Pattern animations swap textures like eye textures for blinking.
+                if let (Some(param), Some(value)) = (
+                    material
+                        .textures
+                        .iter_mut()
+                        .find(|p| track.name == p.param_id.to_string()),
+                    frame_value(v, frame),
+                ) {
+                    // Keep the current texture if the model has no texture for the pattern.
+                    if let Some(name) = pattern_texture_name(&param.data, value).filter(|name| {
+                        texture_names
+                            .iter()
+                            .any(|file_name| matches_material_path(file_name, name))
+                    }) {
+                        param.data = name;
+                    }
                 }
             }
-            TrackValues::PatternIndex(_) => (),
             TrackValues::Boolean(v) => {
-                if let Some(param) = changed_material
-                    .booleans
-                    .iter_mut()
-                    .find(|p| track.name == p.param_id.to_string())
-                {
-                    param.data = frame_value(v, frame);
+                if let (Some(param), Some(value)) = (
+                    material
+                        .booleans
+                        .iter_mut()
+                        .find(|p| track.name == p.param_id.to_string()),
+                    frame_value(v, frame),
+                ) {
+                    param.data = value;
                 }
             }
             TrackValues::Vector4(v) => {
-                if let Some(param) = changed_material
-                    .vectors
-                    .iter_mut()
-                    .find(|p| track.name == p.param_id.to_string())
-                {
-                    param.data = frame_value(v, frame);
+                if let (Some(param), Some(value)) = (
+                    material
+                        .vectors
+                        .iter_mut()
+                        .find(|p| track.name == p.param_id.to_string()),
+                    frame_value(v, frame),
+                ) {
+                    param.data = value;
                 }
             }
         }
     }
 }
 
+fn pattern_texture_name(name: &str, pattern_index: u32) -> Option<String> {
+    // Replace the last number in the name while preserving leading zeros.
+    // For example, "eye_001_col" with index 2 becomes "eye_002_col".
+    // TODO: Does the game use the pattern index directly or with an offset?
+    let end = name.rfind(|c: char| c.is_ascii_digit())? + 1;
+    let start = name[..end]
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let width = end - start;
+
+    Some(format!(
+        "{}{:0width$}{}",
+        &name[..start],
+        pattern_index,
+        &name[end..]
+    ))
+}
+
 fn create_animated_bone<'a>(
     frame: f32,
     bone: &'a BoneData,
     track: &ssbh_data::anim_data::TrackData,
     values: &[ssbh_data::anim_data::Transform],
-) -> AnimatedBone<'a> {
-    let anim_transform = frame_value(values, frame).into();
+) -> Option<AnimatedBone<'a>> {
+    let anim_transform = frame_value(values, frame)?.into();
 
-    AnimatedBone {
+    Some(AnimatedBone {
         bone,
         anim_transform: Some(anim_transform),
         compensate_scale: track.compensate_scale, // TODO: override compensate scale?
         flags: track.transform_flags,
-    }
+    })
 }
 
 trait Interpolate {
@@ -512,6 +991,25 @@ impl Interpolate for bool {
     }
 }
 
+impl Interpolate for u32 {
+    fn interpolate(&self, _other: &Self, _factor: f32) -> Self {
+        // Pattern indices don't interpolate.
+        *self
+    }
+}
+
+impl Interpolate for UvTransform {
+    fn interpolate(&self, other: &Self, factor: f32) -> Self {
+        Self {
+            scale_u: self.scale_u.interpolate(&other.scale_u, factor),
+            scale_v: self.scale_v.interpolate(&other.scale_v, factor),
+            rotation: self.rotation.interpolate(&other.rotation, factor),
+            translate_u: self.translate_u.interpolate(&other.translate_u, factor),
+            translate_v: self.translate_v.interpolate(&other.translate_v, factor),
+        }
+    }
+}
+
 impl Interpolate for f32 {
     fn interpolate(&self, other: &Self, factor: f32) -> Self {
         self * (1.0 - factor) + other * factor
@@ -557,19 +1055,21 @@ impl Interpolate for ssbh_data::anim_data::Transform {
     }
 }
 
-fn frame_value<T>(values: &[T], frame: f32) -> T
+fn frame_value<T>(values: &[T], frame: f32) -> Option<T>
 where
     T: Interpolate,
 {
     // Force the frame to be in bounds.
+    // Tracks with no values have no effect.
     // TODO: Is this the correct way to handle single frame const animations?
     // TODO: Tests for interpolation?
-    let current_frame = (frame.floor() as usize).clamp(0, values.len() - 1);
-    let next_frame = (frame.ceil() as usize).clamp(0, values.len() - 1);
+    let last_frame = values.len().checked_sub(1)?;
+    let current_frame = (frame.floor() as usize).clamp(0, last_frame);
+    let next_frame = (frame.ceil() as usize).clamp(0, last_frame);
     let factor = frame.fract();
 
     // Frame values like 3.5 should be an average of values[3] and values[4].
-    values[current_frame].interpolate(&values[next_frame], factor)
+    Some(values[current_frame].interpolate(&values[next_frame], factor))
 }
 
 #[cfg(test)]
@@ -577,13 +1077,14 @@ mod tests {
     use indexmap::indexset;
     use ssbh_data::{
         anim_data::{GroupData, NodeData, TrackData, Transform, TransformFlags},
-        hlpb_data::OrientConstraintData,
+        hlpb_data::{AimConstraintData, OrientConstraintData},
+        matl_data::{FloatParam, TextureParam},
         skel_data::{BillboardType, BoneData},
     };
 
     use super::*;
 
-    use crate::assert_matrix_relative_eq;
+    use crate::{assert_matrix_relative_eq, assert_vector_relative_eq};
 
     fn identity_bone(name: &str, parent_index: Option<usize>) -> BoneData {
         BoneData {
@@ -621,38 +1122,37 @@ mod tests {
 
     #[test]
     fn animation_transforms_from_skel_512_bones() {
-        AnimationTransforms::from_skel(&SkelData {
+        AnimationTransforms::from_skel(&ValidatedSkel::new(&SkelData {
             major_version: 1,
             minor_version: 0,
             bones: vec![identity_bone("A", None); 512],
-        });
+        }));
     }
 
     #[test]
     fn animation_transforms_from_skel_600_bones() {
-        // Make sure that this doesn't panic.
-        AnimationTransforms::from_skel(&SkelData {
+        // Bones past the limit in game should still have transforms.
+        let transforms = AnimationTransforms::from_skel(&ValidatedSkel::new(&SkelData {
             major_version: 1,
             minor_version: 0,
             bones: vec![identity_bone("A", None); 600],
-        });
+        }));
+        assert_eq!(600, transforms.bone_count());
+        assert_eq!(600, transforms.animated_world_transforms.transforms.len());
     }
 
-    // TODO: Cycle detection in the skeleton?
-    // TODO: Validate the skeleton and convert to a new type?
     // TODO: Out of range frame indices (negative, too large, etc)
     // TODO: Interpolation behavior
 
     #[test]
     fn apply_empty_animation_512_bones() {
-        // TODO: Should this enforce the limit in Smash Ultimate of 511 instead?
         animate_skel(
             &mut AnimationTransforms::identity(),
-            &SkelData {
+            &ValidatedSkel::new(&SkelData {
                 major_version: 1,
                 minor_version: 0,
                 bones: vec![identity_bone("A", None); 512],
-            },
+            }),
             [AnimData {
                 major_version: 2,
                 minor_version: 0,
@@ -667,14 +1167,15 @@ mod tests {
 
     #[test]
     fn apply_empty_animation_too_many_bones() {
-        // TODO: Should this be an error?
+        // ValidatedSkel reports an error but still animates all the bones.
+        let mut transforms = AnimationTransforms::identity();
         animate_skel(
-            &mut AnimationTransforms::identity(),
-            &SkelData {
+            &mut transforms,
+            &ValidatedSkel::new(&SkelData {
                 major_version: 1,
                 minor_version: 0,
                 bones: vec![identity_bone("A", None); 600],
-            },
+            }),
             [AnimData {
                 major_version: 2,
                 minor_version: 0,
@@ -685,17 +1186,18 @@ mod tests {
             None,
             0.0,
         );
+        assert_eq!(600, transforms.bone_count());
     }
 
     #[test]
     fn apply_empty_animation_no_bones() {
         animate_skel(
             &mut AnimationTransforms::identity(),
-            &SkelData {
+            &ValidatedSkel::new(&SkelData {
                 major_version: 1,
                 minor_version: 0,
                 bones: Vec::new(),
-            },
+            }),
             [AnimData {
                 major_version: 2,
                 minor_version: 0,
@@ -715,11 +1217,11 @@ mod tests {
         let mut transforms = AnimationTransforms::identity();
         animate_skel(
             &mut transforms,
-            &SkelData {
+            &ValidatedSkel::new(&SkelData {
                 major_version: 1,
                 minor_version: 0,
                 bones: vec![identity_bone("A", None)],
-            },
+            }),
             [AnimData {
                 major_version: 2,
                 minor_version: 0,
@@ -785,7 +1287,7 @@ mod tests {
         let mut transforms = AnimationTransforms::identity();
         animate_skel(
             &mut transforms,
-            &SkelData {
+            &ValidatedSkel::new(&SkelData {
                 major_version: 1,
                 minor_version: 0,
                 bones: vec![
@@ -793,7 +1295,7 @@ mod tests {
                     identity_bone("B", None),
                     identity_bone("C", None),
                 ],
-            },
+            }),
             [
                 AnimData {
                     major_version: 2,
@@ -908,7 +1410,7 @@ mod tests {
         let mut transforms = AnimationTransforms::identity();
         animate_skel(
             &mut transforms,
-            &SkelData {
+            &ValidatedSkel::new(&SkelData {
                 major_version: 1,
                 minor_version: 0,
                 bones: vec![
@@ -916,7 +1418,7 @@ mod tests {
                     identity_bone("B", Some(0)),
                     identity_bone("C", Some(1)),
                 ],
-            },
+            }),
             [AnimData {
                 major_version: 2,
                 minor_version: 0,
@@ -1008,7 +1510,7 @@ mod tests {
         let mut transforms = AnimationTransforms::identity();
         animate_skel(
             &mut transforms,
-            &SkelData {
+            &ValidatedSkel::new(&SkelData {
                 major_version: 1,
                 minor_version: 0,
                 bones: vec![
@@ -1016,7 +1518,7 @@ mod tests {
                     identity_bone("B", Some(0)),
                     identity_bone("C", Some(1)),
                 ],
-            },
+            }),
             [AnimData {
                 major_version: 2,
                 minor_version: 0,
@@ -1214,7 +1716,7 @@ mod tests {
         let mut transforms = AnimationTransforms::identity();
         animate_skel(
             &mut transforms,
-            &SkelData {
+            &ValidatedSkel::new(&SkelData {
                 major_version: 1,
                 minor_version: 0,
                 bones: vec![
@@ -1223,7 +1725,7 @@ mod tests {
                     identity_bone("B", Some(0)),
                     identity_bone("C", Some(1)),
                 ],
-            },
+            }),
             [AnimData {
                 major_version: 2,
                 minor_version: 0,
@@ -1346,11 +1848,11 @@ mod tests {
         // TODO: Adjust this test to detect incorrectly precomputing anim world transforms.
         animate_skel(
             &mut transforms,
-            &ssbh_data::skel_data::SkelData {
+            &ValidatedSkel::new(&ssbh_data::skel_data::SkelData {
                 major_version: 1,
                 minor_version: 0,
                 bones: vec![l0, l1, r0, r1],
-            },
+            }),
             [AnimData {
                 major_version: 2,
                 minor_version: 0,
@@ -1529,22 +2031,26 @@ mod tests {
 
     #[test]
     fn evaluation_order_empty() {
-        assert!(evaluation_order(&mut Vec::new()).is_empty());
+        assert!(evaluation_order(&[], &[]).0.is_empty());
     }
 
     #[test]
     fn evaluation_order_single_bone() {
         assert_eq!(
             indexset![0],
-            evaluation_order(&mut vec![(
-                0,
-                AnimatedBone {
-                    bone: &identity_bone("a", None),
-                    anim_transform: None,
-                    compensate_scale: false,
-                    flags: TransformFlags::default()
-                }
-            )])
+            evaluation_order(
+                &[(
+                    0,
+                    AnimatedBone {
+                        bone: &identity_bone("a", None),
+                        anim_transform: None,
+                        compensate_scale: false,
+                        flags: TransformFlags::default()
+                    }
+                )],
+                &[]
+            )
+            .0
         );
     }
 
@@ -1552,63 +2058,815 @@ mod tests {
     fn evaluation_order_multiple_bones() {
         assert_eq!(
             indexset![2, 0, 1],
-            evaluation_order(&mut vec![
-                (
-                    0,
-                    AnimatedBone {
-                        bone: &identity_bone("child", Some(2)),
-                        anim_transform: None,
-                        compensate_scale: false,
-                        flags: TransformFlags::default()
-                    }
-                ),
-                (
-                    1,
-                    AnimatedBone {
-                        bone: &identity_bone("grandchild", Some(0)),
-                        anim_transform: None,
-                        compensate_scale: false,
-                        flags: TransformFlags::default()
-                    }
-                ),
-                (
-                    2,
-                    AnimatedBone {
-                        bone: &identity_bone("root", None),
-                        anim_transform: None,
-                        compensate_scale: false,
-                        flags: TransformFlags::default()
-                    }
-                )
-            ])
+            evaluation_order(
+                &[
+                    (
+                        0,
+                        AnimatedBone {
+                            bone: &identity_bone("child", Some(2)),
+                            anim_transform: None,
+                            compensate_scale: false,
+                            flags: TransformFlags::default()
+                        }
+                    ),
+                    (
+                        1,
+                        AnimatedBone {
+                            bone: &identity_bone("grandchild", Some(0)),
+                            anim_transform: None,
+                            compensate_scale: false,
+                            flags: TransformFlags::default()
+                        }
+                    ),
+                    (
+                        2,
+                        AnimatedBone {
+                            bone: &identity_bone("root", None),
+                            anim_transform: None,
+                            compensate_scale: false,
+                            flags: TransformFlags::default()
+                        }
+                    )
+                ],
+                &[]
+            )
+            .0
         );
     }
 
     #[test]
     fn evaluation_order_cycles() {
-        // TODO: How should this case be handled?
+        // Cycles are removed when creating a ValidatedSkel.
         assert_eq!(
             indexset![],
-            evaluation_order(&mut vec![
-                (
-                    0,
-                    AnimatedBone {
-                        bone: &identity_bone("a", Some(1)),
-                        anim_transform: None,
-                        compensate_scale: false,
-                        flags: TransformFlags::default()
-                    }
-                ),
-                (
-                    1,
-                    AnimatedBone {
-                        bone: &identity_bone("b", Some(0)),
-                        anim_transform: None,
-                        compensate_scale: false,
-                        flags: TransformFlags::default()
-                    }
+            evaluation_order(
+                &[
+                    (
+                        0,
+                        AnimatedBone {
+                            bone: &identity_bone("a", Some(1)),
+                            anim_transform: None,
+                            compensate_scale: false,
+                            flags: TransformFlags::default()
+                        }
+                    ),
+                    (
+                        1,
+                        AnimatedBone {
+                            bone: &identity_bone("b", Some(0)),
+                            anim_transform: None,
+                            compensate_scale: false,
+                            flags: TransformFlags::default()
+                        }
+                    ),
+                ],
+                &[]
+            )
+            .0
+        );
+    }
+
+    fn unanimated_bones(skel_bones: &[BoneData]) -> Vec<(usize, AnimatedBone)> {
+        skel_bones
+            .iter()
+            .map(|bone| AnimatedBone {
+                bone,
+                anim_transform: None,
+                compensate_scale: false,
+                flags: TransformFlags::default(),
+            })
+            .enumerate()
+            .collect()
+    }
+
+    #[test]
+    fn evaluation_order_constraint_source_after_target() {
+        let skel_bones = vec![
+            identity_bone("a", None),
+            identity_bone("b", Some(0)),
+            identity_bone("c", None),
+        ];
+        let bones = unanimated_bones(&skel_bones);
+
+        // The constrained bone and its children should be evaluated after the source.
+        let constraints = [ConstraintDependency {
+            name: "constraint1",
+            source: 2,
+            target: 0,
+        }];
+        let (order, cyclic_constraints) = evaluation_order(&bones, &constraints);
+        assert_eq!(vec![2, 0, 1], order.into_iter().collect::<Vec<_>>());
+        assert!(cyclic_constraints.is_empty());
+    }
+
+    #[test]
+    fn evaluation_order_chained_constraints() {
+        let skel_bones = vec![
+            identity_bone("a", None),
+            identity_bone("b", None),
+            identity_bone("c", None),
+        ];
+        let bones = unanimated_bones(&skel_bones);
+
+        // c -> b -> a
+        let constraints = [
+            ConstraintDependency {
+                name: "constraint1",
+                source: 1,
+                target: 0,
+            },
+            ConstraintDependency {
+                name: "constraint2",
+                source: 2,
+                target: 1,
+            },
+        ];
+        let (order, cyclic_constraints) = evaluation_order(&bones, &constraints);
+        assert_eq!(vec![2, 1, 0], order.into_iter().collect::<Vec<_>>());
+        assert!(cyclic_constraints.is_empty());
+    }
+
+    #[test]
+    fn evaluation_order_constraint_cycles() {
+        let skel_bones = vec![
+            identity_bone("a", None),
+            identity_bone("b", Some(0)),
+            identity_bone("c", None),
+        ];
+        let bones = unanimated_bones(&skel_bones);
+
+        // The parent of the source can't depend on the source.
+        // Constraining c and a to each other also creates a cycle.
+        let constraints = [
+            ConstraintDependency {
+                name: "constraint1",
+                source: 1,
+                target: 0,
+            },
+            ConstraintDependency {
+                name: "constraint2",
+                source: 0,
+                target: 2,
+            },
+            ConstraintDependency {
+                name: "constraint3",
+                source: 2,
+                target: 0,
+            },
+        ];
+        let (order, cyclic_constraints) = evaluation_order(&bones, &constraints);
+        assert_eq!(vec![0, 1, 2], order.into_iter().collect::<Vec<_>>());
+        assert_eq!(vec![&constraints[0], &constraints[2]], cyclic_constraints);
+    }
+
+    #[test]
+    fn constraint_cycles_names() {
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![identity_bone("a", None), identity_bone("b", Some(0))],
+        };
+        let hlpb = HlpbData {
+            major_version: 1,
+            minor_version: 0,
+            aim_constraints: vec![AimConstraintData {
+                name: "aim1".into(),
+                aim_bone_name1: "b".into(),
+                aim_bone_name2: "b".into(),
+                aim_type1: "DEFAULT".into(),
+                aim_type2: "DEFAULT".into(),
+                target_bone_name1: "a".into(),
+                target_bone_name2: "a".into(),
+                unk1: 0,
+                unk2: 1,
+                aim: Vector3::new(1.0, 0.0, 0.0),
+                up: Vector3::new(0.0, 1.0, 0.0),
+                quat1: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                quat2: Vector4::new(0.0, 0.0, 0.0, 1.0),
+            }],
+            orient_constraints: Vec::new(),
+        };
+        assert_eq!(
+            vec!["aim1".to_string()],
+            constraint_cycles(&ValidatedSkel::new(&skel), &hlpb)
+        );
+    }
+
+    fn transform_anim(nodes: &[(&str, Transform)]) -> AnimData {
+        AnimData {
+            major_version: 2,
+            minor_version: 0,
+            final_frame_index: 0.0,
+            groups: vec![GroupData {
+                group_type: GroupType::Transform,
+                nodes: nodes
+                    .iter()
+                    .map(|(name, transform)| NodeData {
+                        name: name.to_string(),
+                        tracks: vec![TrackData {
+                            name: "Transform".to_string(),
+                            compensate_scale: false,
+                            values: TrackValues::Transform(vec![transform.clone()]),
+                            transform_flags: TransformFlags::default(),
+                        }],
+                    })
+                    .collect(),
+            }],
+        }
+    }
+
+    fn translation_rotation_z(translation: Vector3, angle: f32) -> Transform {
+        let rotation = glam::Quat::from_rotation_z(angle);
+        Transform {
+            scale: Vector3::new(1.0, 1.0, 1.0),
+            rotation: Vector4::new(rotation.x, rotation.y, rotation.z, rotation.w),
+            translation,
+        }
+    }
+
+    #[test]
+    fn blend_single_layer_from_rest_pose() {
+        let mut transforms = AnimationTransforms::identity();
+        let anim = transform_anim(&[(
+            "A",
+            translation_rotation_z(Vector3::new(4.0, 0.0, 0.0), 90f32.to_radians()),
+        )]);
+        animate_skel_layers(
+            &mut transforms,
+            &ValidatedSkel::new(&SkelData {
+                major_version: 1,
+                minor_version: 0,
+                bones: vec![identity_bone("A", None)],
+            }),
+            [AnimationLayer {
+                anim: &anim,
+                frame: 0.0,
+                weight: 0.5,
+            }]
+            .into_iter(),
+            None,
+        );
+
+        // Rotations should use slerp to avoid distorting the matrix.
+        let expected = glam::Mat4::from_translation(glam::vec3(2.0, 0.0, 0.0))
+            * glam::Mat4::from_rotation_z(45f32.to_radians());
+        assert_matrix_relative_eq!(
+            expected.to_cols_array_2d(),
+            transforms.world_transforms[0].to_cols_array_2d()
+        );
+    }
+
+    #[test]
+    fn pose_override_absolute() {
+        let mut transforms = AnimationTransforms::identity();
+        let anim = transform_anim(&[(
+            "A",
+            translation_rotation_z(Vector3::new(4.0, 0.0, 0.0), 90f32.to_radians()),
+        )]);
+        animate_skel_with_overrides(
+            &mut transforms,
+            &ValidatedSkel::new(&SkelData {
+                major_version: 1,
+                minor_version: 0,
+                bones: vec![identity_bone("A", None)],
+            }),
+            std::iter::once(AnimationLayer::new(&anim, 0.0)),
+            &HashMap::from([(
+                "A".to_string(),
+                PoseOverride::Absolute {
+                    translation: glam::vec3(0.0, 1.0, 0.0),
+                    rotation: glam::Quat::IDENTITY,
+                    scale: glam::Vec3::ONE,
+                },
+            )]),
+            None,
+        );
+
+        // The animation is replaced.
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_translation(glam::vec3(0.0, 1.0, 0.0)).to_cols_array_2d(),
+            transforms.world_transforms[0].to_cols_array_2d()
+        );
+    }
+
+    #[test]
+    fn pose_override_additive_parent() {
+        let mut transforms = AnimationTransforms::identity();
+        let anim = transform_anim(&[(
+            "B",
+            translation_rotation_z(Vector3::new(1.0, 0.0, 0.0), 0.0),
+        )]);
+        animate_skel_with_overrides(
+            &mut transforms,
+            &ValidatedSkel::new(&SkelData {
+                major_version: 1,
+                minor_version: 0,
+                bones: vec![identity_bone("A", None), identity_bone("B", Some(0))],
+            }),
+            std::iter::once(AnimationLayer::new(&anim, 0.0)),
+            &HashMap::from([(
+                "A".to_string(),
+                PoseOverride::Additive {
+                    translation: glam::vec3(0.0, 0.0, 2.0),
+                    rotation: glam::Quat::from_rotation_z(90f32.to_radians()),
+                    scale: glam::Vec3::ONE,
+                },
+            )]),
+            None,
+        );
+
+        // The unanimated parent is offset from the rest pose.
+        let expected = glam::Mat4::from_translation(glam::vec3(0.0, 0.0, 2.0))
+            * glam::Mat4::from_rotation_z(90f32.to_radians());
+        assert_matrix_relative_eq!(
+            expected.to_cols_array_2d(),
+            transforms.world_transforms[0].to_cols_array_2d()
+        );
+
+        // The child moves with the parent.
+        assert_vector_relative_eq!(
+            [0.0, 1.0, 2.0],
+            transforms.world_transforms[1].col(3).truncate().to_array()
+        );
+    }
+
+    #[test]
+    fn pose_override_before_constraints() {
+        let mut transforms = AnimationTransforms::identity();
+        let rotation = glam::Quat::from_rotation_z(45f32.to_radians());
+        animate_skel_with_overrides(
+            &mut transforms,
+            &ValidatedSkel::new(&SkelData {
+                major_version: 1,
+                minor_version: 0,
+                bones: vec![identity_bone("A", None), identity_bone("B", None)],
+            }),
+            std::iter::empty(),
+            &HashMap::from([(
+                "A".to_string(),
+                PoseOverride::Absolute {
+                    translation: glam::Vec3::ZERO,
+                    rotation,
+                    scale: glam::Vec3::ONE,
+                },
+            )]),
+            Some(&HlpbData {
+                major_version: 1,
+                minor_version: 0,
+                aim_constraints: Vec::new(),
+                orient_constraints: vec![OrientConstraintData {
+                    name: "constraint1".to_string(),
+                    parent_bone_name1: "A".to_string(),
+                    parent_bone_name2: "A".to_string(),
+                    source_bone_name: "A".to_string(),
+                    target_bone_name: "B".to_string(),
+                    unk_type: 2,
+                    constraint_axes: Vector3::new(1.0, 1.0, 1.0),
+                    quat1: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                    quat2: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                    range_min: Vector3::new(-180.0, -180.0, -180.0),
+                    range_max: Vector3::new(180.0, 180.0, 180.0),
+                }],
+            }),
+        );
+
+        // The constrained bone uses the overridden rotation.
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_quat(rotation).to_cols_array_2d(),
+            transforms.world_transforms[1].to_cols_array_2d()
+        );
+    }
+
+    #[test]
+    fn blend_layers_weights() {
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![identity_bone("A", None), identity_bone("B", None)],
+        };
+        let body = transform_anim(&[
+            (
+                "A",
+                translation_rotation_z(Vector3::new(2.0, 0.0, 0.0), 0.0),
+            ),
+            (
+                "B",
+                translation_rotation_z(Vector3::new(0.0, 2.0, 0.0), 0.0),
+            ),
+        ]);
+        // The partial layer only affects bone A.
+        let partial = transform_anim(&[(
+            "A",
+            translation_rotation_z(Vector3::new(6.0, 0.0, 0.0), 0.0),
+        )]);
+        let ignored = transform_anim(&[(
+            "B",
+            translation_rotation_z(Vector3::new(0.0, 8.0, 0.0), 0.0),
+        )]);
+
+        let mut transforms = AnimationTransforms::identity();
+        animate_skel_layers(
+            &mut transforms,
+            &ValidatedSkel::new(&skel),
+            [
+                AnimationLayer::new(&body, 0.0),
+                AnimationLayer {
+                    anim: &partial,
+                    frame: 0.0,
+                    weight: 0.25,
+                },
+                AnimationLayer {
+                    anim: &ignored,
+                    frame: 0.0,
+                    weight: 0.0,
+                },
+            ]
+            .into_iter(),
+            None,
+        );
+
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_translation(glam::vec3(3.0, 0.0, 0.0)).to_cols_array_2d(),
+            transforms.world_transforms[0].to_cols_array_2d()
+        );
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_translation(glam::vec3(0.0, 2.0, 0.0)).to_cols_array_2d(),
+            transforms.world_transforms[1].to_cols_array_2d()
+        );
+    }
+
+    #[test]
+    fn apply_animation_invalid_parents() {
+        // The cycle is broken by making B a root bone.
+        // C's parent is out of range, so C is also a root bone.
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![
+                identity_bone("A", Some(1)),
+                identity_bone("B", Some(0)),
+                identity_bone("C", Some(7)),
+            ],
+        };
+        let anim = transform_anim(&[
+            (
+                "A",
+                translation_rotation_z(Vector3::new(1.0, 0.0, 0.0), 0.0),
+            ),
+            (
+                "B",
+                translation_rotation_z(Vector3::new(0.0, 2.0, 0.0), 0.0),
+            ),
+            (
+                "C",
+                translation_rotation_z(Vector3::new(0.0, 0.0, 3.0), 0.0),
+            ),
+        ]);
+
+        let mut transforms = AnimationTransforms::identity();
+        animate_skel(
+            &mut transforms,
+            &ValidatedSkel::new(&skel),
+            std::iter::once(&anim),
+            None,
+            0.0,
+        );
+
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_translation(glam::vec3(1.0, 2.0, 0.0)).to_cols_array_2d(),
+            transforms.world_transforms[0].to_cols_array_2d()
+        );
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_translation(glam::vec3(0.0, 2.0, 0.0)).to_cols_array_2d(),
+            transforms.world_transforms[1].to_cols_array_2d()
+        );
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_translation(glam::vec3(0.0, 0.0, 3.0)).to_cols_array_2d(),
+            transforms.world_transforms[2].to_cols_array_2d()
+        );
+    }
+
+    #[test]
+    fn cross_fade_weights() {
+        let from = transform_anim(&[]);
+        let to = transform_anim(&[]);
+        let weights = |elapsed, duration| {
+            AnimationLayer::cross_fade(
+                AnimationLayer::new(&from, 5.0),
+                AnimationLayer::new(&to, elapsed),
+                elapsed,
+                duration,
+            )
+            .map(|l| l.weight)
+        };
+
+        assert_eq!([1.0, 0.0], weights(0.0, 8.0));
+        assert_eq!([1.0, 0.25], weights(2.0, 8.0));
+        assert_eq!([1.0, 1.0], weights(10.0, 8.0));
+        assert_eq!([1.0, 1.0], weights(0.0, 0.0));
+    }
+
+    fn material(label: &str) -> MatlEntryData {
+        MatlEntryData {
+            material_label: label.to_string(),
+            shader_label: String::new(),
+            blend_states: Vec::new(),
+            floats: vec![FloatParam {
+                param_id: ParamId::CustomFloat8,
+                data: 0.0,
+            }],
+            booleans: Vec::new(),
+            vectors: Vec::new(),
+            rasterizer_states: Vec::new(),
+            samplers: Vec::new(),
+            textures: vec![TextureParam {
+                param_id: ParamId::Texture0,
+                data: "eye_001_col".to_string(),
+            }],
+        }
+    }
+
+    fn material_anim(label: &str, tracks: Vec<TrackData>) -> AnimData {
+        AnimData {
+            major_version: 2,
+            minor_version: 0,
+            final_frame_index: 1.0,
+            groups: vec![GroupData {
+                group_type: GroupType::Material,
+                nodes: vec![NodeData {
+                    name: label.to_string(),
+                    tracks,
+                }],
+            }],
+        }
+    }
+
+    fn track(name: &str, values: TrackValues) -> TrackData {
+        TrackData {
+            name: name.to_string(),
+            compensate_scale: false,
+            values,
+            transform_flags: TransformFlags::default(),
+        }
+    }
+
+    #[test]
+    fn animate_materials_float_pattern_index() {
+        let anim = material_anim(
+            "a",
+            vec![
+                track("CustomFloat8", TrackValues::Float(vec![1.0, 3.0])),
+                track("Texture0", TrackValues::PatternIndex(vec![1, 2])),
+            ],
+        );
+
+        let materials = animate_materials(
+            &anim,
+            0.5,
+            &[material("a"), material("b")],
+            &["eye_001_col.nutexb", "eye_002_col.nutexb"],
+        );
+
+        assert_eq!(2.0, materials[0].material.floats[0].data);
+        // Pattern indices use the previous frame instead of interpolating.
+        assert_eq!("eye_001_col", materials[0].material.textures[0].data);
+        assert_eq!(material("b"), materials[1].material);
+
+        let materials = animate_materials(&anim, 1.0, &[material("a")], &["eye_002_col.nutexb"]);
+        assert_eq!("eye_002_col", materials[0].material.textures[0].data);
+    }
+
+    #[test]
+    fn animate_materials_uv_transform() {
+        let uv_transform = |value| UvTransform {
+            scale_u: value,
+            scale_v: value,
+            rotation: value,
+            translate_u: value,
+            translate_v: value,
+        };
+        let anim = material_anim(
+            "a",
+            vec![track(
+                "Texture0",
+                TrackValues::UvTransform(vec![uv_transform(0.0), uv_transform(1.0)]),
+            )],
+        );
+
+        let materials = animate_materials(&anim, 0.5, &[material("a")], &[]);
+        assert_eq!(1, materials[0].uv_transforms.len());
+
+        let (param, value) = &materials[0].uv_transforms[0];
+        assert_eq!(ParamId::Texture0, *param);
+        assert_eq!(0.5, value.scale_u);
+        assert_eq!(0.5, value.scale_v);
+        assert_eq!(0.5, value.rotation);
+        assert_eq!(0.5, value.translate_u);
+        assert_eq!(0.5, value.translate_v);
+    }
+
+    #[test]
+    fn animate_materials_unsupported_tracks() {
+        // Unexpected track types and empty tracks should be ignored instead of panicking.
+        let anim = material_anim(
+            "a",
+            vec![
+                track(
+                    "Transform",
+                    TrackValues::Transform(vec![Transform {
+                        scale: Vector3::new(1.0, 1.0, 1.0),
+                        rotation: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                        translation: Vector3::new(0.0, 0.0, 0.0),
+                    }]),
                 ),
-            ])
+                track("CustomFloat8", TrackValues::Float(Vec::new())),
+                track("Texture0", TrackValues::PatternIndex(Vec::new())),
+                track("Invalid", TrackValues::UvTransform(Vec::new())),
+            ],
+        );
+
+        let materials = animate_materials(&anim, 0.0, &[material("a")], &[]);
+        assert_eq!(material("a"), materials[0].material);
+        assert!(materials[0].uv_transforms.is_empty());
+    }
+
+    #[test]
+    fn animate_materials_pattern_index_missing_texture() {
+        let anim = material_anim(
+            "a",
+            vec![track("Texture0", TrackValues::PatternIndex(vec![2]))],
+        );
+
+        // The texture shouldn't change if the model has no texture for the pattern index.
+        let materials = animate_materials(&anim, 0.0, &[material("a")], &["eye_001_col.nutexb"]);
+        assert_eq!("eye_001_col", materials[0].material.textures[0].data);
+    }
+
+    #[test]
+    fn apply_material_animation_multiple_anims() {
+        let float_anim = material_anim(
+            "a",
+            vec![track("CustomFloat8", TrackValues::Float(vec![0.5]))],
+        );
+        let pattern_anim = material_anim(
+            "a",
+            vec![track("Texture0", TrackValues::PatternIndex(vec![3]))],
+        );
+
+        let mut materials = vec![AnimatedMaterial::new(&material("a"))];
+        apply_material_animation(&float_anim, 0.0, &mut materials, &[]);
+        apply_material_animation(&pattern_anim, 0.0, &mut materials, &["EYE_003_COL.nutexb"]);
+
+        assert_eq!(0.5, materials[0].material.floats[0].data);
+        assert_eq!("eye_003_col", materials[0].material.textures[0].data);
+    }
+
+    #[test]
+    fn pattern_texture_names() {
+        assert_eq!(
+            Some("eye_002_col".to_string()),
+            pattern_texture_name("eye_001_col", 2)
+        );
+        assert_eq!(
+            Some("mouth_12".to_string()),
+            pattern_texture_name("mouth_3", 12)
+        );
+        assert_eq!(Some("7".to_string()), pattern_texture_name("0", 7));
+        assert_eq!(None, pattern_texture_name("eye_col", 2));
+    }
+
+    #[test]
+    fn bone_transforms_parent_child() {
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![identity_bone("A", None), identity_bone("B", Some(0))],
+        };
+        let anim = AnimData {
+            major_version: 2,
+            minor_version: 0,
+            final_frame_index: 0.0,
+            groups: vec![GroupData {
+                group_type: GroupType::Transform,
+                nodes: vec![
+                    NodeData {
+                        name: "A".to_string(),
+                        tracks: vec![TrackData {
+                            name: "Transform".to_string(),
+                            compensate_scale: false,
+                            transform_flags: TransformFlags::default(),
+                            values: TrackValues::Transform(vec![Transform {
+                                scale: Vector3::new(1.0, 1.0, 1.0),
+                                rotation: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                                translation: Vector3::new(1.0, 2.0, 3.0),
+                            }]),
+                        }],
+                    },
+                    NodeData {
+                        name: "B".to_string(),
+                        tracks: vec![TrackData {
+                            name: "Transform".to_string(),
+                            compensate_scale: false,
+                            transform_flags: TransformFlags::default(),
+                            values: TrackValues::Transform(vec![Transform {
+                                scale: Vector3::new(1.0, 1.0, 1.0),
+                                rotation: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                                translation: Vector3::new(0.0, 1.0, 0.0),
+                            }]),
+                        }],
+                    },
+                ],
+            }],
+        };
+
+        let validated_skel = ValidatedSkel::new(&skel);
+        let mut transforms = AnimationTransforms::from_skel(&validated_skel);
+        animate_skel(
+            &mut transforms,
+            &validated_skel,
+            std::iter::once(&anim),
+            None,
+            0.0,
+        );
+
+        let bone = transforms.bone_transforms_by_name(&skel, "B").unwrap();
+        assert_eq!(transforms.bone_transforms(&skel, 1), Some(bone));
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_translation(glam::vec3(1.0, 3.0, 3.0)).to_cols_array_2d(),
+            bone.world.to_cols_array_2d()
+        );
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_translation(glam::vec3(0.0, 1.0, 0.0)).to_cols_array_2d(),
+            bone.local.to_cols_array_2d()
+        );
+        // The rest pose is the identity for both bones.
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_translation(glam::vec3(1.0, 3.0, 3.0)).to_cols_array_2d(),
+            bone.skinning.to_cols_array_2d()
+        );
+    }
+
+    #[test]
+    fn bone_transforms_zero_scale_parent() {
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![identity_bone("A", None), identity_bone("B", Some(0))],
+        };
+        let anim = AnimData {
+            major_version: 2,
+            minor_version: 0,
+            final_frame_index: 0.0,
+            groups: vec![GroupData {
+                group_type: GroupType::Transform,
+                nodes: vec![NodeData {
+                    name: "A".to_string(),
+                    tracks: vec![TrackData {
+                        name: "Transform".to_string(),
+                        compensate_scale: false,
+                        transform_flags: TransformFlags::default(),
+                        values: TrackValues::Transform(vec![Transform {
+                            scale: Vector3::new(0.0, 0.0, 0.0),
+                            rotation: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                            translation: Vector3::new(1.0, 2.0, 3.0),
+                        }]),
+                    }],
+                }],
+            }],
+        };
+
+        let validated_skel = ValidatedSkel::new(&skel);
+        let mut transforms = AnimationTransforms::from_skel(&validated_skel);
+        animate_skel(
+            &mut transforms,
+            &validated_skel,
+            std::iter::once(&anim),
+            None,
+            0.0,
+        );
+
+        // The parent can't be inverted, so use the skel transform.
+        let bone = transforms.bone_transforms_by_name(&skel, "B").unwrap();
+        assert_matrix_relative_eq!(
+            glam::Mat4::IDENTITY.to_cols_array_2d(),
+            bone.local.to_cols_array_2d()
+        );
+    }
+
+    #[test]
+    fn bone_transforms_missing_bone() {
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![identity_bone("A", None)],
+        };
+        let transforms = AnimationTransforms::from_skel(&ValidatedSkel::new(&skel));
+
+        assert_eq!(None, transforms.bone_transforms_by_name(&skel, "B"));
+        assert_eq!(None, transforms.bone_transforms(&skel, 1));
+        assert_eq!(
+            None,
+            AnimationTransforms::identity().bone_transforms(&skel, 0)
         );
     }
 }
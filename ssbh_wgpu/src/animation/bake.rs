@@ -0,0 +1,351 @@
+use ssbh_data::{
+    anim_data::{
+        GroupData, GroupType, NodeData, TrackData, TrackValues, Transform, TransformFlags,
+    },
+    prelude::*,
+    skel_data::BoneData,
+};
+
+use super::{animate_skel, sampled_local_transforms, AnimationTransforms, ValidatedSkel};
+
+/// Evaluates `anims` and the constraints in `hlpb` for every frame and stores
+/// the transform of each bone relative to its parent in a new [AnimData].
+///
+/// The result has a transform track for every bone in `skel`
+/// and plays back the same without the hlpb.
+/// Material and visibility tracks are not included.
+pub fn bake_anim(skel: &ValidatedSkel, anims: &[AnimData], hlpb: Option<&HlpbData>) -> AnimData {
+    let final_frame_index = anims
+        .iter()
+        .map(|a| a.final_frame_index)
+        .reduce(f32::max)
+        .unwrap_or(0.0)
+        .max(0.0)
+        .floor();
+    let frame_count = final_frame_index as usize + 1;
+
+    let mut bone_values = vec![Vec::with_capacity(frame_count); skel.bones().len()];
+
+    let mut transforms = AnimationTransforms::from_skel(skel);
+    for frame in 0..frame_count {
+        animate_skel(&mut transforms, skel, anims.iter(), hlpb, frame as f32);
+
+        // Only sample the anims again if a bone can't be computed from its parent.
+        let mut sampled_transforms = None;
+        for (i, (bone, values)) in skel.bones().iter().zip(&mut bone_values).enumerate() {
+            let transform = local_transform(bone, i, &transforms.world_transforms, values.last())
+                .unwrap_or_else(|| {
+                    let sampled = sampled_transforms.get_or_insert_with(|| {
+                        sampled_local_transforms(skel, anims.iter(), frame as f32)
+                    });
+                    baked_transform(sampled[i], values.last())
+                });
+            values.push(transform);
+        }
+    }
+
+    AnimData {
+        major_version: 2,
+        minor_version: 0,
+        final_frame_index,
+        groups: vec![GroupData {
+            group_type: GroupType::Transform,
+            nodes: skel
+                .bones()
+                .iter()
+                .zip(bone_values)
+                .map(|(bone, values)| NodeData {
+                    name: bone.name.clone(),
+                    tracks: vec![TrackData {
+                        name: "Transform".to_string(),
+                        compensate_scale: false,
+                        transform_flags: TransformFlags::default(),
+                        values: TrackValues::Transform(values),
+                    }],
+                })
+                .collect(),
+        }],
+    }
+}
+
+fn local_transform(
+    bone: &BoneData,
+    index: usize,
+    world_transforms: &[glam::Mat4],
+    previous: Option<&Transform>,
+) -> Option<Transform> {
+    // The world transforms already include any scale compensation and constraints.
+    let parent_world = bone
+        .parent_index
+        .map(|p| world_transforms[p])
+        .unwrap_or(glam::Mat4::IDENTITY);
+
+    // Parents with zero scale can't be inverted and would produce NaN.
+    if parent_world.determinant() == 0.0 {
+        return None;
+    }
+
+    // Decomposing a transform with zero scale also produces a NaN rotation.
+    let transform = parent_world.inverse() * world_transforms[index];
+    if transform.determinant() == 0.0 {
+        return None;
+    }
+
+    Some(baked_transform(
+        transform.to_scale_rotation_translation(),
+        previous,
+    ))
+}
+
+fn baked_transform(
+    (scale, mut rotation, translation): (glam::Vec3, glam::Quat, glam::Vec3),
+    previous: Option<&Transform>,
+) -> Transform {
+    // Rotations are interpolated linearly between frames.
+    // Avoid sign flips between frames since q and -q are the same rotation.
+    if let Some(previous) = previous {
+        if glam::Quat::from_array(previous.rotation.to_array()).dot(rotation) < 0.0 {
+            rotation = -rotation;
+        }
+    }
+
+    Transform {
+        scale: scale.to_array().into(),
+        rotation: rotation.to_array().into(),
+        translation: translation.to_array().into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assert_matrix_relative_eq;
+    use ssbh_data::{hlpb_data::OrientConstraintData, skel_data::BillboardType, Vector3, Vector4};
+
+    fn identity_bone(name: &str, parent_index: Option<usize>) -> BoneData {
+        BoneData {
+            name: name.to_string(),
+            transform: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+            parent_index,
+            billboard_type: BillboardType::Disabled,
+        }
+    }
+
+    fn rotation_z_anim(name: &str, angles: &[f32]) -> AnimData {
+        AnimData {
+            major_version: 2,
+            minor_version: 0,
+            final_frame_index: angles.len().saturating_sub(1) as f32,
+            groups: vec![GroupData {
+                group_type: GroupType::Transform,
+                nodes: vec![NodeData {
+                    name: name.to_string(),
+                    tracks: vec![TrackData {
+                        name: "Transform".to_string(),
+                        compensate_scale: false,
+                        transform_flags: TransformFlags::default(),
+                        values: TrackValues::Transform(
+                            angles
+                                .iter()
+                                .map(|angle| Transform {
+                                    scale: Vector3::new(1.0, 1.0, 1.0),
+                                    rotation: glam::Quat::from_rotation_z(angle.to_radians())
+                                        .to_array()
+                                        .into(),
+                                    translation: Vector3::new(1.0, 0.0, 0.0),
+                                })
+                                .collect(),
+                        ),
+                    }],
+                }],
+            }],
+        }
+    }
+
+    fn copy_rotation_hlpb(source: &str, target: &str) -> HlpbData {
+        HlpbData {
+            major_version: 1,
+            minor_version: 0,
+            aim_constraints: Vec::new(),
+            orient_constraints: vec![OrientConstraintData {
+                name: "constraint1".to_string(),
+                parent_bone_name1: source.to_string(),
+                parent_bone_name2: source.to_string(),
+                source_bone_name: source.to_string(),
+                target_bone_name: target.to_string(),
+                unk_type: 2,
+                constraint_axes: Vector3::new(1.0, 1.0, 1.0),
+                quat1: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                quat2: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                range_min: Vector3::new(-180.0, -180.0, -180.0),
+                range_max: Vector3::new(180.0, 180.0, 180.0),
+            }],
+        }
+    }
+
+    fn transform_values(anim: &AnimData, node: usize) -> &[Transform] {
+        match &anim.groups[0].nodes[node].tracks[0].values {
+            TrackValues::Transform(values) => values,
+            _ => panic!("Expected transform values"),
+        }
+    }
+
+    #[test]
+    fn bake_no_anims() {
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![identity_bone("A", None), identity_bone("B", Some(0))],
+        };
+        let skel = ValidatedSkel::new(&skel);
+
+        let anim = bake_anim(&skel, &[], None);
+        assert_eq!(0.0, anim.final_frame_index);
+        assert_eq!(2, anim.groups[0].nodes.len());
+        assert_eq!("A", anim.groups[0].nodes[0].name);
+        assert_eq!("B", anim.groups[0].nodes[1].name);
+
+        // Unanimated bones use the rest pose.
+        assert_eq!(
+            vec![Transform {
+                scale: Vector3::new(1.0, 1.0, 1.0),
+                rotation: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                translation: Vector3::new(0.0, 0.0, 0.0),
+            }],
+            transform_values(&anim, 1)
+        );
+    }
+
+    #[test]
+    fn bake_orient_constraint() {
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![identity_bone("A", None), identity_bone("B", None)],
+        };
+        let skel = ValidatedSkel::new(&skel);
+        let anims = [rotation_z_anim("A", &[0.0, 45.0, 90.0])];
+        let hlpb = copy_rotation_hlpb("A", "B");
+
+        let anim = bake_anim(&skel, &anims, Some(&hlpb));
+        assert_eq!(2.0, anim.final_frame_index);
+
+        // The constrained rotation is baked into the track for B.
+        let values = transform_values(&anim, 1);
+        assert_eq!(3, values.len());
+        for (value, angle) in values.iter().zip([0.0f32, 45.0, 90.0]) {
+            let rotation = glam::Quat::from_array(value.rotation.to_array());
+            assert!(
+                rotation.angle_between(glam::Quat::from_rotation_z(angle.to_radians())) < 0.001
+            );
+        }
+    }
+
+    #[test]
+    fn bake_matches_original_world_transforms() {
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![
+                identity_bone("A", None),
+                identity_bone("B", None),
+                identity_bone("C", Some(1)),
+            ],
+        };
+        let skel = ValidatedSkel::new(&skel);
+        let anims = [rotation_z_anim("A", &[0.0, 30.0, 60.0, 90.0])];
+        let hlpb = copy_rotation_hlpb("A", "B");
+
+        let baked = bake_anim(&skel, &anims, Some(&hlpb));
+
+        // Playing back the baked anim without constraints gives the same result.
+        let mut expected = AnimationTransforms::from_skel(&skel);
+        let mut actual = AnimationTransforms::from_skel(&skel);
+        for frame in [0.0, 1.0, 2.0, 3.0] {
+            animate_skel(&mut expected, &skel, anims.iter(), Some(&hlpb), frame);
+            animate_skel(&mut actual, &skel, std::iter::once(&baked), None, frame);
+            for (expected, actual) in expected
+                .world_transforms
+                .iter()
+                .zip(&actual.world_transforms)
+            {
+                assert_matrix_relative_eq!(expected.to_cols_array_2d(), actual.to_cols_array_2d());
+            }
+        }
+    }
+
+    #[test]
+    fn bake_continuous_rotations() {
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![identity_bone("A", None)],
+        };
+        let skel = ValidatedSkel::new(&skel);
+        let anims = [rotation_z_anim("A", &[0.0, 120.0, 240.0, 360.0])];
+
+        // Adjacent quaternions shouldn't change sign.
+        let anim = bake_anim(&skel, &anims, None);
+        for window in transform_values(&anim, 0).windows(2) {
+            let a = glam::Quat::from_array(window[0].rotation.to_array());
+            let b = glam::Quat::from_array(window[1].rotation.to_array());
+            assert!(a.dot(b) >= 0.0);
+        }
+    }
+
+    #[test]
+    fn bake_zero_scale_parent() {
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![identity_bone("A", None), identity_bone("B", Some(0))],
+        };
+        let skel = ValidatedSkel::new(&skel);
+        let anims = [AnimData {
+            major_version: 2,
+            minor_version: 0,
+            final_frame_index: 0.0,
+            groups: vec![GroupData {
+                group_type: GroupType::Transform,
+                nodes: vec![NodeData {
+                    name: "A".to_string(),
+                    tracks: vec![TrackData {
+                        name: "Transform".to_string(),
+                        compensate_scale: false,
+                        transform_flags: TransformFlags::default(),
+                        values: TrackValues::Transform(vec![Transform {
+                            scale: Vector3::new(0.0, 0.0, 0.0),
+                            rotation: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                            translation: Vector3::new(1.0, 0.0, 0.0),
+                        }]),
+                    }],
+                }],
+            }],
+        }];
+
+        // The parent can't be inverted, so both bones use their sampled transforms.
+        let anim = bake_anim(&skel, &anims, None);
+        assert_eq!(
+            vec![Transform {
+                scale: Vector3::new(0.0, 0.0, 0.0),
+                rotation: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                translation: Vector3::new(1.0, 0.0, 0.0),
+            }],
+            transform_values(&anim, 0)
+        );
+        assert_eq!(
+            vec![Transform {
+                scale: Vector3::new(1.0, 1.0, 1.0),
+                rotation: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                translation: Vector3::new(0.0, 0.0, 0.0),
+            }],
+            transform_values(&anim, 1)
+        );
+    }
+}
@@ -25,7 +25,7 @@ pub fn animate_camera(
     let transform_track = transform_node.tracks.first()?;
 
     let transform = match &transform_track.values {
-        TrackValues::Transform(values) => Some(AnimTransform::from(frame_value(values, frame))),
+        TrackValues::Transform(values) => frame_value(values, frame).map(AnimTransform::from),
         _ => None,
     }?;
 
@@ -44,7 +44,7 @@ pub fn animate_camera(
     let near_clip = camera_node
         .and_then(|node| node.tracks.iter().find(|t| t.name == "NearClip"))
         .and_then(|track| match &track.values {
-            TrackValues::Float(values) => Some(frame_value(values, frame)),
+            TrackValues::Float(values) => frame_value(values, frame),
             _ => None,
         })
         .unwrap_or(default_near_clip);
@@ -52,7 +52,7 @@ pub fn animate_camera(
     let far_clip = camera_node
         .and_then(|node| node.tracks.iter().find(|t| t.name == "FarClip"))
         .and_then(|track| match &track.values {
-            TrackValues::Float(values) => Some(frame_value(values, frame)),
+            TrackValues::Float(values) => frame_value(values, frame),
             _ => None,
         })
         .unwrap_or(default_far_clip);
@@ -60,7 +60,7 @@ pub fn animate_camera(
     let fov = camera_node
         .and_then(|node| node.tracks.iter().find(|t| t.name == "FieldOfView"))
         .and_then(|track| match &track.values {
-            TrackValues::Float(values) => Some(frame_value(values, frame)),
+            TrackValues::Float(values) => frame_value(values, frame),
             _ => None,
         })
         .unwrap_or(default_fov);
@@ -5,56 +5,144 @@ fn interp(a: f32, b: f32, f: f32) -> f32 {
     (1.0 - f) * a + f * b
 }
 
-// TODO: Improve tests.
-// TODO: Fix evaluation order when siblings are constrained to each other?
+/// A constraint where the world transform of `target` depends on the world transform of `source`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ConstraintDependency<'a> {
+    pub name: &'a str,
+    pub source: usize,
+    pub target: usize,
+}
+
+/// Finds the bone indices for each constraint in `hlpb` with a source and target in `bones`.
+/// Constraints that depend on multiple bones have a dependency for each source bone.
+pub fn constraint_dependencies<'a>(
+    bones: &[BoneData],
+    hlpb: &'a HlpbData,
+) -> Vec<ConstraintDependency<'a>> {
+    let position = |name: &str| bones.iter().position(|b| b.name == name);
+
+    let orient_dependencies = hlpb.orient_constraints.iter().filter_map(|o| {
+        Some(ConstraintDependency {
+            name: &o.name,
+            source: position(&o.source_bone_name)?,
+            target: position(&o.target_bone_name)?,
+        })
+    });
+    let aim_dependencies = hlpb.aim_constraints.iter().flat_map(|a| {
+        let target = position(&a.target_bone_name1);
+        [&a.aim_bone_name1, &a.aim_bone_name2, &a.target_bone_name2]
+            .into_iter()
+            .filter_map(|source| position(source))
+            .filter_map(move |source| {
+                // The second target name is usually the constrained bone itself.
+                let target = target?;
+                (source != target).then_some(ConstraintDependency {
+                    name: &a.name,
+                    source,
+                    target,
+                })
+            })
+    });
+
+    let mut dependencies: Vec<_> = orient_dependencies.chain(aim_dependencies).collect();
+    dependencies.dedup();
+    dependencies
+}
+
+/// Rotates `target_transform` so the aim axis points at the aim bone and the up axis
+/// points as closely as possible along the up axis of the up bone.
+///
+/// The bone names are assumed to be used as follows:
+/// - `aim_bone_name1` is the bone to point towards.
+/// - `aim_bone_name2` is the up bone and defaults to the parent of the constrained bone.
+/// - `target_bone_name1` is the constrained bone.
+/// - `target_bone_name2` is the start of the aim direction and defaults to the constrained bone.
+///
+/// `quat1` is an additional rotation for the constrained bone,
+/// and `quat2` is an additional rotation for the up bone.
+// TODO: Verify the mapping for aim_bone_name2 and target_bone_name2 against in game helper bones.
+// Constraints that repeat aim_bone_name1 for aim_bone_name2 twist with the aim bone.
 pub fn apply_aim_constraint(
     world_transforms: &[glam::Mat4],
     bones: &[BoneData],
     constraint: &AimConstraintData,
     target_transform: glam::Mat4,
 ) -> Option<glam::Mat4> {
-    // TODO: Investigate the remaining bone name fields.
-    let source = bones
-        .iter()
-        .position(|b| b.name == constraint.aim_bone_name1)?;
+    // TODO: Investigate the aim types and unk values.
+    let position = |name: &str| bones.iter().position(|b| b.name == name);
+    let world_transform = |i: usize| {
+        world_transforms
+            .get(i)
+            .copied()
+            .unwrap_or(glam::Mat4::IDENTITY)
+    };
 
-    // We want the target bone to point at the source bone.
-    let source_world = *world_transforms
-        .get(source)
-        .unwrap_or(&glam::Mat4::IDENTITY);
+    let source = position(&constraint.aim_bone_name1)?;
+    let target = position(&constraint.target_bone_name1)?;
 
-    let target = bones
-        .iter()
-        .position(|b| b.name == constraint.target_bone_name1)?;
+    // The target's world transform hasn't been updated yet for this frame.
+    // Calculate it from the parent instead since parents are always evaluated first.
+    let target_parent_world = bones[target]
+        .parent_index
+        .map(world_transform)
+        .unwrap_or(glam::Mat4::IDENTITY);
+    let target_world = target_parent_world * target_transform;
 
-    let target_world = *world_transforms
-        .get(target)
-        .unwrap_or(&glam::Mat4::IDENTITY);
+    let aim_start = match position(&constraint.target_bone_name2) {
+        Some(i) if i != target => world_transform(i).col(3).xyz(),
+        _ => target_world.col(3).xyz(),
+    };
+    let aim_world = (world_transform(source).col(3).xyz() - aim_start).try_normalize()?;
 
-    // TODO: Can a bone not affected by the anim be the source?
-    // TODO: Will the target of a constraint ever be animated?
-    let src_pos = source_world.col(3);
-    let target_pos = target_world.col(3);
+    // Without an up bone, the up axis stays relative to the target's parent.
+    let (_, up_rotation, _) = match position(&constraint.aim_bone_name2) {
+        Some(i) => world_transform(i),
+        None => target_parent_world,
+    }
+    .to_scale_rotation_translation();
+    let quat2 = glam::Quat::from_array(constraint.quat2.to_array());
+    let up_world = up_rotation * quat2 * glam::Vec3::from(constraint.up.to_array());
 
-    // Get the local axes of the bone to constrain.
-    let aim = (target_world
-        * glam::vec4(constraint.aim.x, constraint.aim.y, constraint.aim.z, 0.0))
-    .xyz();
+    let (target_s, _, target_t) = target_transform.to_scale_rotation_translation();
+    let (_, target_world_r, _) = target_world.to_scale_rotation_translation();
+    let (_, parent_r, _) = target_parent_world.to_scale_rotation_translation();
 
-    // Get the vector pointing to the desired bone.
-    let v = src_pos.xyz() - target_pos.xyz();
+    let aim = glam::Vec3::from(constraint.aim.to_array()).try_normalize()?;
+    let up = glam::Vec3::from(constraint.up.to_array());
 
-    // TODO: Is it correct to assume the target transform is always relative to the target parent?
-    // Apply an additional rotation to orient the local axes towards the desired bone.
-    // TODO: How to also incorporate the up vector?
-    let (target_s, mut target_r, target_t) = target_transform.to_scale_rotation_translation();
-    target_r *= glam::Quat::from_rotation_arc(aim.normalize(), v.normalize());
+    let world_r = aim_rotation(aim, up, aim_world, up_world).unwrap_or_else(|| {
+        // Fall back to the smallest rotation if the up vectors are parallel to the aim vectors.
+        // The resulting twist is arbitrary but only changes gradually between frames.
+        glam::Quat::from_rotation_arc(target_world_r * aim, aim_world) * target_world_r
+    });
+
+    // Convert the world orientation to be relative to the target's parent.
+    let quat1 = glam::Quat::from_array(constraint.quat1.to_array());
+    let target_r = (parent_r.inverse() * world_r * quat1).normalize();
 
     Some(glam::Mat4::from_scale_rotation_translation(
         target_s, target_r, target_t,
     ))
 }
 
+fn aim_rotation(
+    aim: glam::Vec3,
+    up: glam::Vec3,
+    aim_world: glam::Vec3,
+    up_world: glam::Vec3,
+) -> Option<glam::Quat> {
+    // Find the rotation from the orthonormal basis of the local axes to the world axes.
+    // Only the components of the up vectors perpendicular to the aim vectors are used.
+    let basis = |aim: glam::Vec3, up: glam::Vec3| {
+        let up = up.reject_from_normalized(aim).try_normalize()?;
+        Some(glam::Mat3::from_cols(aim, up, aim.cross(up)))
+    };
+
+    let local = basis(aim, up)?;
+    let world = basis(aim_world, up_world)?;
+    Some(glam::Quat::from_mat3(&(world * local.transpose())).normalize())
+}
+
 // TODO: Improve tests.
 pub fn apply_orient_constraint(
     world_transforms: &[glam::Mat4],
@@ -587,7 +675,6 @@ mod tests {
 
     #[test]
     fn single_aim_constraint_xz_plane() {
-        // TODO: How test up vector?
         // Skel + Anim:
         //      B ->
         // A ->
@@ -727,4 +814,399 @@ mod tests {
             }),
         );
     }
+
+    fn copy_rotation(name: &str, source: &str, target: &str) -> OrientConstraintData {
+        OrientConstraintData {
+            name: name.into(),
+            parent_bone_name1: source.into(),
+            parent_bone_name2: source.into(),
+            source_bone_name: source.into(),
+            target_bone_name: target.into(),
+            unk_type: 2,
+            constraint_axes: Vector3::new(1.0, 1.0, 1.0),
+            quat1: Vector4::new(0.0, 0.0, 0.0, 1.0),
+            quat2: Vector4::new(0.0, 0.0, 0.0, 1.0),
+            range_min: Vector3::new(-180.0, -180.0, -180.0),
+            range_max: Vector3::new(180.0, 180.0, 180.0),
+        }
+    }
+
+    #[test]
+    fn orient_constraints_chained() {
+        // The constraint sources appear after their targets.
+        let skel_bones = vec![
+            identity_bone("A", None),
+            identity_bone("B", None),
+            identity_bone("C", None),
+        ];
+
+        let rotation = glam::Quat::from_rotation_z(45.0f32.to_radians());
+        let bones = vec![
+            AnimatedBone {
+                bone: &skel_bones[0],
+                anim_transform: None,
+                compensate_scale: false,
+                flags: TransformFlags::default(),
+            },
+            AnimatedBone {
+                bone: &skel_bones[1],
+                anim_transform: None,
+                compensate_scale: false,
+                flags: TransformFlags::default(),
+            },
+            AnimatedBone {
+                bone: &skel_bones[2],
+                anim_transform: Some(AnimTransform {
+                    translation: glam::Vec3::ZERO,
+                    rotation,
+                    scale: glam::Vec3::ONE,
+                }),
+                compensate_scale: false,
+                flags: TransformFlags::default(),
+            },
+        ];
+        // TODO: Manage the indices in a cleaner way.
+        let mut bones: Vec<_> = bones.into_iter().enumerate().collect();
+
+        // Copy the rotation of C to B and then B to A.
+        let mut result = AnimationTransforms::identity();
+        animate_skel_inner(
+            &mut result,
+            &mut bones,
+            &skel_bones,
+            Some(&HlpbData {
+                major_version: 1,
+                minor_version: 0,
+                aim_constraints: Vec::new(),
+                orient_constraints: vec![
+                    copy_rotation("constraint1", "B", "A"),
+                    copy_rotation("constraint2", "C", "B"),
+                ],
+            }),
+        );
+
+        assert_quat_relative_eq!(
+            rotation.to_array(),
+            relative_rotation(&result, &skel_bones, 1).to_array()
+        );
+        assert_quat_relative_eq!(
+            rotation.to_array(),
+            relative_rotation(&result, &skel_bones, 0).to_array()
+        );
+    }
+
+    #[test]
+    fn orient_constraint_child_of_target() {
+        let skel_bones = vec![
+            identity_bone("A", None),
+            identity_bone("B", None),
+            identity_bone("C", Some(1)),
+        ];
+
+        let rotation = glam::Quat::from_rotation_z(90.0f32.to_radians());
+        let bones = vec![
+            AnimatedBone {
+                bone: &skel_bones[0],
+                anim_transform: Some(AnimTransform {
+                    translation: glam::Vec3::ZERO,
+                    rotation,
+                    scale: glam::Vec3::ONE,
+                }),
+                compensate_scale: false,
+                flags: TransformFlags::default(),
+            },
+            AnimatedBone {
+                bone: &skel_bones[1],
+                anim_transform: None,
+                compensate_scale: false,
+                flags: TransformFlags::default(),
+            },
+            AnimatedBone {
+                bone: &skel_bones[2],
+                anim_transform: Some(AnimTransform {
+                    translation: glam::vec3(1.0, 0.0, 0.0),
+                    rotation: glam::Quat::IDENTITY,
+                    scale: glam::Vec3::ONE,
+                }),
+                compensate_scale: false,
+                flags: TransformFlags::default(),
+            },
+        ];
+        // TODO: Manage the indices in a cleaner way.
+        let mut bones: Vec<_> = bones.into_iter().enumerate().collect();
+
+        // Copy the rotation of A to B.
+        let mut result = AnimationTransforms::identity();
+        animate_skel_inner(
+            &mut result,
+            &mut bones,
+            &skel_bones,
+            Some(&HlpbData {
+                major_version: 1,
+                minor_version: 0,
+                aim_constraints: Vec::new(),
+                orient_constraints: vec![copy_rotation("constraint1", "A", "B")],
+            }),
+        );
+
+        // The child of B should move with the constrained rotation.
+        assert_vector_relative_eq!(
+            [0.0, 1.0, 0.0],
+            result.world_transforms[2].col(3).truncate().to_array()
+        );
+    }
+
+    fn aim_constraint(
+        aim_bone_name1: &str,
+        aim_bone_name2: &str,
+        target_bone_name1: &str,
+        target_bone_name2: &str,
+    ) -> AimConstraintData {
+        AimConstraintData {
+            name: "constraint".to_string(),
+            aim_bone_name1: aim_bone_name1.to_string(),
+            aim_bone_name2: aim_bone_name2.to_string(),
+            aim_type1: "DEFAULT".to_string(),
+            aim_type2: "DEFAULT".to_string(),
+            target_bone_name1: target_bone_name1.to_string(),
+            target_bone_name2: target_bone_name2.to_string(),
+            unk1: 0,
+            unk2: 0,
+            aim: Vector3::new(1.0, 0.0, 0.0),
+            up: Vector3::new(0.0, 1.0, 0.0),
+            quat1: Vector4::new(0.0, 0.0, 0.0, 1.0),
+            quat2: Vector4::new(0.0, 0.0, 0.0, 1.0),
+        }
+    }
+
+    fn animated_bone(
+        bone: &BoneData,
+        translation: glam::Vec3,
+        rotation: glam::Quat,
+    ) -> AnimatedBone {
+        AnimatedBone {
+            bone,
+            anim_transform: Some(AnimTransform {
+                translation,
+                rotation,
+                scale: glam::Vec3::ONE,
+            }),
+            compensate_scale: false,
+            flags: TransformFlags::default(),
+        }
+    }
+
+    fn animate_aim_constraint(
+        skel_bones: &[BoneData],
+        bones: Vec<AnimatedBone>,
+        constraint: AimConstraintData,
+    ) -> AnimationTransforms {
+        let mut bones: Vec<_> = bones.into_iter().enumerate().collect();
+
+        let mut result = AnimationTransforms::identity();
+        animate_skel_inner(
+            &mut result,
+            &mut bones,
+            skel_bones,
+            Some(&HlpbData {
+                major_version: 1,
+                minor_version: 0,
+                aim_constraints: vec![constraint],
+                orient_constraints: Vec::new(),
+            }),
+        );
+        result
+    }
+
+    #[test]
+    fn aim_constraint_dependencies() {
+        let skel_bones = vec![
+            identity_bone("A", None),
+            identity_bone("B", None),
+            identity_bone("C", None),
+            identity_bone("D", None),
+        ];
+        let hlpb = HlpbData {
+            major_version: 1,
+            minor_version: 0,
+            aim_constraints: vec![
+                aim_constraint("B", "C", "A", "D"),
+                aim_constraint("B", "B", "C", "C"),
+            ],
+            orient_constraints: Vec::new(),
+        };
+        assert_eq!(
+            vec![
+                ConstraintDependency {
+                    name: "constraint",
+                    source: 1,
+                    target: 0
+                },
+                ConstraintDependency {
+                    name: "constraint",
+                    source: 2,
+                    target: 0
+                },
+                ConstraintDependency {
+                    name: "constraint",
+                    source: 3,
+                    target: 0
+                },
+                ConstraintDependency {
+                    name: "constraint",
+                    source: 1,
+                    target: 2
+                },
+            ],
+            constraint_dependencies(&skel_bones, &hlpb)
+        );
+    }
+
+    #[test]
+    fn aim_constraint_up_bone_twist() {
+        // Point the X-axis of A to B and the Y-axis of A along the Y-axis of C.
+        let skel_bones = vec![
+            identity_bone("A", None),
+            identity_bone("B", None),
+            identity_bone("C", None),
+        ];
+        let rotation = glam::Quat::from_rotation_x(90.0f32.to_radians());
+        let bones = vec![
+            animated_bone(&skel_bones[0], glam::Vec3::ZERO, glam::Quat::IDENTITY),
+            animated_bone(&skel_bones[1], glam::Vec3::X, glam::Quat::IDENTITY),
+            animated_bone(&skel_bones[2], glam::Vec3::ZERO, rotation),
+        ];
+
+        let result = animate_aim_constraint(&skel_bones, bones, aim_constraint("B", "C", "A", "A"));
+
+        // A twists along its aim axis to match the up bone.
+        assert_quat_relative_eq!(
+            rotation.to_array(),
+            relative_rotation(&result, &skel_bones, 0).to_array()
+        );
+    }
+
+    #[test]
+    fn aim_constraint_opposite_direction() {
+        // Aiming directly behind the bone shouldn't flip the up axis.
+        let skel_bones = vec![identity_bone("A", None), identity_bone("B", None)];
+        let bones = vec![
+            animated_bone(&skel_bones[0], glam::Vec3::ZERO, glam::Quat::IDENTITY),
+            animated_bone(&skel_bones[1], -glam::Vec3::X, glam::Quat::IDENTITY),
+        ];
+
+        let result = animate_aim_constraint(&skel_bones, bones, aim_constraint("B", "", "A", ""));
+
+        assert_vector_relative_eq!(
+            [-1.0, 0.0, 0.0],
+            result.world_transforms[0]
+                .transform_vector3(glam::Vec3::X)
+                .to_array()
+        );
+        assert_vector_relative_eq!(
+            [0.0, 1.0, 0.0],
+            result.world_transforms[0]
+                .transform_vector3(glam::Vec3::Y)
+                .to_array()
+        );
+    }
+
+    #[test]
+    fn aim_constraint_rotated_parent() {
+        // Helper bones like H_ElbowL are usually children of a rotated bone.
+        let skel_bones = vec![
+            identity_bone("Parent", None),
+            identity_bone("A", Some(0)),
+            identity_bone("B", None),
+        ];
+        let bones = vec![
+            animated_bone(
+                &skel_bones[0],
+                glam::Vec3::ZERO,
+                glam::Quat::from_rotation_z(90.0f32.to_radians()),
+            ),
+            animated_bone(&skel_bones[1], glam::Vec3::ZERO, glam::Quat::IDENTITY),
+            animated_bone(&skel_bones[2], glam::Vec3::Z, glam::Quat::IDENTITY),
+        ];
+
+        let result = animate_aim_constraint(&skel_bones, bones, aim_constraint("B", "", "A", ""));
+
+        // The X-axis points to B and the Y-axis stays aligned with the parent.
+        assert_vector_relative_eq!(
+            [0.0, 0.0, 1.0],
+            result.world_transforms[1]
+                .transform_vector3(glam::Vec3::X)
+                .to_array()
+        );
+        assert_vector_relative_eq!(
+            [-1.0, 0.0, 0.0],
+            result.world_transforms[1]
+                .transform_vector3(glam::Vec3::Y)
+                .to_array()
+        );
+    }
+
+    #[test]
+    fn aim_constraint_second_target_bone() {
+        // The aim direction starts from S instead of A.
+        let skel_bones = vec![
+            identity_bone("A", None),
+            identity_bone("B", None),
+            identity_bone("S", None),
+        ];
+        let bones = vec![
+            animated_bone(&skel_bones[0], glam::Vec3::Y, glam::Quat::IDENTITY),
+            animated_bone(&skel_bones[1], glam::Vec3::Z, glam::Quat::IDENTITY),
+            animated_bone(&skel_bones[2], glam::Vec3::ZERO, glam::Quat::IDENTITY),
+        ];
+
+        let result = animate_aim_constraint(&skel_bones, bones, aim_constraint("B", "", "A", "S"));
+
+        assert_quat_relative_eq!(
+            glam::Quat::from_rotation_y(-90.0f32.to_radians()).to_array(),
+            relative_rotation(&result, &skel_bones, 0).to_array()
+        );
+        assert_vector_relative_eq!(
+            [0.0, 1.0, 0.0],
+            relative_translation(&result, &skel_bones, 0).to_array()
+        );
+    }
+
+    #[test]
+    fn aim_constraint_elbow_helper_bone() {
+        // Fighter helper bones like H_ElbowL repeat the same bone for both aim bone names
+        // and both target bone names.
+        // TODO: Use the exact values from a fighter's model.nuhlpb.
+        let skel_bones = vec![
+            identity_bone("ShoulderL", None),
+            identity_bone("ArmL", Some(0)),
+            identity_bone("H_ElbowL", Some(0)),
+        ];
+        let rotation = glam::Quat::from_rotation_x(90.0f32.to_radians());
+        let bones = vec![
+            animated_bone(&skel_bones[0], glam::Vec3::ZERO, glam::Quat::IDENTITY),
+            animated_bone(&skel_bones[1], glam::Vec3::X, rotation),
+            animated_bone(&skel_bones[2], glam::Vec3::ZERO, glam::Quat::IDENTITY),
+        ];
+
+        let result = animate_aim_constraint(
+            &skel_bones,
+            bones,
+            aim_constraint("ArmL", "ArmL", "H_ElbowL", "H_ElbowL"),
+        );
+
+        // The helper bone points to ArmL and twists with ArmL's rotation.
+        assert_vector_relative_eq!(
+            [1.0, 0.0, 0.0],
+            result.world_transforms[2]
+                .transform_vector3(glam::Vec3::X)
+                .to_array()
+        );
+        assert_vector_relative_eq!(
+            [0.0, 0.0, 1.0],
+            result.world_transforms[2]
+                .transform_vector3(glam::Vec3::Y)
+                .to_array()
+        );
+    }
 }
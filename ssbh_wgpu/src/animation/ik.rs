@@ -0,0 +1,268 @@
+use super::{AnimationTransforms, ValidatedSkel};
+
+/// A chain of bones like an arm or leg posed with inverse kinematics.
+///
+/// The mid bone should be a descendant of the root bone,
+/// and the end bone should be a descendant of the mid bone.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TwoBoneIk {
+    pub root_bone_name: String,
+    pub mid_bone_name: String,
+    pub end_bone_name: String,
+    /// The position to move the end bone towards
+    /// in the same model space as [AnimationTransforms::world_transforms].
+    pub target: glam::Vec3,
+    /// The direction from the root bone that the mid bone bends towards
+    /// in the same model space as [AnimationTransforms::world_transforms].
+    pub pole_vector: glam::Vec3,
+}
+
+/// Rotates the root and mid bones in `ik` so the end bone reaches the target
+/// or points towards the target if the target is out of reach.
+///
+/// This should be applied after animations and constraints.
+/// Children of the chain move with their parents.
+/// Chains with missing bones are ignored.
+pub fn apply_two_bone_ik(
+    transforms: &mut AnimationTransforms,
+    skel: &ValidatedSkel,
+    ik: &TwoBoneIk,
+) {
+    solve_two_bone_ik(transforms, skel, ik);
+}
+
+fn solve_two_bone_ik(
+    transforms: &mut AnimationTransforms,
+    skel: &ValidatedSkel,
+    ik: &TwoBoneIk,
+) -> Option<()> {
+    let position = |name: &str| skel.bones().iter().position(|b| b.name == name);
+    let root = position(&ik.root_bone_name)?;
+    let mid = position(&ik.mid_bone_name)?;
+    let end = position(&ik.end_bone_name)?;
+    if !is_descendant(skel, mid, root) || !is_descendant(skel, end, mid) {
+        return None;
+    }
+
+    let world_position = |transforms: &AnimationTransforms, i: usize| {
+        transforms
+            .world_transforms
+            .get(i)
+            .map(|t| t.col(3).truncate())
+    };
+    let root_pos = world_position(transforms, root)?;
+    let mid_pos = world_position(transforms, mid)?;
+    let end_pos = world_position(transforms, end)?;
+
+    let upper_length = root_pos.distance(mid_pos);
+    let lower_length = mid_pos.distance(end_pos);
+    if upper_length <= f32::EPSILON || lower_length <= f32::EPSILON {
+        return None;
+    }
+
+    // Targets out of reach fully extend or fully bend the chain.
+    let to_target = ik.target - root_pos;
+    let direction = to_target.try_normalize()?;
+    let distance = to_target
+        .length()
+        .clamp(
+            (upper_length - lower_length).abs(),
+            upper_length + lower_length,
+        )
+        .max(f32::EPSILON);
+
+    // Bend towards the pole vector or the current bend direction if the pole is parallel.
+    let bend = ik
+        .pole_vector
+        .reject_from_normalized(direction)
+        .try_normalize()
+        .or_else(|| {
+            (mid_pos - root_pos)
+                .reject_from_normalized(direction)
+                .try_normalize()
+        })
+        .unwrap_or_else(|| direction.any_orthonormal_vector());
+
+    // Find the mid position using the law of cosines.
+    let cos_root_angle = ((upper_length * upper_length + distance * distance
+        - lower_length * lower_length)
+        / (2.0 * upper_length * distance))
+        .clamp(-1.0, 1.0);
+    let sin_root_angle = (1.0 - cos_root_angle * cos_root_angle).sqrt();
+    let new_mid_pos =
+        root_pos + upper_length * (cos_root_angle * direction + sin_root_angle * bend);
+    let new_end_pos = root_pos + distance * direction;
+
+    let root_rotation = glam::Quat::from_rotation_arc(
+        (mid_pos - root_pos).normalize(),
+        (new_mid_pos - root_pos).normalize(),
+    );
+    rotate_descendants(transforms, skel, root, root_pos, root_rotation);
+
+    // The end position changed after rotating the root bone.
+    let end_pos = world_position(transforms, end)?;
+    let mid_rotation = glam::Quat::from_rotation_arc(
+        (end_pos - new_mid_pos).try_normalize()?,
+        (new_end_pos - new_mid_pos).try_normalize()?,
+    );
+    rotate_descendants(transforms, skel, mid, new_mid_pos, mid_rotation);
+
+    Some(())
+}
+
+fn rotate_descendants(
+    transforms: &mut AnimationTransforms,
+    skel: &ValidatedSkel,
+    bone: usize,
+    pivot: glam::Vec3,
+    rotation: glam::Quat,
+) {
+    let world_rotation = glam::Mat4::from_translation(pivot)
+        * glam::Mat4::from_quat(rotation)
+        * glam::Mat4::from_translation(-pivot);
+
+    for i in 0..skel.bones().len().min(transforms.bone_count()) {
+        if i == bone || is_descendant(skel, i, bone) {
+            // Skinning uses the world transform relative to the rest pose,
+            // so the same world space rotation applies to both transforms.
+            transforms.world_transforms[i] = world_rotation * transforms.world_transforms[i];
+
+            let animated = &mut transforms.animated_world_transforms;
+            animated.transforms[i] = world_rotation * animated.transforms[i];
+            animated.transforms_inv_transpose[i] = animated.transforms[i].inverse().transpose();
+        }
+    }
+}
+
+fn is_descendant(skel: &ValidatedSkel, bone: usize, ancestor: usize) -> bool {
+    // ValidatedSkel removes cycles, so this always terminates.
+    let mut parent_index = skel.bones()[bone].parent_index;
+    while let Some(parent) = parent_index {
+        if parent == ancestor {
+            return true;
+        }
+        parent_index = skel.bones()[parent].parent_index;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{animation::animate_skel, assert_vector_relative_eq};
+    use ssbh_data::{
+        prelude::*,
+        skel_data::{BillboardType, BoneData},
+    };
+
+    fn bone(name: &str, parent_index: Option<usize>, translation: [f32; 3]) -> BoneData {
+        BoneData {
+            name: name.to_string(),
+            transform: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [translation[0], translation[1], translation[2], 1.0],
+            ],
+            parent_index,
+            billboard_type: BillboardType::Disabled,
+        }
+    }
+
+    fn arm_skel() -> SkelData {
+        SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![
+                bone("Shoulder", None, [0.0, 0.0, 0.0]),
+                bone("Arm", Some(0), [1.0, 0.0, 0.0]),
+                bone("Hand", Some(1), [1.0, 0.0, 0.0]),
+                bone("Finger", Some(2), [0.0, 0.0, 1.0]),
+            ],
+        }
+    }
+
+    fn arm_ik(target: glam::Vec3, pole_vector: glam::Vec3) -> TwoBoneIk {
+        TwoBoneIk {
+            root_bone_name: "Shoulder".to_string(),
+            mid_bone_name: "Arm".to_string(),
+            end_bone_name: "Hand".to_string(),
+            target,
+            pole_vector,
+        }
+    }
+
+    fn posed_arm(ik: &TwoBoneIk) -> AnimationTransforms {
+        let skel = arm_skel();
+        let skel = ValidatedSkel::new(&skel);
+        let mut transforms = AnimationTransforms::from_skel(&skel);
+        animate_skel(&mut transforms, &skel, std::iter::empty(), None, 0.0);
+        apply_two_bone_ik(&mut transforms, &skel, ik);
+        transforms
+    }
+
+    fn position(transforms: &AnimationTransforms, i: usize) -> [f32; 3] {
+        transforms.world_transforms[i].col(3).truncate().to_array()
+    }
+
+    #[test]
+    fn two_bone_ik_reachable_target() {
+        let transforms = posed_arm(&arm_ik(glam::vec3(1.0, 1.0, 0.0), glam::Vec3::Y));
+
+        assert_vector_relative_eq!([0.0, 0.0, 0.0], position(&transforms, 0));
+        assert_vector_relative_eq!([0.0, 1.0, 0.0], position(&transforms, 1));
+        assert_vector_relative_eq!([1.0, 1.0, 0.0], position(&transforms, 2));
+
+        // Children of the end bone move with the chain.
+        assert_vector_relative_eq!([1.0, 1.0, 1.0], position(&transforms, 3));
+    }
+
+    #[test]
+    fn two_bone_ik_pole_vector() {
+        // The mid bone bends the other way.
+        let transforms = posed_arm(&arm_ik(glam::vec3(1.0, 1.0, 0.0), -glam::Vec3::Y));
+
+        assert_vector_relative_eq!([1.0, 0.0, 0.0], position(&transforms, 1));
+        assert_vector_relative_eq!([1.0, 1.0, 0.0], position(&transforms, 2));
+    }
+
+    #[test]
+    fn two_bone_ik_unreachable_target() {
+        // The chain extends towards the target.
+        let transforms = posed_arm(&arm_ik(glam::vec3(0.0, 5.0, 0.0), glam::Vec3::X));
+
+        assert_vector_relative_eq!([0.0, 1.0, 0.0], position(&transforms, 1));
+        assert_vector_relative_eq!([0.0, 2.0, 0.0], position(&transforms, 2));
+    }
+
+    #[test]
+    fn two_bone_ik_skinning_transforms() {
+        let transforms = posed_arm(&arm_ik(glam::vec3(1.0, 1.0, 0.0), glam::Vec3::Y));
+
+        // Vertices at the rest position of each bone should move with the bone.
+        let skinned = |i: usize, rest: glam::Vec3| {
+            transforms.animated_world_transforms.transforms[i]
+                .transform_point3(rest)
+                .to_array()
+        };
+        assert_vector_relative_eq!([0.0, 1.0, 0.0], skinned(1, glam::vec3(1.0, 0.0, 0.0)));
+        assert_vector_relative_eq!([1.0, 1.0, 0.0], skinned(2, glam::vec3(2.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn two_bone_ik_invalid_chain() {
+        // The bones are not in a chain.
+        let ik = TwoBoneIk {
+            root_bone_name: "Hand".to_string(),
+            mid_bone_name: "Arm".to_string(),
+            end_bone_name: "Shoulder".to_string(),
+            target: glam::vec3(1.0, 1.0, 0.0),
+            pole_vector: glam::Vec3::Y,
+        };
+        let transforms = posed_arm(&ik);
+
+        assert_vector_relative_eq!([1.0, 0.0, 0.0], position(&transforms, 1));
+        assert_vector_relative_eq!([2.0, 0.0, 0.0], position(&transforms, 2));
+    }
+}
@@ -131,19 +131,24 @@ fn scene_attributes_from_node(node: &NodeData, frame: f32) -> SceneAttributesFor
         if let Ok(param) = ParamId::from_str(&track.name) {
             match &track.values {
                 TrackValues::Float(values) => {
-                    if let Some(index) = float_index(param) {
-                        attributes.custom_float[index][0] = frame_value(values, frame);
+                    if let (Some(index), Some(value)) =
+                        (float_index(param), frame_value(values, frame))
+                    {
+                        attributes.custom_float[index][0] = value;
                     }
                 }
                 TrackValues::Boolean(values) => {
-                    if let Some(index) = boolean_index(param) {
-                        attributes.custom_boolean[index][0] = frame_value(values, frame) as u32;
+                    if let (Some(index), Some(value)) =
+                        (boolean_index(param), frame_value(values, frame))
+                    {
+                        attributes.custom_boolean[index][0] = value as u32;
                     }
                 }
                 TrackValues::Vector4(values) => {
-                    if let Some(index) = vector_index(param) {
-                        attributes.custom_vector[index] =
-                            frame_value(values, frame).to_array().into();
+                    if let (Some(index), Some(value)) =
+                        (vector_index(param), frame_value(values, frame))
+                    {
+                        attributes.custom_vector[index] = value.to_array().into();
                     }
                 }
                 _ => (),
@@ -162,7 +167,7 @@ fn light_from_node(node: &NodeData, frame: f32) -> Light {
         .iter()
         .find(|t| t.name == "CustomFloat0")
         .and_then(|t| match &t.values {
-            TrackValues::Float(values) => Some(frame_value(values, frame)),
+            TrackValues::Float(values) => frame_value(values, frame),
             _ => None,
         })
         .unwrap_or_default();
@@ -172,7 +177,7 @@ fn light_from_node(node: &NodeData, frame: f32) -> Light {
         .iter()
         .find(|t| t.name == "CustomVector0")
         .and_then(|t| match &t.values {
-            TrackValues::Vector4(values) => Some(frame_value(values, frame)),
+            TrackValues::Vector4(values) => frame_value(values, frame),
             _ => None,
         })
         .unwrap_or_default();
@@ -183,7 +188,7 @@ fn light_from_node(node: &NodeData, frame: f32) -> Light {
         .iter()
         .find(|t| t.name == "Transform")
         .and_then(|t| match &t.values {
-            TrackValues::Transform(values) => Some(frame_value(values, frame)),
+            TrackValues::Transform(values) => frame_value(values, frame),
             _ => None,
         });
 
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use ssbh_data::prelude::*;
+
+use super::{
+    animate_skel_with_overrides, apply_two_bone_ik, AnimationLayer, AnimationTransforms,
+    PoseOverride, TwoBoneIk, ValidatedSkel,
+};
+
+/// Evaluates `layers`, `overrides`, the constraints in `hlpb`, and `ik_chains`
+/// for each frame from `start_frame` to `end_frame`
+/// and returns the world position at each frame for each of the bones in `bone_names`.
+///
+/// Frames are sampled in increments of `1.0` starting from `start_frame`.
+/// Each layer is sampled at the same frame with its current weight.
+/// Bones not in `skel` have an empty path.
+#[allow(clippy::too_many_arguments)]
+pub fn bone_motion_paths(
+    skel: &ValidatedSkel,
+    layers: &[AnimationLayer],
+    overrides: &HashMap<String, PoseOverride>,
+    ik_chains: &[TwoBoneIk],
+    hlpb: Option<&HlpbData>,
+    bone_names: &[&str],
+    start_frame: f32,
+    end_frame: f32,
+) -> Vec<Vec<glam::Vec3>> {
+    let bone_indices: Vec<_> = bone_names
+        .iter()
+        .map(|name| skel.bones().iter().position(|b| b.name == *name))
+        .collect();
+
+    let frame_count = if end_frame >= start_frame {
+        (end_frame - start_frame).floor() as usize + 1
+    } else {
+        0
+    };
+
+    let mut paths = vec![Vec::with_capacity(frame_count); bone_names.len()];
+
+    let mut transforms = AnimationTransforms::from_skel(skel);
+    for i in 0..frame_count {
+        let frame = start_frame + i as f32;
+        animate_skel_with_overrides(
+            &mut transforms,
+            skel,
+            layers.iter().map(|l| AnimationLayer { frame, ..*l }),
+            overrides,
+            hlpb,
+        );
+
+        // Match the IK in RenderModel::apply_anim_layers.
+        for ik in ik_chains {
+            apply_two_bone_ik(&mut transforms, skel, ik);
+        }
+
+        for (path, index) in paths.iter_mut().zip(&bone_indices) {
+            if let Some(transform) = index.and_then(|i| transforms.world_transforms.get(i)) {
+                path.push(transform.col(3).truncate());
+            }
+        }
+    }
+
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assert_vector_relative_eq;
+    use ssbh_data::{
+        anim_data::{
+            GroupData, GroupType, NodeData, TrackData, TrackValues, Transform, TransformFlags,
+        },
+        skel_data::{BillboardType, BoneData},
+        Vector3, Vector4,
+    };
+
+    fn bone(name: &str, parent_index: Option<usize>, translation: [f32; 3]) -> BoneData {
+        BoneData {
+            name: name.to_string(),
+            transform: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [translation[0], translation[1], translation[2], 1.0],
+            ],
+            parent_index,
+            billboard_type: BillboardType::Disabled,
+        }
+    }
+
+    fn translation_anim(name: &str, translations: &[[f32; 3]]) -> AnimData {
+        AnimData {
+            major_version: 2,
+            minor_version: 0,
+            final_frame_index: translations.len().saturating_sub(1) as f32,
+            groups: vec![GroupData {
+                group_type: GroupType::Transform,
+                nodes: vec![NodeData {
+                    name: name.to_string(),
+                    tracks: vec![TrackData {
+                        name: "Transform".to_string(),
+                        compensate_scale: false,
+                        transform_flags: TransformFlags::default(),
+                        values: TrackValues::Transform(
+                            translations
+                                .iter()
+                                .map(|t| Transform {
+                                    scale: Vector3::new(1.0, 1.0, 1.0),
+                                    rotation: Vector4::new(0.0, 0.0, 0.0, 1.0),
+                                    translation: (*t).into(),
+                                })
+                                .collect(),
+                        ),
+                    }],
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn motion_paths_child_bone() {
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![
+                bone("Trans", None, [0.0; 3]),
+                bone("Hand", Some(0), [0.0, 1.0, 0.0]),
+            ],
+        };
+        let skel = ValidatedSkel::new(&skel);
+        let anim = translation_anim(
+            "Trans",
+            &[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [2.0, 0.0, 0.0]],
+        );
+
+        let paths = bone_motion_paths(
+            &skel,
+            &[AnimationLayer::new(&anim, 0.0)],
+            &HashMap::new(),
+            &[],
+            None,
+            &["Hand", "Trans"],
+            0.0,
+            2.0,
+        );
+
+        assert_eq!(2, paths.len());
+        assert_eq!(3, paths[0].len());
+        assert_vector_relative_eq!([0.0, 1.0, 0.0], paths[0][0].to_array());
+        assert_vector_relative_eq!([1.0, 1.0, 0.0], paths[0][1].to_array());
+        assert_vector_relative_eq!([2.0, 1.0, 0.0], paths[0][2].to_array());
+        assert_vector_relative_eq!([2.0, 0.0, 0.0], paths[1][2].to_array());
+    }
+
+    #[test]
+    fn motion_paths_frame_range() {
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![bone("Trans", None, [0.0; 3])],
+        };
+        let skel = ValidatedSkel::new(&skel);
+        let anim = translation_anim(
+            "Trans",
+            &[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [2.0, 0.0, 0.0]],
+        );
+
+        let layers = [AnimationLayer::new(&anim, 0.0)];
+        let paths = bone_motion_paths(
+            &skel,
+            &layers,
+            &HashMap::new(),
+            &[],
+            None,
+            &["Trans"],
+            1.0,
+            2.5,
+        );
+        assert_eq!(2, paths[0].len());
+        assert_vector_relative_eq!([1.0, 0.0, 0.0], paths[0][0].to_array());
+        assert_vector_relative_eq!([2.0, 0.0, 0.0], paths[0][1].to_array());
+
+        // The end frame is before the start frame.
+        let paths = bone_motion_paths(
+            &skel,
+            &layers,
+            &HashMap::new(),
+            &[],
+            None,
+            &["Trans"],
+            2.0,
+            1.0,
+        );
+        assert!(paths[0].is_empty());
+    }
+
+    #[test]
+    fn motion_paths_missing_bone() {
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![bone("Trans", None, [0.0; 3])],
+        };
+        let skel = ValidatedSkel::new(&skel);
+
+        let paths = bone_motion_paths(&skel, &[], &HashMap::new(), &[], None, &["Rot"], 0.0, 2.0);
+        assert_eq!(vec![Vec::<glam::Vec3>::new()], paths);
+    }
+
+    #[test]
+    fn motion_paths_pose_override() {
+        let skel = SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: vec![
+                bone("Trans", None, [0.0; 3]),
+                bone("Hand", Some(0), [0.0, 1.0, 0.0]),
+            ],
+        };
+        let skel = ValidatedSkel::new(&skel);
+        let anim = translation_anim("Trans", &[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0]]);
+
+        // The override should apply on every frame after sampling the animation.
+        let overrides = HashMap::from([(
+            "Hand".to_string(),
+            PoseOverride::Additive {
+                translation: glam::vec3(0.0, 0.0, 2.0),
+                rotation: glam::Quat::IDENTITY,
+                scale: glam::Vec3::ONE,
+            },
+        )]);
+
+        let paths = bone_motion_paths(
+            &skel,
+            &[AnimationLayer::new(&anim, 0.0)],
+            &overrides,
+            &[],
+            None,
+            &["Hand"],
+            0.0,
+            1.0,
+        );
+
+        assert_vector_relative_eq!([0.0, 1.0, 2.0], paths[0][0].to_array());
+        assert_vector_relative_eq!([1.0, 1.0, 2.0], paths[0][1].to_array());
+    }
+}
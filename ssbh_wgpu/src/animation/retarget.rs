@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+
+use ssbh_data::{
+    anim_data::{GroupType, TrackValues, Transform},
+    prelude::*,
+    skel_data::BoneData,
+};
+
+use super::AnimTransform;
+
+/// Converts `anim` authored for `source_skel` to animate the bones in `target_skel`.
+///
+/// Transform nodes are renamed using the source to target bone names in `bone_map`.
+/// Nodes not in `bone_map` keep their name.
+/// Each transform is applied relative to the rest pose of the target bone,
+/// so bones with different resting orientations or lengths animate the same way.
+/// The values for nodes without a bone in both skels are copied unchanged.
+/// Other group types like visibility and materials don't use bone names and are copied unchanged.
+pub fn retarget_anim(
+    anim: &AnimData,
+    source_skel: &SkelData,
+    target_skel: &SkelData,
+    bone_map: &HashMap<String, String>,
+) -> AnimData {
+    let mut anim = anim.clone();
+
+    for group in anim
+        .groups
+        .iter_mut()
+        .filter(|g| g.group_type == GroupType::Transform)
+    {
+        for node in &mut group.nodes {
+            let target_name = bone_map.get(&node.name).unwrap_or(&node.name);
+
+            let source_bone = source_skel.bones.iter().find(|b| b.name == node.name);
+            let target_bone = target_skel.bones.iter().find(|b| &b.name == target_name);
+            if let (Some(source_bone), Some(target_bone)) = (source_bone, target_bone) {
+                for track in &mut node.tracks {
+                    if let TrackValues::Transform(values) = &mut track.values {
+                        for value in values {
+                            *value = retarget_transform(value, source_bone, target_bone);
+                        }
+                    }
+                }
+            }
+
+            node.name = target_name.clone();
+        }
+    }
+
+    anim
+}
+
+fn retarget_transform(
+    value: &Transform,
+    source_bone: &BoneData,
+    target_bone: &BoneData,
+) -> Transform {
+    let rest_transform = |bone: &BoneData| {
+        let matrix = glam::Mat4::from_cols_array_2d(&bone.transform);
+        if matrix.determinant() == 0.0 {
+            // The rotation can't be decomposed from a transform with zero scale.
+            AnimTransform {
+                translation: matrix.w_axis.truncate(),
+                rotation: glam::Quat::IDENTITY,
+                scale: glam::vec3(
+                    matrix.x_axis.truncate().length(),
+                    matrix.y_axis.truncate().length(),
+                    matrix.z_axis.truncate().length(),
+                ),
+            }
+        } else {
+            let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+            AnimTransform {
+                translation,
+                rotation,
+                scale,
+            }
+        }
+    };
+    let source_rest = rest_transform(source_bone);
+    let target_rest = rest_transform(target_bone);
+    let value = AnimTransform::from(value.clone());
+
+    // Find the change from the source rest pose and apply it to the target rest pose.
+    let rest_rotation = target_rest.rotation * source_rest.rotation.inverse();
+    let rotation = rest_rotation * value.rotation;
+
+    // Scale translations by the relative bone length to preserve proportions.
+    let source_length = source_rest.translation.length();
+    let length_scale = if source_length > f32::EPSILON {
+        target_rest.translation.length() / source_length
+    } else {
+        1.0
+    };
+    // Rotate the offset from the rest pose to match the rotation above.
+    let translation = target_rest.translation
+        + rest_rotation * (value.translation - source_rest.translation) * length_scale;
+
+    // Avoid dividing by zero for source bones with zero scale.
+    let scale_ratio = glam::Vec3::select(
+        source_rest
+            .scale
+            .abs()
+            .cmpgt(glam::Vec3::splat(f32::EPSILON)),
+        target_rest.scale / source_rest.scale,
+        glam::Vec3::ONE,
+    );
+    let scale = value.scale * scale_ratio;
+
+    Transform {
+        translation: translation.to_array().into(),
+        rotation: rotation.normalize().to_array().into(),
+        scale: scale.to_array().into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{assert_quat_relative_eq, assert_vector_relative_eq};
+    use ssbh_data::{
+        anim_data::{GroupData, NodeData, TrackData, TransformFlags},
+        skel_data::BillboardType,
+        Vector3,
+    };
+
+    fn bone(name: &str, rotation: glam::Quat, translation: glam::Vec3) -> BoneData {
+        BoneData {
+            name: name.to_string(),
+            transform: glam::Mat4::from_rotation_translation(rotation, translation)
+                .to_cols_array_2d(),
+            parent_index: None,
+            billboard_type: BillboardType::Disabled,
+        }
+    }
+
+    fn skel(bones: Vec<BoneData>) -> SkelData {
+        SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones,
+        }
+    }
+
+    fn anim(name: &str, rotation: glam::Quat, translation: glam::Vec3) -> AnimData {
+        AnimData {
+            major_version: 2,
+            minor_version: 0,
+            final_frame_index: 0.0,
+            groups: vec![
+                GroupData {
+                    group_type: GroupType::Transform,
+                    nodes: vec![NodeData {
+                        name: name.to_string(),
+                        tracks: vec![TrackData {
+                            name: "Transform".to_string(),
+                            compensate_scale: false,
+                            transform_flags: TransformFlags::default(),
+                            values: TrackValues::Transform(vec![Transform {
+                                scale: Vector3::new(1.0, 1.0, 1.0),
+                                rotation: rotation.to_array().into(),
+                                translation: translation.to_array().into(),
+                            }]),
+                        }],
+                    }],
+                },
+                GroupData {
+                    group_type: GroupType::Visibility,
+                    nodes: vec![NodeData {
+                        name: name.to_string(),
+                        tracks: vec![TrackData {
+                            name: "Visibility".to_string(),
+                            compensate_scale: false,
+                            transform_flags: TransformFlags::default(),
+                            values: TrackValues::Boolean(vec![true]),
+                        }],
+                    }],
+                },
+            ],
+        }
+    }
+
+    fn transform(anim: &AnimData) -> &Transform {
+        match &anim.groups[0].nodes[0].tracks[0].values {
+            TrackValues::Transform(values) => &values[0],
+            _ => panic!("Expected transform values"),
+        }
+    }
+
+    #[test]
+    fn retarget_same_rest_pose() {
+        let source = skel(vec![bone("A", glam::Quat::IDENTITY, glam::Vec3::X)]);
+        let target = skel(vec![bone("B", glam::Quat::IDENTITY, glam::Vec3::X)]);
+        let rotation = glam::Quat::from_rotation_x(1.0);
+        let anim = anim("A", rotation, glam::vec3(1.0, 2.0, 3.0));
+
+        let bone_map = HashMap::from([("A".to_string(), "B".to_string())]);
+        let retargeted = retarget_anim(&anim, &source, &target, &bone_map);
+
+        assert_eq!("B", retargeted.groups[0].nodes[0].name);
+        assert_quat_relative_eq!(
+            rotation.to_array(),
+            transform(&retargeted).rotation.to_array()
+        );
+        assert_vector_relative_eq!(
+            [1.0, 2.0, 3.0],
+            transform(&retargeted).translation.to_array()
+        );
+
+        // Other groups don't use bone names.
+        assert_eq!(anim.groups[1], retargeted.groups[1]);
+    }
+
+    #[test]
+    fn retarget_rest_rotation() {
+        let target_rest = glam::Quat::from_rotation_z(90f32.to_radians());
+        let source = skel(vec![bone("A", glam::Quat::IDENTITY, glam::Vec3::ZERO)]);
+        let target = skel(vec![bone("A", target_rest, glam::Vec3::ZERO)]);
+        let rotation = glam::Quat::from_rotation_x(90f32.to_radians());
+        let anim = anim("A", rotation, glam::Vec3::ZERO);
+
+        // The name is unchanged without an entry in the map.
+        let retargeted = retarget_anim(&anim, &source, &target, &HashMap::new());
+
+        assert_eq!("A", retargeted.groups[0].nodes[0].name);
+        assert_quat_relative_eq!(
+            (target_rest * rotation).to_array(),
+            transform(&retargeted).rotation.to_array()
+        );
+    }
+
+    #[test]
+    fn retarget_bone_length() {
+        let source = skel(vec![bone(
+            "A",
+            glam::Quat::IDENTITY,
+            glam::vec3(1.0, 0.0, 0.0),
+        )]);
+        let target = skel(vec![bone(
+            "B",
+            glam::Quat::IDENTITY,
+            glam::vec3(2.0, 0.0, 0.0),
+        )]);
+        let anim = anim("A", glam::Quat::IDENTITY, glam::vec3(1.5, 0.0, 0.0));
+
+        let bone_map = HashMap::from([("A".to_string(), "B".to_string())]);
+        let retargeted = retarget_anim(&anim, &source, &target, &bone_map);
+
+        assert_vector_relative_eq!(
+            [3.0, 0.0, 0.0],
+            transform(&retargeted).translation.to_array()
+        );
+    }
+
+    #[test]
+    fn retarget_rest_rotation_translation() {
+        let target_rest = glam::Quat::from_rotation_z(90f32.to_radians());
+        let source = skel(vec![bone("A", glam::Quat::IDENTITY, glam::Vec3::X)]);
+        let target = skel(vec![bone("A", target_rest, glam::Vec3::Y)]);
+        let anim = anim("A", glam::Quat::IDENTITY, glam::vec3(1.0, 1.0, 0.0));
+
+        let retargeted = retarget_anim(&anim, &source, &target, &HashMap::new());
+
+        // The offset from the rest pose is rotated like the rotation.
+        assert_vector_relative_eq!(
+            [-1.0, 1.0, 0.0],
+            transform(&retargeted).translation.to_array()
+        );
+    }
+
+    #[test]
+    fn retarget_zero_scale_source() {
+        let source = skel(vec![BoneData {
+            name: "A".to_string(),
+            transform: glam::Mat4::from_scale(glam::Vec3::ZERO).to_cols_array_2d(),
+            parent_index: None,
+            billboard_type: BillboardType::Disabled,
+        }]);
+        let target = skel(vec![bone("A", glam::Quat::IDENTITY, glam::Vec3::ZERO)]);
+        let anim = anim("A", glam::Quat::IDENTITY, glam::Vec3::ZERO);
+
+        let retargeted = retarget_anim(&anim, &source, &target, &HashMap::new());
+
+        assert_vector_relative_eq!([1.0, 1.0, 1.0], transform(&retargeted).scale.to_array());
+        assert_quat_relative_eq!(
+            [0.0, 0.0, 0.0, 1.0],
+            transform(&retargeted).rotation.to_array()
+        );
+    }
+
+    #[test]
+    fn retarget_missing_target_bone() {
+        let source = skel(vec![bone("A", glam::Quat::IDENTITY, glam::Vec3::X)]);
+        let target = skel(Vec::new());
+        let anim = anim("A", glam::Quat::IDENTITY, glam::vec3(1.5, 0.0, 0.0));
+
+        let bone_map = HashMap::from([("A".to_string(), "B".to_string())]);
+        let retargeted = retarget_anim(&anim, &source, &target, &bone_map);
+
+        assert_eq!("B", retargeted.groups[0].nodes[0].name);
+        assert_eq!(
+            anim.groups[0].nodes[0].tracks,
+            retargeted.groups[0].nodes[0].tracks
+        );
+    }
+}
@@ -0,0 +1,380 @@
+use std::{borrow::Cow, collections::HashSet, fmt::Display};
+
+use ssbh_data::{prelude::*, skel_data::BoneData};
+
+/// The maximum number of bones supported by Smash Ultimate.
+pub const MAX_SKEL_BONE_COUNT: usize = 511;
+
+/// A problem with the bones in a [SkelData].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SkelValidationError {
+    /// The parent index does not refer to a bone in the skel.
+    /// The bone is treated as a root bone.
+    InvalidParentIndex {
+        bone_name: String,
+        parent_index: usize,
+    },
+    /// The bones are their own ancestors.
+    /// The last bone in the cycle is treated as a root bone.
+    ParentCycle { bone_names: Vec<String> },
+    /// The bone name is already used by a previous bone.
+    /// Animations and constraints only affect the first bone with this name.
+    DuplicateBoneName {
+        bone_name: String,
+        bone_index: usize,
+    },
+    /// The skel has more than [MAX_SKEL_BONE_COUNT] bones.
+    /// This only affects compatibility with the game since all bones are still rendered.
+    TooManyBones { bone_count: usize },
+}
+
+impl Display for SkelValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkelValidationError::InvalidParentIndex {
+                bone_name,
+                parent_index,
+            } => write!(
+                f,
+                "Parent index {parent_index} for bone {bone_name} does not refer to a bone in the skel."
+            ),
+            SkelValidationError::ParentCycle { bone_names } => {
+                write!(f, "Bones {bone_names:?} form a cycle of parents.")
+            }
+            SkelValidationError::DuplicateBoneName {
+                bone_name,
+                bone_index,
+            } => write!(
+                f,
+                "Bone {bone_name} at index {bone_index} has the same name as a previous bone."
+            ),
+            SkelValidationError::TooManyBones { bone_count } => write!(
+                f,
+                "The skel has {bone_count} bones, which exceeds the in game limit of {MAX_SKEL_BONE_COUNT}."
+            ),
+        }
+    }
+}
+
+/// A [SkelData] with no invalid or cyclic parents.
+///
+/// Problems are removed rather than rejected, so any skel can be animated.
+/// The removed problems are available from [ValidatedSkel::errors].
+#[derive(Debug, Clone)]
+pub struct ValidatedSkel<'a> {
+    skel: Cow<'a, SkelData>,
+    errors: Vec<SkelValidationError>,
+}
+
+impl<'a> ValidatedSkel<'a> {
+    /// Validates `skel` and only clones it if the bones need to be modified.
+    pub fn new(skel: &'a SkelData) -> Self {
+        let mut errors = Vec::new();
+
+        let bones = &skel.bones;
+        let bone_count = bones.len();
+
+        if bone_count > MAX_SKEL_BONE_COUNT {
+            errors.push(SkelValidationError::TooManyBones { bone_count });
+        }
+
+        let mut names = HashSet::new();
+        for (i, bone) in bones.iter().enumerate() {
+            if !names.insert(bone.name.as_str()) {
+                errors.push(SkelValidationError::DuplicateBoneName {
+                    bone_name: bone.name.clone(),
+                    bone_index: i,
+                });
+            }
+        }
+
+        let mut parent_indices: Vec<_> = bones
+            .iter()
+            .map(|bone| match bone.parent_index {
+                Some(parent_index) if parent_index >= bone_count => {
+                    errors.push(SkelValidationError::InvalidParentIndex {
+                        bone_name: bone.name.clone(),
+                        parent_index,
+                    });
+                    None
+                }
+                parent_index => parent_index,
+            })
+            .collect();
+
+        remove_cycles(bones, &mut parent_indices, &mut errors);
+
+        let is_modified = bones
+            .iter()
+            .zip(&parent_indices)
+            .any(|(bone, parent_index)| bone.parent_index != *parent_index);
+
+        let skel = if is_modified {
+            let mut skel = skel.clone();
+            for (bone, parent_index) in skel.bones.iter_mut().zip(parent_indices) {
+                bone.parent_index = parent_index;
+            }
+            Cow::Owned(skel)
+        } else {
+            Cow::Borrowed(skel)
+        };
+
+        Self { skel, errors }
+    }
+
+    /// The skel with any invalid parents removed.
+    pub fn skel(&self) -> &SkelData {
+        &self.skel
+    }
+
+    /// The bones of [ValidatedSkel::skel].
+    /// Every parent index refers to a bone in the skel, and no bone is its own ancestor.
+    pub fn bones(&self) -> &[BoneData] {
+        &self.skel.bones
+    }
+
+    /// The problems found in the original skel.
+    pub fn errors(&self) -> &[SkelValidationError] {
+        &self.errors
+    }
+
+    /// Converts to a [ValidatedSkel] that owns its data by cloning the skel if needed.
+    pub fn into_owned(self) -> ValidatedSkel<'static> {
+        ValidatedSkel {
+            skel: Cow::Owned(self.skel.into_owned()),
+            errors: self.errors,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    Unvisited,
+    InProgress,
+    Finished,
+}
+
+fn remove_cycles(
+    bones: &[BoneData],
+    parent_indices: &mut [Option<usize>],
+    errors: &mut Vec<SkelValidationError>,
+) {
+    // Walk up the parent chain of each bone.
+    // Reaching a bone from the current chain again means the chain contains a cycle.
+    let mut visits = vec![Visit::Unvisited; parent_indices.len()];
+    for start in 0..parent_indices.len() {
+        let mut chain = Vec::new();
+        let mut current = start;
+        while visits[current] == Visit::Unvisited {
+            visits[current] = Visit::InProgress;
+            chain.push(current);
+
+            match parent_indices[current] {
+                Some(parent_index) if visits[parent_index] == Visit::InProgress => {
+                    // Break the cycle by removing the parent that closes it.
+                    parent_indices[current] = None;
+
+                    let cycle_start = chain
+                        .iter()
+                        .position(|i| *i == parent_index)
+                        .unwrap_or_default();
+                    errors.push(SkelValidationError::ParentCycle {
+                        bone_names: chain[cycle_start..]
+                            .iter()
+                            .map(|i| bones[*i].name.clone())
+                            .collect(),
+                    });
+                }
+                Some(parent_index) => current = parent_index,
+                None => (),
+            }
+        }
+
+        for i in chain {
+            visits[i] = Visit::Finished;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ssbh_data::skel_data::BillboardType;
+
+    use super::*;
+
+    fn bone(name: &str, parent_index: Option<usize>) -> BoneData {
+        BoneData {
+            name: name.to_string(),
+            transform: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+            parent_index,
+            billboard_type: BillboardType::Disabled,
+        }
+    }
+
+    fn skel(bones: Vec<BoneData>) -> SkelData {
+        SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones,
+        }
+    }
+
+    fn parent_indices(skel: &ValidatedSkel) -> Vec<Option<usize>> {
+        skel.bones().iter().map(|b| b.parent_index).collect()
+    }
+
+    #[test]
+    fn validate_empty_skel() {
+        let skel = skel(Vec::new());
+        let validated = ValidatedSkel::new(&skel);
+        assert!(validated.errors().is_empty());
+        assert!(validated.bones().is_empty());
+    }
+
+    #[test]
+    fn validate_valid_skel() {
+        let skel = skel(vec![
+            bone("a", None),
+            bone("b", Some(0)),
+            bone("c", Some(1)),
+        ]);
+        let validated = ValidatedSkel::new(&skel);
+        assert!(validated.errors().is_empty());
+        assert!(matches!(validated.skel, Cow::Borrowed(_)));
+        assert_eq!(vec![None, Some(0), Some(1)], parent_indices(&validated));
+    }
+
+    #[test]
+    fn validate_invalid_parent_index() {
+        let skel = skel(vec![bone("a", None), bone("b", Some(2))]);
+        let validated = ValidatedSkel::new(&skel);
+        assert_eq!(
+            &[SkelValidationError::InvalidParentIndex {
+                bone_name: "b".to_string(),
+                parent_index: 2
+            }],
+            validated.errors()
+        );
+        assert_eq!(vec![None, None], parent_indices(&validated));
+    }
+
+    #[test]
+    fn validate_into_owned() {
+        let validated = {
+            let skel = skel(vec![bone("a", None), bone("b", Some(2))]);
+            ValidatedSkel::new(&skel).into_owned()
+        };
+        assert_eq!(1, validated.errors().len());
+        assert_eq!(vec![None, None], parent_indices(&validated));
+    }
+
+    #[test]
+    fn validate_self_parent() {
+        let skel = skel(vec![bone("a", Some(0))]);
+        let validated = ValidatedSkel::new(&skel);
+        assert_eq!(
+            &[SkelValidationError::ParentCycle {
+                bone_names: vec!["a".to_string()]
+            }],
+            validated.errors()
+        );
+        assert_eq!(vec![None], parent_indices(&validated));
+    }
+
+    #[test]
+    fn validate_parent_cycle() {
+        // The cycle doesn't include the first bone.
+        let skel = skel(vec![
+            bone("a", Some(1)),
+            bone("b", Some(2)),
+            bone("c", Some(3)),
+            bone("d", Some(1)),
+        ]);
+        let validated = ValidatedSkel::new(&skel);
+        assert_eq!(
+            &[SkelValidationError::ParentCycle {
+                bone_names: vec!["b".to_string(), "c".to_string(), "d".to_string()]
+            }],
+            validated.errors()
+        );
+        assert_eq!(
+            vec![Some(1), Some(2), Some(3), None],
+            parent_indices(&validated)
+        );
+    }
+
+    #[test]
+    fn validate_multiple_parent_cycles() {
+        let skel = skel(vec![
+            bone("a", Some(1)),
+            bone("b", Some(0)),
+            bone("c", Some(3)),
+            bone("d", Some(2)),
+        ]);
+        let validated = ValidatedSkel::new(&skel);
+        assert_eq!(
+            &[
+                SkelValidationError::ParentCycle {
+                    bone_names: vec!["a".to_string(), "b".to_string()]
+                },
+                SkelValidationError::ParentCycle {
+                    bone_names: vec!["c".to_string(), "d".to_string()]
+                }
+            ],
+            validated.errors()
+        );
+        assert_eq!(
+            vec![Some(1), None, Some(3), None],
+            parent_indices(&validated)
+        );
+    }
+
+    #[test]
+    fn validate_duplicate_bone_names() {
+        let skel = skel(vec![
+            bone("a", None),
+            bone("b", Some(0)),
+            bone("a", Some(0)),
+        ]);
+        let validated = ValidatedSkel::new(&skel);
+        assert_eq!(
+            &[SkelValidationError::DuplicateBoneName {
+                bone_name: "a".to_string(),
+                bone_index: 2
+            }],
+            validated.errors()
+        );
+        assert_eq!(3, validated.bones().len());
+    }
+
+    #[test]
+    fn validate_511_bones() {
+        let skel = skel(vec![bone("a", None); 511]);
+        let validated = ValidatedSkel::new(&skel);
+        assert!(!validated
+            .errors()
+            .iter()
+            .any(|e| matches!(e, SkelValidationError::TooManyBones { .. })));
+    }
+
+    #[test]
+    fn validate_600_bones() {
+        let mut bones: Vec<_> = (0..599).map(|i| bone(&i.to_string(), None)).collect();
+        bones.push(bone("599", Some(598)));
+        let skel = skel(bones);
+
+        // All bones are kept even though the game only supports 511 bones.
+        let validated = ValidatedSkel::new(&skel);
+        assert_eq!(
+            &[SkelValidationError::TooManyBones { bone_count: 600 }],
+            validated.errors()
+        );
+        assert_eq!(600, validated.bones().len());
+        assert_eq!(Some(598), validated.bones()[599].parent_index);
+    }
+}
@@ -13,6 +13,7 @@ pub struct BonePipelines {
     pub bone_pipeline: wgpu::RenderPipeline,
     pub joint_pipeline: wgpu::RenderPipeline,
     pub bone_axes_pipeline: wgpu::RenderPipeline,
+    pub motion_path_pipeline: wgpu::RenderPipeline,
 }
 
 impl BonePipelines {
@@ -20,12 +21,46 @@ impl BonePipelines {
         // TODO: Move this to bone rendering?
         let bone_pipeline = skeleton_pipeline(device, "vs_bone", "fs_main", wgpu::Face::Back);
         let joint_pipeline = skeleton_pipeline(device, "vs_joint", "fs_main", wgpu::Face::Back);
-        let bone_axes_pipeline = bone_axes_pipeline(device);
+        // TODO: Just disable the depth?
+        let bone_axes_pipeline = line_pipeline(
+            device,
+            "vs_axes",
+            Some(wgpu::DepthStencilState {
+                format: crate::renderer::DEPTH_STENCIL_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+        );
+        // Motion paths are drawn as an overlay with no depth.
+        let motion_path_pipeline = line_pipeline(device, "vs_path", None);
 
         Self {
             bone_pipeline,
             joint_pipeline,
             bone_axes_pipeline,
+            motion_path_pipeline,
+        }
+    }
+}
+
+/// Line vertices for bone trajectories with a tick at each frame.
+pub struct MotionPathBuffers {
+    pub vertex_buffer: wgpu::Buffer,
+    pub vertex_count: u32,
+}
+
+impl MotionPathBuffers {
+    pub fn new(device: &wgpu::Device, paths: &[Vec<glam::Vec3>]) -> Self {
+        let vertices = motion_path_vertices(paths);
+        Self {
+            vertex_buffer: device.create_buffer_from_data(
+                "Motion Path Vertex Buffer",
+                &vertices,
+                wgpu::BufferUsages::VERTEX,
+            ),
+            vertex_count: (vertices.len() / 2) as u32,
         }
     }
 }
@@ -66,14 +101,13 @@ impl BoneBuffers {
 }
 
 pub fn joint_transforms(skel: &SkelData, anim_transforms: &AnimationTransforms) -> Vec<glam::Mat4> {
-    let mut joint_transforms: Vec<_> = skel
-        .bones
+    skel.bones
         .iter()
-        .enumerate()
-        .map(|(i, bone)| {
+        .zip(&anim_transforms.world_transforms)
+        .map(|(bone, world_transform)| {
             // TODO: Add an option to show the bone's actual rotation?
             // TODO: The bones wont be connected and should use a different model for rendering.
-            let pos = anim_transforms.world_transforms[i].col(3).xyz();
+            let pos = world_transform.col(3).xyz();
             let parent_pos = bone
                 .parent_index
                 .and_then(|parent_index| anim_transforms.world_transforms.get(parent_index))
@@ -92,9 +126,49 @@ pub fn joint_transforms(skel: &SkelData, anim_transforms: &AnimationTransforms)
                 * glam::Mat4::from_quat(rotation)
                 * glam::Mat4::from_scale(glam::vec3(1.0, scale, 1.0))
         })
-        .collect();
-    joint_transforms.resize(crate::animation::MAX_BONE_COUNT, glam::Mat4::IDENTITY);
-    joint_transforms
+        .collect()
+}
+
+fn motion_path_vertices(paths: &[Vec<glam::Vec3>]) -> Vec<[f32; 4]> {
+    let path_color = [1.0, 0.8, 0.0, 1.0];
+    let tick_color = [1.0, 1.0, 1.0, 1.0];
+
+    // Scale the ticks with the paths since models can have very different sizes.
+    let (min, max) = paths.iter().flatten().fold(
+        (glam::Vec3::splat(f32::MAX), glam::Vec3::splat(f32::MIN)),
+        |(min, max), p| (min.min(*p), max.max(*p)),
+    );
+    let tick_size = if max.cmpge(min).all() {
+        (max - min).max_element() * 0.01
+    } else {
+        0.0
+    };
+
+    // Pos0 Color0 Pos1 Color1 ...
+    let mut vertices = Vec::new();
+    for path in paths {
+        for segment in path.windows(2) {
+            vertices.extend([
+                segment[0].extend(1.0).to_array(),
+                path_color,
+                segment[1].extend(1.0).to_array(),
+                path_color,
+            ]);
+        }
+
+        // Mark each frame with a small cross.
+        for position in path {
+            for axis in [glam::Vec3::X, glam::Vec3::Y, glam::Vec3::Z] {
+                vertices.extend([
+                    (*position - axis * tick_size).extend(1.0).to_array(),
+                    tick_color,
+                    (*position + axis * tick_size).extend(1.0).to_array(),
+                    tick_color,
+                ]);
+            }
+        }
+    }
+    vertices
 }
 
 pub fn bone_axes_index_count() -> usize {
@@ -117,7 +191,7 @@ pub fn bone_colors_buffer(
     device.create_buffer_from_data(
         "Bone Colors Buffer",
         &bone_colors(skel, hlpb),
-        wgpu::BufferUsages::UNIFORM,
+        wgpu::BufferUsages::STORAGE,
     )
 }
 
@@ -247,7 +321,9 @@ fn bone_colors(skel: Option<&SkelData>, hlpb: Option<&HlpbData>) -> Vec<[f32; 4]
     let helper_color = [0.3, 0.0, 0.6, 1.0];
     let default_color = [0.65, 0.65, 0.65, 1.0];
 
-    let mut colors = vec![[0.0; 4]; crate::animation::MAX_BONE_COUNT];
+    // Empty buffers can't be bound, so always include at least one color.
+    let bone_count = skel.map(|s| s.bones.len()).unwrap_or_default();
+    let mut colors = vec![[0.0; 4]; bone_count.max(1)];
     if let Some(skel) = skel {
         for (i, bone) in skel.bones.iter().enumerate() {
             colors[i] = default_color;
@@ -308,7 +384,11 @@ fn skeleton_pipeline(
     })
 }
 
-fn bone_axes_pipeline(device: &wgpu::Device) -> wgpu::RenderPipeline {
+fn line_pipeline(
+    device: &wgpu::Device,
+    vertex_entry: &str,
+    depth_stencil: Option<wgpu::DepthStencilState>,
+) -> wgpu::RenderPipeline {
     let shader = crate::shader::skeleton::create_shader_module(device);
     let layout = crate::shader::skeleton::create_pipeline_layout(device);
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
@@ -316,7 +396,7 @@ fn bone_axes_pipeline(device: &wgpu::Device) -> wgpu::RenderPipeline {
         layout: Some(&layout),
         vertex: wgpu::VertexState {
             module: &shader,
-            entry_point: "vs_axes",
+            entry_point: vertex_entry,
             buffers: &[crate::shader::skeleton::VertexInput::vertex_buffer_layout(
                 wgpu::VertexStepMode::Vertex,
             )],
@@ -330,14 +410,8 @@ fn bone_axes_pipeline(device: &wgpu::Device) -> wgpu::RenderPipeline {
             polygon_mode: wgpu::PolygonMode::Line,
             topology: wgpu::PrimitiveTopology::LineList,
             ..Default::default()
-        }, // TODO: Just disable the depth?
-        depth_stencil: Some(wgpu::DepthStencilState {
-            format: crate::renderer::DEPTH_STENCIL_FORMAT,
-            depth_write_enabled: true,
-            depth_compare: wgpu::CompareFunction::LessEqual,
-            stencil: wgpu::StencilState::default(),
-            bias: wgpu::DepthBiasState::default(),
-        }),
+        },
+        depth_stencil,
         multisample: wgpu::MultisampleState::default(),
         multiview: None,
     })
@@ -0,0 +1,162 @@
+use glam::Vec4Swizzles;
+
+/// A sphere containing all the points of some geometry.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct BoundingSphere {
+    pub center: glam::Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// Calculates a sphere containing all `points` or `None` if there are no points.
+    ///
+    /// The sphere is centered on the axis aligned bounding box of the points.
+    /// This isn't the smallest possible sphere but is fast to compute.
+    pub fn from_points(points: &[glam::Vec3]) -> Option<Self> {
+        let min = points.iter().copied().reduce(glam::Vec3::min)?;
+        let max = points.iter().copied().reduce(glam::Vec3::max)?;
+
+        let center = (min + max) / 2.0;
+        let radius = points
+            .iter()
+            .map(|p| p.distance(center))
+            .fold(0.0, f32::max);
+
+        Some(Self { center, radius })
+    }
+
+    /// Calculates a sphere containing all `spheres` or `None` if there are no spheres.
+    pub fn from_spheres(spheres: impl IntoIterator<Item = BoundingSphere>) -> Option<Self> {
+        spheres.into_iter().reduce(|a, b| a.union(&b))
+    }
+
+    /// The smallest sphere containing both `self` and `other`.
+    pub fn union(&self, other: &BoundingSphere) -> Self {
+        let offset = other.center - self.center;
+        let distance = offset.length();
+
+        if distance + other.radius <= self.radius {
+            // other is inside self.
+            *self
+        } else if distance + self.radius <= other.radius {
+            // self is inside other.
+            *other
+        } else {
+            let radius = (distance + self.radius + other.radius) / 2.0;
+            let center = self.center + offset / distance * (radius - self.radius);
+            Self { center, radius }
+        }
+    }
+
+    /// Transforms the sphere by `transform`.
+    /// The radius is scaled by the largest scale of `transform` to still contain the geometry.
+    pub fn transform(&self, transform: glam::Mat4) -> Self {
+        let max_scale = transform
+            .x_axis
+            .xyz()
+            .length()
+            .max(transform.y_axis.xyz().length())
+            .max(transform.z_axis.xyz().length());
+
+        Self {
+            center: transform.transform_point3(self.center),
+            radius: self.radius * max_scale,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_vector_relative_eq;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn from_points_empty() {
+        assert_eq!(None, BoundingSphere::from_points(&[]));
+    }
+
+    #[test]
+    fn from_points_single_point() {
+        assert_eq!(
+            Some(BoundingSphere {
+                center: glam::vec3(1.0, 2.0, 3.0),
+                radius: 0.0
+            }),
+            BoundingSphere::from_points(&[glam::vec3(1.0, 2.0, 3.0)])
+        );
+    }
+
+    #[test]
+    fn from_points_multiple_points() {
+        let sphere = BoundingSphere::from_points(&[
+            glam::vec3(-1.0, 0.0, 0.0),
+            glam::vec3(3.0, 0.0, 0.0),
+            glam::vec3(1.0, 1.0, 0.0),
+        ])
+        .unwrap();
+        assert_vector_relative_eq!([1.0, 0.5, 0.0], sphere.center.to_array());
+        assert_relative_eq!(4.25f32.sqrt(), sphere.radius);
+    }
+
+    #[test]
+    fn from_spheres_empty() {
+        assert_eq!(None, BoundingSphere::from_spheres(std::iter::empty()));
+    }
+
+    #[test]
+    fn union_disjoint() {
+        let a = BoundingSphere {
+            center: glam::vec3(-2.0, 0.0, 0.0),
+            radius: 1.0,
+        };
+        let b = BoundingSphere {
+            center: glam::vec3(2.0, 0.0, 0.0),
+            radius: 1.0,
+        };
+        let sphere = a.union(&b);
+        assert_vector_relative_eq!([0.0, 0.0, 0.0], sphere.center.to_array());
+        assert_relative_eq!(3.0, sphere.radius);
+    }
+
+    #[test]
+    fn union_contained() {
+        let a = BoundingSphere {
+            center: glam::Vec3::ZERO,
+            radius: 5.0,
+        };
+        let b = BoundingSphere {
+            center: glam::vec3(1.0, 0.0, 0.0),
+            radius: 1.0,
+        };
+        assert_eq!(a, a.union(&b));
+        assert_eq!(a, b.union(&a));
+    }
+
+    #[test]
+    fn union_same_center() {
+        let a = BoundingSphere {
+            center: glam::Vec3::ONE,
+            radius: 1.0,
+        };
+        let b = BoundingSphere {
+            center: glam::Vec3::ONE,
+            radius: 2.0,
+        };
+        assert_eq!(b, a.union(&b));
+    }
+
+    #[test]
+    fn transform_translate_scale() {
+        let sphere = BoundingSphere {
+            center: glam::vec3(1.0, 0.0, 0.0),
+            radius: 1.0,
+        }
+        .transform(
+            glam::Mat4::from_translation(glam::vec3(0.0, 1.0, 0.0))
+                * glam::Mat4::from_scale(glam::vec3(1.0, 3.0, 2.0)),
+        );
+        assert_vector_relative_eq!([1.0, 1.0, 0.0], sphere.center.to_array());
+        assert_relative_eq!(3.0, sphere.radius);
+    }
+}
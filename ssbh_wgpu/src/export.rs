@@ -0,0 +1,281 @@
+use std::{error::Error, fs::File, path::Path};
+
+use image::{codecs::gif::GifEncoder, Delay, Frame, RgbaImage};
+use ssbh_data::anim_data::AnimData;
+
+use crate::{
+    viewport::OrbitCamera, ModelFolder, ModelRenderOptions, OffscreenTarget, RenderModel,
+    SharedRenderData, SsbhRenderer,
+};
+
+/// The camera used for each frame of a [FrameSequence].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SequenceCamera {
+    /// Use the camera last set with [SsbhRenderer::update_camera] for all frames.
+    Fixed,
+    /// Orbit the camera a full 360 degrees about the Y-axis over the sequence
+    /// starting from the given camera.
+    Turntable(OrbitCamera),
+}
+
+/// The frames and camera for [render_frame_sequence].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FrameSequence {
+    /// The animation frame for the first image.
+    pub start_frame: f32,
+    /// The number of images to render.
+    pub frame_count: usize,
+    /// The number of animation frames to advance between images.
+    pub frame_step: f32,
+    /// Wrap around to the start after reaching the end of the animations.
+    pub should_loop: bool,
+    pub camera: SequenceCamera,
+}
+
+impl Default for FrameSequence {
+    fn default() -> Self {
+        Self {
+            start_frame: 0.0,
+            frame_count: 1,
+            frame_step: 1.0,
+            should_loop: true,
+            camera: SequenceCamera::Fixed,
+        }
+    }
+}
+
+impl FrameSequence {
+    /// A sequence covering every frame of the longest animation in `anims`.
+    pub fn from_anims(anims: &[AnimData]) -> Self {
+        let final_frame_index = final_frame_index(anims);
+        Self {
+            frame_count: final_frame_index as usize + 1,
+            should_loop: false,
+            ..Default::default()
+        }
+    }
+}
+
+/// The file format for [export_frame_sequence].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FrameSequenceFormat {
+    /// Numbered images like `0000.png`, `0001.png`, ... in the output folder.
+    Png,
+    /// A single looping animated GIF at 60 fps divided by the frame step.
+    Gif,
+}
+
+/// Renders each image in `sequence` and calls `write_frame` with the image index and image.
+///
+/// Animations in `anims` are applied to each of the `render_models`
+/// using the skel, matl, and hlpb from the corresponding folder in `models`.
+/// The dimensions of `renderer` should match `target`.
+#[allow(clippy::too_many_arguments)]
+pub fn render_frame_sequence(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    renderer: &mut SsbhRenderer,
+    target: &OffscreenTarget,
+    render_models: &mut [RenderModel],
+    models: &[ModelFolder],
+    anims: &[AnimData],
+    shared_data: &SharedRenderData,
+    options: &ModelRenderOptions,
+    sequence: &FrameSequence,
+    mut write_frame: impl FnMut(usize, RgbaImage) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let final_frame_index = final_frame_index(anims);
+
+    let mut current_frame = sequence.start_frame;
+    for i in 0..sequence.frame_count {
+        if let SequenceCamera::Turntable(mut camera) = sequence.camera {
+            camera.orbit(turntable_angle(i, sequence.frame_count), 0.0);
+            renderer.update_camera(
+                queue,
+                camera.camera_transforms(target.width(), target.height(), 1.0),
+            );
+        }
+
+        if !anims.is_empty() {
+            for (render_model, model) in render_models.iter_mut().zip(models) {
+                render_model.apply_anims(
+                    queue,
+                    anims.iter(),
+                    model.find_skel(),
+                    model.find_matl(),
+                    model.find_hlpb(),
+                    shared_data,
+                    current_frame,
+                );
+                render_model.update_material_textures(device, shared_data);
+            }
+        }
+
+        let image = renderer.render_to_image(
+            device,
+            queue,
+            target,
+            render_models,
+            shared_data.database(),
+            options,
+        )?;
+        write_frame(i, image)?;
+
+        current_frame = next_sequence_frame(
+            current_frame,
+            sequence.frame_step,
+            final_frame_index,
+            sequence.should_loop,
+        );
+    }
+
+    Ok(())
+}
+
+/// Renders `sequence` with [render_frame_sequence] and saves the result to `path`.
+///
+/// For [FrameSequenceFormat::Png], `path` is the output folder and will be created if needed.
+/// For [FrameSequenceFormat::Gif], `path` is the output file.
+#[allow(clippy::too_many_arguments)]
+pub fn export_frame_sequence<P: AsRef<Path>>(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    renderer: &mut SsbhRenderer,
+    target: &OffscreenTarget,
+    render_models: &mut [RenderModel],
+    models: &[ModelFolder],
+    anims: &[AnimData],
+    shared_data: &SharedRenderData,
+    options: &ModelRenderOptions,
+    sequence: &FrameSequence,
+    format: FrameSequenceFormat,
+    path: P,
+) -> Result<(), Box<dyn Error>> {
+    let path = path.as_ref();
+    match format {
+        FrameSequenceFormat::Png => {
+            std::fs::create_dir_all(path)?;
+            render_frame_sequence(
+                device,
+                queue,
+                renderer,
+                target,
+                render_models,
+                models,
+                anims,
+                shared_data,
+                options,
+                sequence,
+                |i, image| {
+                    image.save(path.join(format!("{i:04}.png")))?;
+                    Ok(())
+                },
+            )
+        }
+        FrameSequenceFormat::Gif => {
+            let mut encoder = GifEncoder::new(File::create(path)?);
+            encoder.set_repeat(image::codecs::gif::Repeat::Infinite)?;
+
+            // GIF delays are stored in hundredths of a second.
+            let delay = Delay::from_numer_denom_ms((sequence.frame_step * 1000.0) as u32, 60);
+            render_frame_sequence(
+                device,
+                queue,
+                renderer,
+                target,
+                render_models,
+                models,
+                anims,
+                shared_data,
+                options,
+                sequence,
+                |_, image| {
+                    encoder.encode_frame(Frame::from_parts(image, 0, 0, delay))?;
+                    Ok(())
+                },
+            )
+        }
+    }
+}
+
+fn final_frame_index(anims: &[AnimData]) -> f32 {
+    anims
+        .iter()
+        .map(|a| a.final_frame_index)
+        .reduce(f32::max)
+        .unwrap_or(0.0)
+}
+
+fn next_sequence_frame(
+    current_frame: f32,
+    frame_step: f32,
+    final_frame_index: f32,
+    should_loop: bool,
+) -> f32 {
+    // Advance by exactly the frame step instead of converting to a duration for next_frame.
+    // This avoids skipping frames from rounding to whole milliseconds.
+    let next_frame = current_frame + frame_step;
+    if next_frame > final_frame_index {
+        if !should_loop {
+            final_frame_index
+        } else if final_frame_index > 0.0 {
+            next_frame.rem_euclid(final_frame_index)
+        } else {
+            0.0
+        }
+    } else {
+        next_frame
+    }
+}
+
+fn turntable_angle(index: usize, frame_count: usize) -> f32 {
+    // Don't repeat the starting angle to loop seamlessly.
+    index as f32 / frame_count.max(1) as f32 * std::f32::consts::TAU
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anim(final_frame_index: f32) -> AnimData {
+        AnimData {
+            major_version: 2,
+            minor_version: 0,
+            groups: Vec::new(),
+            final_frame_index,
+        }
+    }
+
+    #[test]
+    fn final_frame_index_no_anims() {
+        assert_eq!(0.0, final_frame_index(&[]));
+    }
+
+    #[test]
+    fn final_frame_index_longest_anim() {
+        assert_eq!(29.0, final_frame_index(&[anim(9.0), anim(29.0), anim(1.0)]));
+    }
+
+    #[test]
+    fn sequence_from_anims() {
+        let sequence = FrameSequence::from_anims(&[anim(9.0), anim(29.0)]);
+        assert_eq!(30, sequence.frame_count);
+        assert!(!sequence.should_loop);
+    }
+
+    #[test]
+    fn next_sequence_frames() {
+        assert_eq!(1.0, next_sequence_frame(0.0, 1.0, 59.0, true));
+        assert_eq!(0.5, next_sequence_frame(0.0, 0.5, 59.0, false));
+        assert_eq!(1.0, next_sequence_frame(59.0, 1.0, 59.0, true));
+        assert_eq!(59.0, next_sequence_frame(59.0, 1.0, 59.0, false));
+        assert_eq!(0.0, next_sequence_frame(0.0, 1.0, 0.0, true));
+    }
+
+    #[test]
+    fn turntable_angles() {
+        assert_eq!(0.0, turntable_angle(0, 4));
+        assert_eq!(std::f32::consts::PI, turntable_angle(2, 4));
+        assert_eq!(0.0, turntable_angle(0, 0));
+    }
+}
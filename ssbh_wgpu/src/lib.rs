@@ -2,6 +2,7 @@ use bytemuck::Pod;
 use encase::{internal::WriteInto, ShaderSize, ShaderType, StorageBuffer};
 use log::{error, info};
 use model::pipeline::PipelineData;
+use rayon::prelude::*;
 use ssbh_data::prelude::*;
 use std::{
     error::Error,
@@ -11,15 +12,19 @@ use std::{
 use walkdir::WalkDir;
 use wgpu::util::DeviceExt;
 use xmb_lib::XmbFile;
-// TODO: Use rayon to speed up load times?
 
 // TODO: Rework this public API and improve docs.
 pub use nutexb_wgpu::NutexbFile;
 
 pub mod animation;
 mod bone_rendering;
+mod bounding;
+mod export;
 mod floor_grid;
 mod model;
+mod offscreen;
+mod picking;
+mod reload;
 mod render_settings;
 mod renderer;
 mod shader;
@@ -29,18 +34,29 @@ pub mod swing;
 mod swing_rendering;
 mod texture;
 mod uniforms;
+mod validation;
 mod vertex;
 pub mod viewport;
 
-pub use model::{RenderMesh, RenderModel};
+pub use bounding::BoundingSphere;
+pub use export::{
+    export_frame_sequence, render_frame_sequence, FrameSequence, FrameSequenceFormat,
+    SequenceCamera,
+};
+pub use model::{apply_model_attachments, ModelAttachment, RenderMesh, RenderModel};
+pub use offscreen::OffscreenTarget;
+pub use picking::{PickResult, PickTarget};
+pub use reload::{ModelFolderDiff, ModelFolderWatcher};
 pub use render_settings::{
-    DebugMode, ModelRenderOptions, RenderSettings, SkinningSettings, TransitionMaterial,
+    DebugMode, ModelRenderOptions, OnionSkinOptions, RenderSettings, SkinningSettings,
+    TransitionMaterial,
 };
 pub use renderer::SsbhRenderer;
 pub use renderer::RGBA_COLOR_FORMAT;
 pub use shader::model::CameraTransforms;
 pub use shader_database::{split_param, ShaderDatabase, ShaderProgram};
 pub use texture::{create_default_textures, load_default_spec_cube};
+pub use validation::{ModelValidationError, ModelValidationReport};
 
 // TODO: Find a way to avoid using the format features for filterable f32 textures.
 /// Required WGPU features for using this library.
@@ -278,12 +294,27 @@ pub fn load_render_models<'a>(
     models: impl IntoIterator<Item = &'a ModelFolder>,
     shared_data: &SharedRenderData,
 ) -> Vec<RenderModel> {
+    load_render_models_with_reports(device, queue, models, shared_data)
+        .into_iter()
+        .map(|(model, _)| model)
+        .collect()
+}
+
+/// Loads render models like [load_render_models]
+/// and returns any problems found with the files for each model.
+pub fn load_render_models_with_reports<'a>(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    models: impl IntoIterator<Item = &'a ModelFolder>,
+    shared_data: &SharedRenderData,
+) -> Vec<(RenderModel, ModelValidationReport)> {
     let start = std::time::Instant::now();
 
-    // TODO: Find a way to efficiently parallelize render mesh creation?
+    // Textures and vertex data for each model are still prepared in parallel.
+    // Creating one model at a time limits the memory used for decoded textures.
     let render_models: Vec<_> = models
         .into_iter()
-        .map(|model| RenderModel::from_folder(device, queue, model, shared_data))
+        .map(|model| RenderModel::from_folder_with_report(device, queue, model, shared_data))
         .collect();
 
     info!(
@@ -307,6 +338,7 @@ pub fn load_model_folders<P: AsRef<Path>>(root: P) -> Vec<(PathBuf, ModelFolder)
     // The ARC paths only need a max depth of 4 for model files.
     // Examples include mario/model/body/c00 or mario_galaxy/normal/model/stc_ring_set.
     // Opening the entire fighter folder has a depth of 5 and will likely crash.
+    // The files in each folder are already read in parallel.
     let models: Vec<_> = WalkDir::new(root)
         .max_depth(4)
         .into_iter()
@@ -326,20 +358,27 @@ pub fn load_model_folders<P: AsRef<Path>>(root: P) -> Vec<(PathBuf, ModelFolder)
 
 fn read_files<T, F>(files: &[PathBuf], extension: &str, read_t: F) -> ModelFiles<T>
 where
-    F: Fn(PathBuf) -> Result<T, Box<dyn Error>>,
+    T: Send,
+    F: Fn(PathBuf) -> Result<T, Box<dyn Error>> + Sync,
 {
-    files
-        .iter()
+    // Box<dyn Error> can't be sent between threads, so only the message is preserved.
+    let results: Vec<(String, Result<T, String>)> = files
+        .par_iter()
         .filter(|p| p.extension().and_then(|p| p.to_str()) == Some(extension))
         .filter_map(|p| {
             Some((
                 p.file_name()?.to_string_lossy().to_string(),
                 read_t(p.clone()).map_err(|e| {
                     error!("Error reading {:?}: {}", p, e);
-                    e
+                    e.to_string()
                 }),
             ))
         })
+        .collect();
+
+    results
+        .into_iter()
+        .map(|(name, result)| (name, result.map_err(Into::into)))
         .collect()
 }
 
@@ -1,34 +1,51 @@
 use crate::{
-    animation::{animate_materials, animate_skel, animate_visibility, AnimationTransforms},
+    animation::{
+        animate_skel_with_overrides, animate_visibility, apply_material_animation,
+        apply_two_bone_ik, bone_motion_paths, AnimatedMaterial, AnimationLayer,
+        AnimationTransforms, BoneTransforms, PoseOverride, TwoBoneIk, ValidatedSkel,
+    },
     bone_rendering::*,
+    bounding::BoundingSphere,
+    picking::{intersect_sphere, pick_joint, MeshPickingData, PickResult, PickTarget},
+    reload::ModelFolderDiff,
     shape::IndexedMeshBuffers,
-    swing::SwingPrc,
+    swing::{Simulator, SwingPrc},
     swing_rendering::{draw_swing_collisions, SwingRenderData},
+    validation::ModelValidationReport,
     vertex::MeshObjectBufferData,
-    viewport::world_to_screen,
-    ModelFolder, QueueExt, ShaderDatabase, SharedRenderData,
+    viewport::{screen_to_ray, world_to_screen},
+    CameraTransforms, ModelFolder, OnionSkinOptions, QueueExt, ShaderDatabase, SharedRenderData,
 };
 use glam::Vec4Swizzles;
 use log::{debug, info};
 use mesh_creation::{
-    material_data, MaterialData, MeshBufferAccess, MeshBuffers, RenderMeshSharedData,
+    create_texture, material_data, skinning_per_model, MaterialData, MeshBufferAccess, MeshBuffers,
+    RenderMeshSharedData,
 };
+use onion_skin::OnionSkinFrame;
 use pipeline::{pipeline, PipelineKey};
 use ssbh_data::{
     matl_data::{MatlEntryData, SamplerData},
     meshex_data::EntryFlags,
     prelude::*,
 };
-use std::collections::{HashMap, HashSet};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
 use wgpu_text::{
     font::FontRef,
     section::{BuiltInLineBreaker, Layout, Section, Text, VerticalAlign},
     TextBrush,
 };
 
+mod attachment;
 mod mesh_creation;
+mod onion_skin;
 pub mod pipeline;
 
+pub use attachment::{apply_model_attachments, ModelAttachment};
+
 pub type SamplerCache = Vec<(SamplerData, wgpu::Sampler)>;
 
 /// A renderable version of a [ModelFolder].
@@ -55,8 +72,24 @@ pub struct RenderModel {
     // TODO: The swing pipelines should be created only once in the renderer.
     swing_render_data: SwingRenderData,
 
+    swing_simulator: Option<Simulator>,
+    // Keep the PRC data to recreate the swing data when reloading the model.
+    swing_collision_prc: Option<SwingPrc>,
+    swing_simulation_prc: Option<SwingPrc>,
+
+    pose_overrides: HashMap<String, PoseOverride>,
+    ik_chains: Vec<TwoBoneIk>,
+    onion_skin_frames: Vec<OnionSkinFrame>,
+    motion_path_buffers: Option<MotionPathBuffers>,
+    transform: glam::Mat4,
+    attachment_transform: glam::Mat4,
+
     buffer_data: MeshObjectBufferData,
 
+    // Validate the skel only once when creating or reloading the model.
+    // Keep the original skel to check if animations use the same skel.
+    validated_skel: Option<(SkelData, ValidatedSkel<'static>)>,
+
     // Used for text rendering.
     animation_transforms: Box<AnimationTransforms>,
 }
@@ -88,6 +121,11 @@ pub struct RenderMesh {
     vertex_index_count: usize,
     access: MeshBufferAccess,
     attribute_names: Vec<String>,
+    // The sphere for the unanimated vertex positions.
+    bounding_sphere: Option<BoundingSphere>,
+    parent_index: i32,
+    influence_bone_indices: Vec<usize>,
+    picking_data: MeshPickingData,
 }
 
 struct BoneRenderData {
@@ -98,6 +136,37 @@ struct BoneRenderData {
     bone_bind_groups: Vec<crate::shader::skeleton::bind_groups::BindGroup2>,
 }
 
+impl RenderMesh {
+    fn animated_bounding_spheres(
+        &self,
+        transforms: &AnimationTransforms,
+        root_transform: glam::Mat4,
+    ) -> Vec<BoundingSphere> {
+        let sphere = match self.bounding_sphere {
+            Some(sphere) => sphere,
+            None => return Vec::new(),
+        };
+
+        if self.influence_bone_indices.is_empty() {
+            // Match the parenting logic in the skinning shader.
+            let transform = usize::try_from(self.parent_index)
+                .ok()
+                .and_then(|i| transforms.world_transforms.get(i))
+                .copied()
+                .unwrap_or(glam::Mat4::IDENTITY);
+            vec![sphere.transform(root_transform * transform)]
+        } else {
+            // Skinned vertices are a weighted average of the vertex transformed by each bone.
+            // The spheres transformed by each bone will contain all the skinned vertices.
+            self.influence_bone_indices
+                .iter()
+                .filter_map(|i| transforms.animated_world_transforms.transforms.get(*i))
+                .map(|t| sphere.transform(root_transform * *t))
+                .collect()
+        }
+    }
+}
+
 impl RenderModel {
     pub fn from_folder(
         device: &wgpu::Device,
@@ -105,23 +174,20 @@ impl RenderModel {
         model: &ModelFolder,
         shared_data: &SharedRenderData,
     ) -> Self {
+        Self::from_folder_with_report(device, queue, model, shared_data).0
+    }
+
+    /// Creates a render model like [RenderModel::from_folder]
+    /// and returns any problems found with the files in `model`.
+    pub fn from_folder_with_report(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        model: &ModelFolder,
+        shared_data: &SharedRenderData,
+    ) -> (Self, ModelValidationReport) {
         info!("Creating render model.");
-        // TODO: Should this use the file names in the modl itself?
         // TODO: Avoid creating the render model if there is no mesh?
-        let shared_data = RenderMeshSharedData {
-            mesh: model.find_mesh(),
-            meshex: model.find_meshex(),
-            modl: model.find_modl(),
-            skel: model.find_skel(),
-            matl: model.find_matl(),
-            adj: model.find_adj(),
-            hlpb: model.find_hlpb(),
-            model_xmb: model.find_model_xmb(),
-            nutexbs: &model.nutexbs,
-            shared_data,
-        };
-
-        shared_data.to_render_model(device, queue)
+        RenderMeshSharedData::new(model, shared_data).to_render_model(device, queue)
     }
 
     /// Finds the texture with the given `file_name`.
@@ -134,9 +200,295 @@ impl RenderModel {
             .find(|(f, _, _)| f == file_name)
             .map(|(_, t, d)| (t, d))
     }
+
+    /// Recreates only the render data affected by the files in `diff`
+    /// and returns any problems found with the files in `model`.
+    ///
+    /// The files in `model` should already be updated with [ModelFolder::reload_files].
+    /// Changes to the mesh, meshex, adj, or skel recreate everything except the textures.
+    /// Animations should be applied again after updating.
+    /// Changes to the skel also remove the paths from [RenderModel::update_motion_paths].
+    pub fn update_from_changes(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        model: &ModelFolder,
+        shared_data: &SharedRenderData,
+        diff: &ModelFolderDiff,
+    ) -> ModelValidationReport {
+        let data = RenderMeshSharedData::new(model, shared_data);
+        let mut errors = Vec::new();
+
+        // Textures are shared by all materials, so update them first.
+        for name in diff.with_extension("nutexb") {
+            self.textures.retain(|(f, _, _)| f != name);
+            if let Some((_, nutexb)) = model.nutexbs.iter().find(|(f, _)| f == name) {
+                self.textures
+                    .extend(create_texture(device, queue, name, nutexb, &mut errors));
+            }
+        }
+
+        if diff.requires_mesh_rebuild() {
+            let textures = std::mem::take(&mut self.textures);
+            let new_model = data.to_render_model_with_textures(device, textures, &mut errors);
+            let frame_offsets: Vec<_> = self.onion_skin_frames().collect();
+            self.replace_preserving_selection(new_model);
+
+            // The motion paths were evaluated with the previous skel.
+            if diff.contains("model.nusktb") {
+                self.motion_path_buffers = None;
+            }
+
+            // The swing data depends on the bone indices in the new skel.
+            let skel = model.find_skel();
+            if let Some(swing_prc) = self.swing_collision_prc.take() {
+                self.recreate_swing_collisions(device, &swing_prc, skel);
+            }
+            let gravity = self.swing_simulator.as_ref().map(|s| s.gravity);
+            let swing_simulation_prc = self.swing_simulation_prc.take();
+            self.set_swing_simulation(swing_simulation_prc.as_ref(), skel);
+            if let (Some(simulator), Some(gravity)) = (&mut self.swing_simulator, gravity) {
+                simulator.gravity = gravity;
+            }
+
+            // The onion skin buffers depend on the vertex buffers for the new meshes.
+            self.set_onion_skin_frames(device, &frame_offsets);
+            self.write_root_transform(queue);
+
+            // The new model already includes all the other changes.
+            return ModelValidationReport { errors };
+        }
+
+        if diff.requires_material_update() {
+            if let Some(modl) = data.modl {
+                self.reassign_materials(modl, data.matl);
+            }
+            let materials = data.matl.map(|m| m.entries.as_slice()).unwrap_or_default();
+            self.recreate_materials(device, materials, shared_data);
+        }
+
+        if diff.contains("model.nuhlpb") {
+            self.bone_render_data = data.create_bone_render_data(
+                device,
+                &self.animation_transforms,
+                &self.mesh_buffers,
+            );
+        }
+
+        if diff.contains("model.xmb") {
+            self.per_model_bind_group = data.per_model_bind_group(device);
+        }
+
+        let validated_skel = self.validated_skel.as_ref().map(|(_, skel)| skel);
+        errors.extend(data.validate(&self.textures, validated_skel));
+        ModelValidationReport { errors }
+    }
+
+    fn replace_preserving_selection(&mut self, new_model: RenderModel) {
+        // Only replace the render data created from the files.
+        // This preserves any state set by the user like visibility or selection.
+        let RenderModel {
+            mut meshes,
+            mesh_buffers,
+            material_data_by_label,
+            default_material_data,
+            pipelines,
+            textures,
+            per_model_bind_group,
+            bone_render_data,
+            swing_render_data,
+            buffer_data,
+            validated_skel,
+            animation_transforms,
+            ..
+        } = new_model;
+
+        for mesh in &mut meshes {
+            if let Some(old_mesh) = self
+                .meshes
+                .iter()
+                .find(|m| m.name == mesh.name && m.subindex == mesh.subindex)
+            {
+                mesh.is_visible = old_mesh.is_visible;
+                mesh.is_selected = old_mesh.is_selected;
+            }
+        }
+
+        self.meshes = meshes;
+        self.mesh_buffers = mesh_buffers;
+        self.material_data_by_label = material_data_by_label;
+        self.default_material_data = default_material_data;
+        self.pipelines = pipelines;
+        self.textures = textures;
+        self.per_model_bind_group = per_model_bind_group;
+        self.bone_render_data = bone_render_data;
+        self.swing_render_data = swing_render_data;
+        self.buffer_data = buffer_data;
+        self.validated_skel = validated_skel;
+        self.animation_transforms = animation_transforms;
+    }
 }
 
 impl RenderModel {
+    /// Calculates a sphere containing the visible meshes for the most recently applied animation frame.
+    /// Returns `None` if no visible meshes have vertices.
+    ///
+    /// The sphere is conservative and may be larger than the meshes
+    /// since the vertices are not skinned on the CPU.
+    pub fn bounding_sphere(&self) -> Option<BoundingSphere> {
+        let root_transform = self.root_transform();
+        BoundingSphere::from_spheres(
+            self.meshes.iter().filter(|m| m.is_visible).flat_map(|m| {
+                m.animated_bounding_spheres(&self.animation_transforms, root_transform)
+            }),
+        )
+    }
+
+    /// The transforms for the bone at `index` in `skel` for the most recently applied animation frame.
+    /// Returns `None` if the bone has no transforms.
+    ///
+    /// This includes any constraints, pose overrides, inverse kinematics, and swing simulation.
+    /// The `world` transform also includes [RenderModel::root_transform].
+    pub fn bone_transforms(&self, skel: &SkelData, index: usize) -> Option<BoneTransforms> {
+        self.animation_transforms
+            .bone_transforms(skel, index)
+            .map(|t| self.with_root_transform(t))
+    }
+
+    /// The transforms for the bone with name `bone_name` in `skel`
+    /// for the most recently applied animation frame.
+    /// Returns `None` if the bone is not found.
+    ///
+    /// The `world` transform also includes [RenderModel::root_transform].
+    pub fn bone_transforms_by_name(
+        &self,
+        skel: &SkelData,
+        bone_name: &str,
+    ) -> Option<BoneTransforms> {
+        self.animation_transforms
+            .bone_transforms_by_name(skel, bone_name)
+            .map(|t| self.with_root_transform(t))
+    }
+
+    fn with_root_transform(&self, transforms: BoneTransforms) -> BoneTransforms {
+        BoneTransforms {
+            world: self.root_transform() * transforms.world,
+            ..transforms
+        }
+    }
+
+    /// The model-to-world transform for positioning this model in the scene.
+    /// This defaults to the identity matrix.
+    pub fn transform(&self) -> glam::Mat4 {
+        self.transform
+    }
+
+    /// Sets the model-to-world transform for positioning this model in the scene.
+    ///
+    /// The transform applies to the meshes, shadows, bones, swing collisions, and picking.
+    /// For models attached with [apply_model_attachments],
+    /// the transform is relative to the parent bone.
+    pub fn set_transform(&mut self, queue: &wgpu::Queue, transform: glam::Mat4) {
+        self.transform = transform;
+        self.write_root_transform(queue);
+    }
+
+    /// The transform applied to the entire model after animations.
+    /// This combines any parent bone from [apply_model_attachments] with [RenderModel::transform].
+    pub fn root_transform(&self) -> glam::Mat4 {
+        self.attachment_transform * self.transform
+    }
+
+    fn set_attachment_transform(&mut self, queue: &wgpu::Queue, transform: glam::Mat4) {
+        self.attachment_transform = transform;
+        self.write_root_transform(queue);
+    }
+
+    fn write_root_transform(&self, queue: &wgpu::Queue) {
+        let per_model = skinning_per_model(self.root_transform());
+        queue.write_data(&self.mesh_buffers.per_model, &[per_model]);
+        for onion_skin_frame in &self.onion_skin_frames {
+            queue.write_data(&onion_skin_frame.mesh_buffers.per_model, &[per_model]);
+        }
+    }
+
+    /// Finds the closest visible mesh or bone under `point` in physical pixels
+    /// for the most recently applied animation frame.
+    ///
+    /// Bones are only checked if `skel` is not `None`.
+    /// Bones take priority over meshes since bones are drawn on top of the meshes.
+    pub fn pick(
+        &self,
+        skel: Option<&SkelData>,
+        point: (f32, f32),
+        camera: &CameraTransforms,
+    ) -> Option<PickResult> {
+        skel.and_then(|skel| self.pick_bone(skel, point, camera))
+            .or_else(|| self.pick_mesh(point, camera))
+    }
+
+    /// Finds the closest visible mesh under `point` in physical pixels.
+    ///
+    /// The vertices are skinned on the CPU, so this only needs to be called in response to user input.
+    pub fn pick_mesh(&self, point: (f32, f32), camera: &CameraTransforms) -> Option<PickResult> {
+        if !self.is_visible {
+            return None;
+        }
+
+        let ray = screen_to_ray(point, camera);
+        let root_transform = self.root_transform();
+        self.meshes
+            .iter()
+            .filter(|m| m.is_visible)
+            .filter(|m| {
+                // Avoid skinning meshes that can't intersect the ray.
+                m.animated_bounding_spheres(&self.animation_transforms, root_transform)
+                    .iter()
+                    .any(|s| intersect_sphere(&ray, s.center, s.radius).is_some())
+            })
+            .filter_map(|m| {
+                let distance = m.picking_data.intersect(
+                    &ray,
+                    m.parent_index,
+                    &self.animation_transforms,
+                    root_transform,
+                )?;
+                Some(PickResult {
+                    target: PickTarget::Mesh {
+                        name: m.name.clone(),
+                        subindex: m.subindex,
+                    },
+                    distance,
+                })
+            })
+            .reduce(|a, b| if b.distance < a.distance { b } else { a })
+    }
+
+    /// Finds the closest bone in `skel` under `point` in physical pixels.
+    pub fn pick_bone(
+        &self,
+        skel: &SkelData,
+        point: (f32, f32),
+        camera: &CameraTransforms,
+    ) -> Option<PickResult> {
+        if !self.is_visible {
+            return None;
+        }
+
+        let ray = screen_to_ray(point, camera);
+        let root_transform = self.root_transform();
+        let joint_transforms: Vec<_> = joint_transforms(skel, &self.animation_transforms)
+            .into_iter()
+            .map(|t| root_transform * t)
+            .collect();
+
+        let (index, distance) = pick_joint(&ray, &joint_transforms, camera.camera_pos.xyz())?;
+        Some(PickResult {
+            target: PickTarget::Bone { index },
+            distance,
+        })
+    }
+
     /// Reassign the mesh materials based on `modl`.
     /// This does not create materials that do not already exist.
     pub fn reassign_materials(&mut self, modl: &ModlData, matl: Option<&MatlData>) {
@@ -210,6 +562,7 @@ impl RenderModel {
     ///
     /// If `should_loop` is true, `frame` values less than `0.0`
     /// or greater than the max frame count for each animation will wrap around.
+    /// Call [RenderModel::update_material_textures] to apply texture changes from texture pattern animations.
     pub fn apply_anims<'a>(
         &mut self,
         queue: &wgpu::Queue,
@@ -219,43 +572,101 @@ impl RenderModel {
         hlpb: Option<&HlpbData>,
         shared_data: &SharedRenderData,
         current_frame: f32,
+    ) {
+        // TODO: Avoid allocating here?
+        let layers: Vec<_> = anims
+            .map(|anim| AnimationLayer::new(anim, current_frame))
+            .collect();
+
+        self.apply_anim_layers(queue, &layers, skel, matl, hlpb, shared_data, current_frame);
+    }
+
+    /// Apply skeletal and material animations for this model by blending each [AnimationLayer] in order.
+    /// See [AnimationLayer::cross_fade] for transitioning between animations.
+    ///
+    /// Visibility and material animations can't be blended
+    /// and only apply for layers with a weight of at least `0.5`.
+    /// The `current_frame` is used for effects that depend on time like swing simulation.
+    /// The `skel` is only validated again if it differs from the skel used to create the model.
+    /// Call [RenderModel::update_material_textures] to apply texture changes from texture pattern animations.
+    pub fn apply_anim_layers(
+        &mut self,
+        queue: &wgpu::Queue,
+        layers: &[AnimationLayer],
+        skel: Option<&SkelData>,
+        matl: Option<&MatlData>,
+        hlpb: Option<&HlpbData>,
+        shared_data: &SharedRenderData,
+        current_frame: f32,
     ) {
         // Update the buffers associated with each skel.
         // This avoids updating per mesh object and allocating new buffers.
         let start = std::time::Instant::now();
 
         // TODO: Restructure this to iterate the animations only once?
-        for anim in anims.clone() {
+        let discrete_layers = layers.iter().filter(|l| l.weight >= 0.5);
+        for layer in discrete_layers.clone() {
             // Assume final_frame_index is set to the length of the longest track.
-            animate_visibility(anim, current_frame, &mut self.meshes);
+            animate_visibility(layer.anim, layer.frame, &mut self.meshes);
+        }
 
-            if let Some(matl) = matl {
-                self.update_material_uniforms(anim, current_frame, matl, shared_data, queue);
+        if let Some(matl) = matl {
+            if discrete_layers.clone().next().is_some() {
+                self.update_material_uniforms(queue, discrete_layers, matl, shared_data);
             }
         }
 
         if let Some(skel) = skel {
-            animate_skel(
+            let validated_skel = &cached_validated_skel(&self.validated_skel, skel);
+
+            animate_skel_with_overrides(
                 &mut self.animation_transforms,
-                skel,
-                anims,
+                validated_skel,
+                layers.iter().copied(),
+                &self.pose_overrides,
                 hlpb,
-                current_frame,
             );
 
-            queue.write_data(
-                &self.mesh_buffers.skinning_transforms,
-                &[self.animation_transforms.animated_world_transforms],
-            );
+            // TODO: Avoid allocating here?
+            let ik_chains = self.model_space_ik_chains();
+            for ik in &ik_chains {
+                apply_two_bone_ik(&mut self.animation_transforms, validated_skel, ik);
+            }
 
-            queue.write_data(
-                &self.mesh_buffers.world_transforms,
-                &self.animation_transforms.world_transforms,
-            );
+            // Swing bones should also affect skinning and anything parented to them.
+            if let Some(simulator) = &mut self.swing_simulator {
+                simulator.update(&mut self.animation_transforms, current_frame);
+            }
+
+            write_mesh_buffers(queue, &self.mesh_buffers, &self.animation_transforms);
+
+            for onion_skin_frame in &self.onion_skin_frames {
+                // TODO: Avoid allocating here?
+                let mut transforms = AnimationTransforms::from_skel(validated_skel);
+                animate_skel_with_overrides(
+                    &mut transforms,
+                    validated_skel,
+                    layers.iter().map(|l| AnimationLayer {
+                        frame: l.frame + onion_skin_frame.frame_offset,
+                        ..*l
+                    }),
+                    &self.pose_overrides,
+                    hlpb,
+                );
+
+                for ik in &ik_chains {
+                    apply_two_bone_ik(&mut transforms, validated_skel, ik);
+                }
+
+                // The swing simulation depends on the previous frames,
+                // so only the current frame includes swing bone physics.
+                write_mesh_buffers(queue, &onion_skin_frame.mesh_buffers, &transforms);
+            }
 
             // TODO: Avoid allocating here?
             let joint_transforms = joint_transforms(skel, &self.animation_transforms);
-            queue.write_data(
+            write_bone_transforms(
+                queue,
                 &self.bone_render_data.joint_world_transforms,
                 &joint_transforms,
             );
@@ -270,6 +681,125 @@ impl RenderModel {
         debug!("Apply Anim: {:?}", start.elapsed());
     }
 
+    /// Manually poses the bone with name `bone_name` in [RenderModel::apply_anims]
+    /// after sampling the animations and before applying constraints.
+    /// This replaces any previous override for the bone.
+    pub fn set_pose_override(&mut self, bone_name: &str, pose_override: PoseOverride) {
+        self.pose_overrides
+            .insert(bone_name.to_string(), pose_override);
+    }
+
+    /// Removes the override for the bone with name `bone_name` and returns the previous value.
+    pub fn remove_pose_override(&mut self, bone_name: &str) -> Option<PoseOverride> {
+        self.pose_overrides.remove(bone_name)
+    }
+
+    /// Removes the overrides for all bones.
+    pub fn clear_pose_overrides(&mut self) {
+        self.pose_overrides.clear();
+    }
+
+    /// The current overrides by bone name.
+    pub fn pose_overrides(&self) -> &HashMap<String, PoseOverride> {
+        &self.pose_overrides
+    }
+
+    /// Poses each chain in `ik_chains` in order in [RenderModel::apply_anims]
+    /// after applying animations and constraints.
+    /// Set `ik_chains` to an empty slice to disable inverse kinematics.
+    ///
+    /// The target and pole vector for each chain are in world space
+    /// and account for [RenderModel::root_transform].
+    pub fn set_ik_chains(&mut self, ik_chains: &[TwoBoneIk]) {
+        self.ik_chains = ik_chains.to_vec();
+    }
+
+    /// The current chains for inverse kinematics.
+    pub fn ik_chains(&self) -> &[TwoBoneIk] {
+        &self.ik_chains
+    }
+
+    fn model_space_ik_chains(&self) -> Vec<TwoBoneIk> {
+        // Skeletons are animated without the root transform.
+        let root_transform = self.root_transform();
+        if root_transform.determinant() == 0.0 {
+            // Models with zero scale have no visible bones to pose.
+            return Vec::new();
+        }
+        let inverse = root_transform.inverse();
+
+        self.ik_chains
+            .iter()
+            .map(|ik| TwoBoneIk {
+                target: inverse.transform_point3(ik.target),
+                pole_vector: inverse.transform_vector3(ik.pole_vector),
+                ..ik.clone()
+            })
+            .collect()
+    }
+
+    /// Poses copies of the model at each of the `frame_offsets` relative to the current frame
+    /// in [RenderModel::apply_anims].
+    /// Use negative offsets for previous frames and positive offsets for next frames.
+    ///
+    /// The copies are drawn as tinted silhouettes when
+    /// [ModelRenderOptions::onion_skin](crate::ModelRenderOptions::onion_skin) is set.
+    /// Set `frame_offsets` to an empty slice to disable onion skinning for this model.
+    pub fn set_onion_skin_frames(&mut self, device: &wgpu::Device, frame_offsets: &[f32]) {
+        self.onion_skin_frames = frame_offsets
+            .iter()
+            .map(|frame_offset| {
+                OnionSkinFrame::new(
+                    device,
+                    *frame_offset,
+                    &self.meshes,
+                    &self.buffer_data,
+                    &self.animation_transforms,
+                    self.root_transform(),
+                )
+            })
+            .collect();
+    }
+
+    /// The current frame offsets for onion skinning.
+    pub fn onion_skin_frames(&self) -> impl Iterator<Item = f32> + '_ {
+        self.onion_skin_frames.iter().map(|f| f.frame_offset)
+    }
+
+    /// Evaluates `layers` for each frame from `start_frame` to `end_frame` to draw the trajectories
+    /// of the bones in `bone_names` with [SsbhRenderer::render_motion_paths](crate::SsbhRenderer::render_motion_paths).
+    /// Set `bone_names` to an empty slice to remove the paths.
+    ///
+    /// The paths include the pose overrides and IK chains like [RenderModel::apply_anim_layers].
+    /// This method should be called again any time the animations, skel, frame range,
+    /// pose overrides, or IK chains change.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_motion_paths(
+        &mut self,
+        device: &wgpu::Device,
+        layers: &[AnimationLayer],
+        skel: Option<&SkelData>,
+        hlpb: Option<&HlpbData>,
+        bone_names: &[&str],
+        start_frame: f32,
+        end_frame: f32,
+    ) {
+        let ik_chains = self.model_space_ik_chains();
+        self.motion_path_buffers = skel.filter(|_| !bone_names.is_empty()).map(|skel| {
+            let paths = bone_motion_paths(
+                &cached_validated_skel(&self.validated_skel, skel),
+                layers,
+                &self.pose_overrides,
+                &ik_chains,
+                hlpb,
+                bone_names,
+                start_frame,
+                end_frame,
+            );
+            MotionPathBuffers::new(device, &paths)
+        });
+    }
+
     /// Creates the data for rendering the collisions in `swing_prc`.
     /// This method should be called once to initialize the swing collisions
     /// and any time collisions in the PRC are added, edited, or removed.
@@ -286,22 +816,60 @@ impl RenderModel {
             skel,
             &self.animation_transforms.world_transforms,
         );
+        self.swing_collision_prc = Some(swing_prc.clone());
     }
 
-    fn update_material_uniforms(
+    /// Simulates the swing bones in `swing_prc` in [RenderModel::apply_anims].
+    /// Set `swing_prc` to `None` to only use the animated pose for swing bones.
+    ///
+    /// This method should be called again any time the PRC changes.
+    /// [RenderModel::update_from_changes] recreates the simulation if the skel changes.
+    pub fn set_swing_simulation(&mut self, swing_prc: Option<&SwingPrc>, skel: Option<&SkelData>) {
+        self.swing_simulator = swing_prc
+            .zip(skel)
+            .map(|(swing_prc, skel)| Simulator::new(swing_prc, skel));
+        self.swing_simulation_prc = swing_prc.cloned();
+    }
+
+    /// Recreates the material bind groups for any textures assigned by texture pattern animations
+    /// in the previous call to [RenderModel::apply_anims] or [RenderModel::apply_anim_layers].
+    ///
+    /// This only creates GPU resources for materials with changed textures.
+    pub fn update_material_textures(
         &mut self,
-        anim: &AnimData,
-        frame: f32,
-        matl: &MatlData,
+        device: &wgpu::Device,
         shared_data: &SharedRenderData,
+    ) {
+        for material_data in self.material_data_by_label.values_mut() {
+            material_data.update_textures(device, &self.textures, shared_data);
+        }
+    }
+
+    fn update_material_uniforms<'a>(
+        &mut self,
         queue: &wgpu::Queue,
+        layers: impl Iterator<Item = &'a AnimationLayer<'a>>,
+        matl: &MatlData,
+        shared_data: &SharedRenderData,
     ) {
         // Get a list of changed materials.
+        // Combine the layers first to avoid swapping textures more than once per frame.
         // TODO: Avoid per frame allocations here?
-        let animated_materials = animate_materials(anim, frame, &matl.entries);
+        let texture_names: Vec<_> = self.textures.iter().map(|(n, _, _)| n.as_str()).collect();
+        let mut animated_materials: Vec<_> =
+            matl.entries.iter().map(AnimatedMaterial::new).collect();
+        for layer in layers {
+            apply_material_animation(
+                layer.anim,
+                layer.frame,
+                &mut animated_materials,
+                &texture_names,
+            );
+        }
+
         for material in animated_materials {
             self.material_data_by_label
-                .entry(material.material_label.clone())
+                .entry(material.material.material_label.clone())
                 .and_modify(|material_data| {
                     material_data.update(queue, &material, &shared_data.database);
                 });
@@ -438,6 +1006,33 @@ impl RenderModel {
         }
     }
 
+    pub(crate) fn draw_motion_paths<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        camera_bind_group: &'a crate::shader::skeleton::bind_groups::BindGroup0,
+        motion_path_pipeline: &'a wgpu::RenderPipeline,
+    ) {
+        // The path vertices are in world space, so any bone bind group works.
+        if let (Some(buffers), Some(bind_group2)) = (
+            &self.motion_path_buffers,
+            self.bone_render_data.bone_bind_groups.first(),
+        ) {
+            if buffers.vertex_count > 0 {
+                render_pass.set_pipeline(motion_path_pipeline);
+                render_pass.set_vertex_buffer(0, buffers.vertex_buffer.slice(..));
+                crate::shader::skeleton::bind_groups::set_bind_groups(
+                    render_pass,
+                    crate::shader::skeleton::bind_groups::BindGroups::<'a> {
+                        bind_group0: camera_bind_group,
+                        bind_group1: &self.bone_render_data.bone_data,
+                        bind_group2,
+                    },
+                );
+                render_pass.draw(0..buffers.vertex_count, 0..1);
+            }
+        }
+    }
+
     pub(crate) fn draw_swing<'a>(
         &'a self,
         render_pass: &mut wgpu::RenderPass<'a>,
@@ -599,6 +1194,54 @@ impl RenderModel {
         active
     }
 
+    pub(crate) fn draw_onion_skin<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        per_frame_bind_group: &'a crate::shader::model::bind_groups::BindGroup0,
+        options: &OnionSkinOptions,
+    ) {
+        // Assume the pipeline is already set.
+        let max_offset = self
+            .onion_skin_frames()
+            .map(f32::abs)
+            .reduce(f32::max)
+            .unwrap_or(0.0);
+
+        for onion_skin_frame in &self.onion_skin_frames {
+            // The pipeline tints the output using the blend constant.
+            let [r, g, b] = options.frame_color(onion_skin_frame.frame_offset, max_offset);
+            render_pass.set_blend_constant(wgpu::Color {
+                r: r as f64,
+                g: g as f64,
+                b: b as f64,
+                a: 1.0,
+            });
+
+            for mesh in self
+                .meshes
+                .iter()
+                .filter(|m| m.is_visible && m.meshex_flags.draw_model && m.vertex_index_count > 0)
+            {
+                crate::shader::model::bind_groups::set_bind_groups(
+                    render_pass,
+                    crate::shader::model::bind_groups::BindGroups::<'a> {
+                        bind_group0: per_frame_bind_group,
+                        bind_group1: &self.per_model_bind_group,
+                        bind_group2: &self.default_material_data.material_uniforms_bind_group,
+                    },
+                );
+
+                self.set_mesh_buffers_with_buffer0(
+                    render_pass,
+                    mesh,
+                    &onion_skin_frame.vertex_buffer0,
+                );
+
+                render_pass.draw_indexed(0..mesh.vertex_index_count as u32, 0, 0..1);
+            }
+        }
+    }
+
     pub(crate) fn draw_meshes_uv<'a>(
         &'a self,
         render_pass: &mut wgpu::RenderPass<'a>,
@@ -625,12 +1268,14 @@ impl RenderModel {
         mvp: glam::Mat4,
         font_size: f32,
     ) {
+        let root_transform = self.root_transform();
         for (i, bone) in skel.bones.iter().enumerate() {
-            let bone_world = *self
-                .animation_transforms
-                .world_transforms
-                .get(i)
-                .unwrap_or(&glam::Mat4::IDENTITY);
+            let bone_world = root_transform
+                * *self
+                    .animation_transforms
+                    .world_transforms
+                    .get(i)
+                    .unwrap_or(&glam::Mat4::IDENTITY);
 
             let position = bone_world * glam::vec4(0.0, 0.0, 0.0, 1.0);
             let (position_x_screen, position_y_screen) =
@@ -658,9 +1303,18 @@ impl RenderModel {
     }
 
     fn set_mesh_buffers<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, mesh: &RenderMesh) {
+        self.set_mesh_buffers_with_buffer0(render_pass, mesh, &self.buffer_data.vertex_buffer0);
+    }
+
+    fn set_mesh_buffers_with_buffer0<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        mesh: &RenderMesh,
+        vertex_buffer0: &'a wgpu::Buffer,
+    ) {
         render_pass.set_vertex_buffer(
             0,
-            self.buffer_data.vertex_buffer0.slice(
+            vertex_buffer0.slice(
                 mesh.access.buffer0_start..mesh.access.buffer0_start + mesh.access.buffer0_size,
             ),
         );
@@ -703,6 +1357,50 @@ impl RenderModel {
     }
 }
 
+fn write_mesh_buffers(
+    queue: &wgpu::Queue,
+    buffers: &MeshBuffers,
+    transforms: &AnimationTransforms,
+) {
+    write_bone_transforms(
+        queue,
+        &buffers.skinning_transforms,
+        &transforms.animated_world_transforms.transforms,
+    );
+    write_bone_transforms(
+        queue,
+        &buffers.skinning_transforms_inv_transpose,
+        &transforms
+            .animated_world_transforms
+            .transforms_inv_transpose,
+    );
+    write_bone_transforms(
+        queue,
+        &buffers.world_transforms,
+        &transforms.world_transforms,
+    );
+}
+
+fn cached_validated_skel<'a>(
+    cached: &'a Option<(SkelData, ValidatedSkel<'static>)>,
+    skel: &'a SkelData,
+) -> Cow<'a, ValidatedSkel<'a>> {
+    // Only validate the skel again if it differs from the skel used to create the model.
+    match cached {
+        Some((original, validated)) if original == skel => Cow::Borrowed(validated),
+        _ => Cow::Owned(ValidatedSkel::new(skel)),
+    }
+}
+
+fn write_bone_transforms(queue: &wgpu::Queue, buffer: &wgpu::Buffer, transforms: &[glam::Mat4]) {
+    // The buffers are sized for the skel used to create the model.
+    // Ignore any additional bones to avoid writing past the end of the buffer.
+    let count = transforms
+        .len()
+        .min(buffer.size() as usize / std::mem::size_of::<glam::Mat4>());
+    queue.write_data(buffer, &transforms[..count]);
+}
+
 pub fn dispatch_renormal<'a>(meshes: &'a [RenderMesh], compute_pass: &mut wgpu::ComputePass<'a>) {
     // Assume the pipeline is already set.
     // Some meshes have a material label tag to enable the recalculating of normals.
@@ -726,6 +1424,17 @@ pub fn dispatch_renormal<'a>(meshes: &'a [RenderMesh], compute_pass: &mut wgpu::
     }
 }
 
+/// Skins the vertices for each frame in [RenderModel::set_onion_skin_frames].
+pub fn dispatch_onion_skin_skinning<'a>(
+    model: &'a RenderModel,
+    compute_pass: &mut wgpu::ComputePass<'a>,
+    bind_group3: &'a crate::shader::skinning::bind_groups::BindGroup3,
+) {
+    for onion_skin_frame in &model.onion_skin_frames {
+        onion_skin_frame.dispatch_skinning(&model.meshes, compute_pass, bind_group3);
+    }
+}
+
 pub fn dispatch_skinning<'a>(
     meshes: &'a [RenderMesh],
     compute_pass: &mut wgpu::ComputePass<'a>,
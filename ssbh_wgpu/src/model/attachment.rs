@@ -0,0 +1,194 @@
+use ssbh_data::prelude::*;
+
+use super::RenderModel;
+
+/// Attaches the root of a child [RenderModel] to a bone of a parent [RenderModel]
+/// like a weapon held in a hand.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ModelAttachment {
+    /// The index of the parent model in the render models.
+    pub parent_model_index: usize,
+    /// The index of the bone in the parent model's skel.
+    pub parent_bone_index: usize,
+    /// The index of the attached model in the render models.
+    pub child_model_index: usize,
+}
+
+impl ModelAttachment {
+    /// Attaches the child model to the bone with name `parent_bone_name` in `parent_skel`.
+    /// Returns `None` if the bone is not found.
+    pub fn new(
+        parent_model_index: usize,
+        parent_skel: &SkelData,
+        parent_bone_name: &str,
+        child_model_index: usize,
+    ) -> Option<Self> {
+        let parent_bone_index = parent_skel
+            .bones
+            .iter()
+            .position(|b| b.name == parent_bone_name)?;
+
+        Some(Self {
+            parent_model_index,
+            parent_bone_index,
+            child_model_index,
+        })
+    }
+}
+
+/// Moves the root of each child model in `attachments` to the world transform of its parent bone
+/// for the most recently applied animation frame.
+///
+/// This should be called each frame after [RenderModel::apply_anims] for all the models.
+/// Attachments are applied in order, so attach parent models before their children.
+/// Models without an attachment are positioned using only [RenderModel::transform].
+pub fn apply_model_attachments(
+    queue: &wgpu::Queue,
+    render_models: &mut [RenderModel],
+    attachments: &[ModelAttachment],
+) {
+    let world_transforms: Vec<_> = render_models
+        .iter()
+        .map(|m| m.animation_transforms.world_transforms.as_slice())
+        .collect();
+    let transforms: Vec<_> = render_models.iter().map(|m| m.transform).collect();
+
+    let attached_transforms = attachment_transforms(&world_transforms, &transforms, attachments);
+
+    for (model, transform) in render_models.iter_mut().zip(attached_transforms) {
+        model.set_attachment_transform(queue, transform);
+    }
+}
+
+// The parent bone transform applied before each model's own transform.
+fn attachment_transforms(
+    world_transforms: &[&[glam::Mat4]],
+    transforms: &[glam::Mat4],
+    attachments: &[ModelAttachment],
+) -> Vec<glam::Mat4> {
+    let mut attachment_transforms = vec![glam::Mat4::IDENTITY; world_transforms.len()];
+
+    for attachment in attachments {
+        // A model can't be attached to itself.
+        if attachment.parent_model_index == attachment.child_model_index
+            || attachment.child_model_index >= attachment_transforms.len()
+        {
+            continue;
+        }
+
+        if let Some(bone_transform) = world_transforms
+            .get(attachment.parent_model_index)
+            .and_then(|t| t.get(attachment.parent_bone_index))
+        {
+            // Children follow the parent's root transform like the parent's bones.
+            let parent_root = attachment_transforms[attachment.parent_model_index]
+                * transforms[attachment.parent_model_index];
+            attachment_transforms[attachment.child_model_index] = parent_root * *bone_transform;
+        }
+    }
+
+    attachment_transforms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assert_matrix_relative_eq;
+
+    fn attachment(parent: usize, bone: usize, child: usize) -> ModelAttachment {
+        ModelAttachment {
+            parent_model_index: parent,
+            parent_bone_index: bone,
+            child_model_index: child,
+        }
+    }
+
+    #[test]
+    fn attachment_transforms_no_attachments() {
+        let transforms = [glam::Mat4::from_translation(glam::vec3(1.0, 2.0, 3.0))];
+        assert_eq!(
+            vec![glam::Mat4::IDENTITY; 2],
+            attachment_transforms(&[&transforms, &[]], &[glam::Mat4::IDENTITY; 2], &[])
+        );
+    }
+
+    #[test]
+    fn attachment_transforms_chained_attachments() {
+        let parent = [
+            glam::Mat4::IDENTITY,
+            glam::Mat4::from_translation(glam::vec3(1.0, 2.0, 3.0)),
+        ];
+        let child = [glam::Mat4::from_scale(glam::vec3(2.0, 2.0, 2.0))];
+
+        // The grandchild follows the child's bone after the child moves.
+        let roots = attachment_transforms(
+            &[&parent, &child, &[]],
+            &[glam::Mat4::IDENTITY; 3],
+            &[attachment(0, 1, 1), attachment(1, 0, 2)],
+        );
+
+        assert_matrix_relative_eq!(
+            glam::Mat4::IDENTITY.to_cols_array_2d(),
+            roots[0].to_cols_array_2d()
+        );
+        assert_matrix_relative_eq!(
+            [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [1.0, 2.0, 3.0, 1.0]
+            ],
+            roots[1].to_cols_array_2d()
+        );
+        assert_matrix_relative_eq!(
+            [
+                [2.0, 0.0, 0.0, 0.0],
+                [0.0, 2.0, 0.0, 0.0],
+                [0.0, 0.0, 2.0, 0.0],
+                [1.0, 2.0, 3.0, 1.0]
+            ],
+            roots[2].to_cols_array_2d()
+        );
+    }
+
+    #[test]
+    fn attachment_transforms_invalid_attachments() {
+        let transforms = [glam::Mat4::from_translation(glam::vec3(1.0, 2.0, 3.0))];
+
+        let roots = attachment_transforms(
+            &[&transforms, &transforms],
+            &[glam::Mat4::IDENTITY; 2],
+            &[
+                attachment(0, 0, 0),
+                attachment(0, 5, 1),
+                attachment(2, 0, 1),
+                attachment(0, 0, 3),
+            ],
+        );
+        assert_eq!(vec![glam::Mat4::IDENTITY; 2], roots);
+    }
+
+    #[test]
+    fn attachment_transforms_parent_transform() {
+        let parent = [glam::Mat4::from_translation(glam::vec3(1.0, 2.0, 3.0))];
+        let transforms = [
+            glam::Mat4::from_translation(glam::vec3(10.0, 0.0, 0.0)),
+            glam::Mat4::from_translation(glam::vec3(0.0, 5.0, 0.0)),
+        ];
+
+        // The child's own transform is applied separately after the attachment.
+        let roots = attachment_transforms(&[&parent, &[]], &transforms, &[attachment(0, 0, 1)]);
+
+        assert_eq!(glam::Mat4::IDENTITY, roots[0]);
+        assert_matrix_relative_eq!(
+            [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [11.0, 2.0, 3.0, 1.0]
+            ],
+            roots[1].to_cols_array_2d()
+        );
+    }
+}
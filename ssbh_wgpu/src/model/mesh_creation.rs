@@ -1,22 +1,34 @@
 use super::pipeline::{pipeline, PipelineKey};
 use crate::{
-    animation::AnimationTransforms,
+    animation::{AnimatedMaterial, AnimationTransforms, ValidatedSkel},
     bone_rendering::*,
+    bounding::BoundingSphere,
     model::{BoneRenderData, SamplerCache},
+    picking::MeshPickingData,
+    shader::{
+        model::{VertexInput0, VertexInput1},
+        skinning::VertexWeight,
+    },
     swing_rendering::SwingRenderData,
     uniforms::{
-        default_material_uniforms_bind_group, default_uniforms_buffer,
-        material_uniforms_bind_group, per_material, uniforms_buffer,
+        animated_per_material, default_material_uniforms_bind_group, default_uniforms_buffer,
+        material_uniforms_bind_group, uniforms_buffer,
     },
+    validation::{validate_model, ModelValidationError, ModelValidationReport},
     vertex::{buffer0, buffer1, mesh_object_buffers, skin_weights, MeshObjectBufferData},
-    DeviceBufferExt, ModelFiles, RenderMesh, RenderModel, ShaderDatabase, SharedRenderData,
+    DeviceBufferExt, ModelFiles, ModelFolder, RenderMesh, RenderModel, ShaderDatabase,
+    SharedRenderData,
 };
 use encase::{DynamicStorageBuffer, ShaderType};
 use log::{error, info};
-use nutexb_wgpu::NutexbFile;
+use nutexb_wgpu::{NutexbFile, TextureData};
+use rayon::prelude::*;
 use ssbh_data::{
-    adj_data::AdjEntryData, matl_data::MatlEntryData, mesh_data::MeshObjectData,
-    meshex_data::EntryFlags, prelude::*,
+    adj_data::AdjEntryData,
+    matl_data::{MatlEntryData, TextureParam},
+    mesh_data::MeshObjectData,
+    meshex_data::EntryFlags,
+    prelude::*,
 };
 use std::{collections::HashMap, error::Error, num::NonZeroU64};
 use xmb_lib::XmbFile;
@@ -24,26 +36,54 @@ use xmb_lib::XmbFile;
 pub struct MaterialData {
     pub material_uniforms_bind_group: crate::shader::model::bind_groups::BindGroup2,
     pub uniforms_buffer: wgpu::Buffer,
+    // The texture assignments for the current bind group.
+    textures: Vec<TextureParam>,
+    // The animated material if its textures differ from the current bind group.
+    changed_textures_material: Option<MatlEntryData>,
 }
 
 impl MaterialData {
     pub fn update(
         &mut self,
         queue: &wgpu::Queue,
-        material: &MatlEntryData,
+        material: &AnimatedMaterial,
         database: &ShaderDatabase,
     ) {
-        // Material animations don't assign textures.
-        // We only need to update the material parameter buffer.
-        // This avoids creating GPU resources each frame.
-        let uniforms = per_material(material, database);
+        // Texture pattern animations can assign different textures.
+        // Creating the bind group is deferred to update_textures since it requires the device.
+        self.changed_textures_material =
+            (material.material.textures != self.textures).then(|| material.material.clone());
+
+        let uniforms = animated_per_material(material, database);
         queue.write_buffer(&self.uniforms_buffer, 0, bytemuck::cast_slice(&[uniforms]));
     }
+
+    pub fn update_textures(
+        &mut self,
+        device: &wgpu::Device,
+        textures: &[(String, wgpu::Texture, wgpu::TextureViewDimension)],
+        shared_data: &SharedRenderData,
+    ) {
+        // Avoid creating GPU resources each frame if the textures are unchanged.
+        if let Some(material) = self.changed_textures_material.take() {
+            self.material_uniforms_bind_group = material_uniforms_bind_group(
+                &material,
+                device,
+                textures,
+                &shared_data.default_textures,
+                &self.uniforms_buffer,
+                &mut Vec::new(),
+            );
+            self.textures = material.textures;
+        }
+    }
 }
 
 pub struct MeshBuffers {
     pub skinning_transforms: wgpu::Buffer,
+    pub skinning_transforms_inv_transpose: wgpu::Buffer,
     pub world_transforms: wgpu::Buffer,
+    pub per_model: wgpu::Buffer,
 }
 
 struct RenderMeshData {
@@ -54,6 +94,125 @@ struct RenderMeshData {
     buffer_data: MeshObjectBufferData,
 }
 
+/// The CPU side data for a model that can be prepared in parallel before creating GPU resources.
+pub struct PreparedModelData {
+    textures: Vec<(String, Result<TextureData, String>)>,
+    mesh_objects: Vec<Result<MeshObjectVertexData, String>>,
+}
+
+/// References to the files used to create [PreparedModelData].
+///
+/// Unlike [ModelFolder], this can be sent to other threads.
+pub struct PrepareModelFiles<'a> {
+    mesh: Option<&'a MeshData>,
+    skel: Option<&'a SkelData>,
+    nutexbs: Vec<(&'a str, Result<&'a NutexbFile, String>)>,
+}
+
+impl<'a> PrepareModelFiles<'a> {
+    /// Deswizzles the textures and converts the vertex data in parallel.
+    pub fn prepare(self) -> PreparedModelData {
+        let (textures, mesh_objects) = rayon::join(
+            || {
+                self.nutexbs
+                    .into_par_iter()
+                    .map(|(name, nutexb)| {
+                        let data = nutexb.and_then(|nutexb| {
+                            TextureData::from_nutexb(nutexb).map_err(|e| e.to_string())
+                        });
+                        (name.to_string(), data)
+                    })
+                    .collect()
+            },
+            || mesh_objects_vertex_data(self.mesh, self.skel),
+        );
+
+        PreparedModelData {
+            textures,
+            mesh_objects,
+        }
+    }
+}
+
+// The converted vertex data for a single mesh object.
+struct MeshObjectVertexData {
+    buffer0: Vec<VertexInput0>,
+    buffer1: Vec<VertexInput1>,
+    skin_weights: Vec<VertexWeight>,
+    positions: Vec<glam::Vec3>,
+}
+
+fn mesh_objects_vertex_data(
+    mesh: Option<&MeshData>,
+    skel: Option<&SkelData>,
+) -> Vec<Result<MeshObjectVertexData, String>> {
+    mesh.map(|mesh| {
+        mesh.objects
+            .par_iter()
+            .map(|mesh_object| {
+                mesh_object_vertex_data(mesh_object, skel).map_err(|e| {
+                    error!(
+                        "Error accessing vertex data for mesh {}: {}",
+                        mesh_object.name, e
+                    );
+                    e.to_string()
+                })
+            })
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+// Pair the successfully converted vertex data with the index of its mesh object.
+// The index is preserved since some files like the adj refer to mesh objects by index.
+fn valid_mesh_objects<'a>(
+    objects: &'a [MeshObjectData],
+    mesh_objects: Vec<Result<MeshObjectVertexData, String>>,
+    errors: &mut Vec<ModelValidationError>,
+) -> Vec<(usize, &'a MeshObjectData, MeshObjectVertexData)> {
+    objects
+        .iter()
+        .zip(mesh_objects)
+        .enumerate()
+        .filter_map(|(i, (mesh_object, vertex_data))| match vertex_data {
+            Ok(vertex_data) => Some((i, mesh_object, vertex_data)),
+            Err(message) => {
+                errors.push(ModelValidationError::InvalidVertexData {
+                    mesh_name: mesh_object.name.clone(),
+                    subindex: mesh_object.subindex,
+                    message,
+                });
+                None
+            }
+        })
+        .collect()
+}
+
+fn mesh_object_vertex_data(
+    mesh_object: &MeshObjectData,
+    skel: Option<&SkelData>,
+) -> Result<MeshObjectVertexData, ssbh_data::mesh_data::error::Error> {
+    // Keep the unskinned positions on the CPU for bounding spheres and picking.
+    let positions = mesh_object
+        .positions
+        .first()
+        .map(|a| {
+            a.data
+                .to_vec4_with_w(1.0)
+                .into_iter()
+                .map(|[x, y, z, _]| glam::vec3(x, y, z))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(MeshObjectVertexData {
+        buffer0: buffer0(mesh_object)?,
+        buffer1: buffer1(mesh_object)?,
+        skin_weights: skin_weights(mesh_object, skel)?,
+        positions,
+    })
+}
+
 // TODO: Come up with a better name.
 pub struct RenderMeshSharedData<'a> {
     pub shared_data: &'a SharedRenderData,
@@ -69,7 +228,87 @@ pub struct RenderMeshSharedData<'a> {
 }
 
 impl<'a> RenderMeshSharedData<'a> {
-    pub fn to_render_model(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> RenderModel {
+    pub fn new(model: &'a ModelFolder, shared_data: &'a SharedRenderData) -> Self {
+        // TODO: Should this use the file names in the modl itself?
+        Self {
+            mesh: model.find_mesh(),
+            meshex: model.find_meshex(),
+            modl: model.find_modl(),
+            skel: model.find_skel(),
+            matl: model.find_matl(),
+            adj: model.find_adj(),
+            hlpb: model.find_hlpb(),
+            model_xmb: model.find_model_xmb(),
+            nutexbs: &model.nutexbs,
+            shared_data,
+        }
+    }
+
+    pub fn prepare_files(&self) -> PrepareModelFiles<'a> {
+        PrepareModelFiles {
+            mesh: self.mesh,
+            skel: self.skel,
+            // Box<dyn Error> can't be shared between threads.
+            nutexbs: self
+                .nutexbs
+                .iter()
+                .map(|(name, nutexb)| {
+                    (
+                        name.as_str(),
+                        nutexb.as_ref().map_err(|e| {
+                            error!("Failed to read nutexb file {}: {}", name, e);
+                            e.to_string()
+                        }),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_render_model(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> (RenderModel, ModelValidationReport) {
+        let prepared = self.prepare_files().prepare();
+        self.to_render_model_from_prepared(device, queue, prepared)
+    }
+
+    /// Creates the GPU resources for `prepared` on the current thread.
+    pub fn to_render_model_from_prepared(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        prepared: PreparedModelData,
+    ) -> (RenderModel, ModelValidationReport) {
+        // Initialize textures exactly once for performance.
+        // Unused textures are rare, so we won't lazy load them.
+        let mut errors = Vec::new();
+        let textures = create_textures(device, queue, prepared.textures, &mut errors);
+
+        let render_model =
+            self.create_render_model(device, textures, prepared.mesh_objects, &mut errors);
+        (render_model, ModelValidationReport { errors })
+    }
+
+    /// Creates everything except the textures, which are often the slowest part.
+    pub fn to_render_model_with_textures(
+        &self,
+        device: &wgpu::Device,
+        textures: Vec<(String, wgpu::Texture, wgpu::TextureViewDimension)>,
+        errors: &mut Vec<ModelValidationError>,
+    ) -> RenderModel {
+        let mesh_objects = mesh_objects_vertex_data(self.mesh, self.skel);
+        self.create_render_model(device, textures, mesh_objects, errors)
+    }
+
+    fn create_render_model(
+        &self,
+        device: &wgpu::Device,
+        textures: Vec<(String, wgpu::Texture, wgpu::TextureViewDimension)>,
+        mesh_objects: Vec<Result<MeshObjectVertexData, String>>,
+        errors: &mut Vec<ModelValidationError>,
+    ) -> RenderModel {
         let start = std::time::Instant::now();
 
         // Attempt to initialize transforms using the skel.
@@ -77,52 +316,68 @@ impl<'a> RenderMeshSharedData<'a> {
         // Otherwise, don't apply any transformations.
         // TODO: Is it worth matching the in game behavior for a missing skel?
         // "Invisible" models might be more confusing for users to understand.
-        let animation_transforms = self
+        let validated_skel = self
             .skel
-            .map(AnimationTransforms::from_skel)
+            .map(|skel| (skel.clone(), ValidatedSkel::new(skel).into_owned()));
+        let animation_transforms = validated_skel
+            .as_ref()
+            .map(|(_, skel)| AnimationTransforms::from_skel(skel))
             .unwrap_or_else(AnimationTransforms::identity);
 
-        // Share the transforms buffer to avoid redundant updates.
-        let skinning_transforms_buffer = device.create_buffer_from_data(
+        // Share the transforms buffers to avoid redundant updates.
+        let skinning_transforms = bone_transforms_buffer(
+            device,
             "Bone Transforms Buffer",
-            &[animation_transforms.animated_world_transforms],
-            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            &animation_transforms.animated_world_transforms.transforms,
+        );
+
+        let skinning_transforms_inv_transpose = bone_transforms_buffer(
+            device,
+            "Bone Transforms Inv Transpose Buffer",
+            &animation_transforms
+                .animated_world_transforms
+                .transforms_inv_transpose,
         );
 
-        let world_transforms = device.create_buffer_from_data(
+        let world_transforms = bone_transforms_buffer(
+            device,
             "World Transforms Buffer",
             &animation_transforms.world_transforms,
-            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         );
 
-        let swing_render_data = SwingRenderData::new(device, &world_transforms);
-
-        let bone_render_data =
-            self.create_bone_render_data(device, &animation_transforms, &world_transforms);
-
         let mesh_buffers = MeshBuffers {
-            skinning_transforms: skinning_transforms_buffer,
+            skinning_transforms,
+            skinning_transforms_inv_transpose,
             world_transforms,
+            per_model: skinning_per_model_buffer(
+                device,
+                "Skinning PerModel Buffer",
+                glam::Mat4::IDENTITY,
+            ),
         };
 
-        let default_material_data = default_material_data(device, self.shared_data);
-
-        let per_model_buffer = self.per_model_buffer(device);
-
-        let per_model_bind_group = crate::shader::model::bind_groups::BindGroup1::from_bindings(
+        let swing_render_data = SwingRenderData::new(
             device,
-            crate::shader::model::bind_groups::BindGroupLayout1 {
-                per_model: per_model_buffer.as_entire_buffer_binding(),
-            },
+            &mesh_buffers.world_transforms,
+            &mesh_buffers.per_model,
         );
 
+        let bone_render_data =
+            self.create_bone_render_data(device, &animation_transforms, &mesh_buffers);
+
+        let default_material_data = default_material_data(device, self.shared_data);
+
+        let per_model_bind_group = self.per_model_bind_group(device);
+
         let RenderMeshData {
             meshes,
             material_data_by_label,
             textures,
             pipelines,
             buffer_data,
-        } = self.create_render_mesh_data(device, queue, &mesh_buffers);
+        } = self.create_render_mesh_data(device, textures, mesh_objects, &mesh_buffers, errors);
+
+        errors.extend(self.validate(&textures, validated_skel.as_ref().map(|(_, s)| s)));
 
         info!(
             "Created {:?} render meshe(s), {:?} material(s), {:?} pipeline(s): {:?}",
@@ -145,10 +400,57 @@ impl<'a> RenderMeshSharedData<'a> {
             buffer_data,
             animation_transforms: Box::new(animation_transforms),
             swing_render_data,
+            swing_simulator: None,
+            swing_collision_prc: None,
+            swing_simulation_prc: None,
+            pose_overrides: HashMap::new(),
+            ik_chains: Vec::new(),
+            onion_skin_frames: Vec::new(),
+            motion_path_buffers: None,
+            transform: glam::Mat4::IDENTITY,
+            attachment_transform: glam::Mat4::IDENTITY,
+            validated_skel,
             per_model_bind_group,
         }
     }
 
+    /// Finds errors in the model files using the texture names and dimensions in `textures`.
+    pub fn validate(
+        &self,
+        textures: &[(String, wgpu::Texture, wgpu::TextureViewDimension)],
+        skel: Option<&ValidatedSkel>,
+    ) -> Vec<ModelValidationError> {
+        // Check the textures that were actually created.
+        let texture_dimensions: Vec<_> = textures
+            .iter()
+            .chain(&self.shared_data.default_textures)
+            .map(|(name, _, dimension)| (name.clone(), *dimension))
+            .collect();
+        validate_model(
+            self.mesh,
+            skel,
+            self.hlpb,
+            self.matl,
+            self.modl,
+            &texture_dimensions,
+            &self.shared_data.database,
+        )
+    }
+
+    pub fn per_model_bind_group(
+        &self,
+        device: &wgpu::Device,
+    ) -> crate::shader::model::bind_groups::BindGroup1 {
+        let per_model_buffer = self.per_model_buffer(device);
+
+        crate::shader::model::bind_groups::BindGroup1::from_bindings(
+            device,
+            crate::shader::model::bind_groups::BindGroupLayout1 {
+                per_model: per_model_buffer.as_entire_buffer_binding(),
+            },
+        )
+    }
+
     fn per_model_buffer(&self, device: &wgpu::Device) -> wgpu::Buffer {
         // Get light set information from the model.xmb.
         // We don't initialize the light data itself here.
@@ -175,27 +477,35 @@ impl<'a> RenderMeshSharedData<'a> {
         )
     }
 
-    fn create_bone_render_data(
+    pub fn create_bone_render_data(
         &self,
         device: &wgpu::Device,
         animation_transforms: &AnimationTransforms,
-        world_transforms: &wgpu::Buffer,
+        mesh_buffers: &MeshBuffers,
     ) -> BoneRenderData {
         let bone_colors = bone_colors_buffer(device, self.skel, self.hlpb);
 
         let joint_transforms = self
             .skel
             .map(|skel| joint_transforms(skel, animation_transforms))
-            .unwrap_or_else(|| vec![glam::Mat4::IDENTITY; 512]);
+            .unwrap_or_default();
 
-        let joint_world_transforms = device.create_buffer_from_data(
-            "Joint World Transforms Buffer",
-            &joint_transforms,
-            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        );
+        let joint_world_transforms =
+            bone_transforms_buffer(device, "Joint World Transforms Buffer", &joint_transforms);
 
-        let bone_data = bone_bind_group1(device, world_transforms, &bone_colors);
-        let joint_data = bone_bind_group1(device, &joint_world_transforms, &bone_colors);
+        // The model transform is applied to bones and joints in the shader.
+        let bone_data = bone_bind_group1(
+            device,
+            &mesh_buffers.world_transforms,
+            &bone_colors,
+            &mesh_buffers.per_model,
+        );
+        let joint_data = bone_bind_group1(
+            device,
+            &joint_world_transforms,
+            &bone_colors,
+            &mesh_buffers.per_model,
+        );
         let bone_bind_groups = bone_bind_groups(device, self.skel);
 
         BoneRenderData {
@@ -209,15 +519,13 @@ impl<'a> RenderMeshSharedData<'a> {
     fn create_render_mesh_data(
         &self,
         device: &wgpu::Device,
-        queue: &wgpu::Queue,
+        textures: Vec<(String, wgpu::Texture, wgpu::TextureViewDimension)>,
+        mesh_objects: Vec<Result<MeshObjectVertexData, String>>,
         mesh_buffers: &MeshBuffers,
+        errors: &mut Vec<ModelValidationError>,
     ) -> RenderMeshData {
         // TODO: Find a way to organize this.
 
-        // Initialize textures exactly once for performance.
-        // Unused textures are rare, so we won't lazy load them.
-        let textures = self.create_textures(device, queue);
-
         // Materials can be shared between mesh objects.
         let material_data_by_label = self.create_materials(device, &textures);
 
@@ -228,32 +536,23 @@ impl<'a> RenderMeshSharedData<'a> {
 
         let mut model_indices = Vec::new();
 
-        let mut accesses = Vec::new();
-
-        // TODO: Refactor this to use iterators.
-        if let Some(mesh) = self.mesh.as_ref() {
-            for mesh_object in &mesh.objects {
+        // The vertex data is converted in parallel ahead of time.
+        let objects = self.mesh.map(|m| m.objects.as_slice()).unwrap_or_default();
+        let accesses: Vec<_> = valid_mesh_objects(objects, mesh_objects, errors)
+            .into_iter()
+            .map(|(i, mesh_object, vertex_data)| {
                 // TODO: Find a way to have fewer function parameters?
-                match append_mesh_object_buffer_data(
+                let access = append_mesh_object_buffer_data(
                     &mut model_buffer0_data,
                     &mut model_buffer1_data,
                     &mut model_skin_weights_data,
                     &mut model_indices,
                     mesh_object,
-                    self,
-                ) {
-                    Ok(access) => {
-                        accesses.push(access);
-                    }
-                    Err(e) => {
-                        error!(
-                            "Error accessing vertex data for mesh {}: {}",
-                            mesh_object.name, e
-                        );
-                    }
-                }
-            }
-        }
+                    &vertex_data,
+                );
+                (i, mesh_object, access, vertex_data)
+            })
+            .collect();
 
         let buffer_data = mesh_object_buffers(
             device,
@@ -268,9 +567,8 @@ impl<'a> RenderMeshSharedData<'a> {
         // Cache materials separately since materials may share a pipeline.
         let mut pipelines = HashMap::new();
 
-        let meshes = self
-            .create_render_meshes(accesses, device, &mut pipelines, mesh_buffers, &buffer_data)
-            .unwrap_or_default();
+        let meshes =
+            self.create_render_meshes(accesses, device, &mut pipelines, mesh_buffers, &buffer_data);
 
         RenderMeshData {
             meshes,
@@ -283,80 +581,55 @@ impl<'a> RenderMeshSharedData<'a> {
 
     fn create_render_meshes(
         &self,
-        accesses: Vec<MeshBufferAccess>,
+        accesses: Vec<(
+            usize,
+            &MeshObjectData,
+            MeshBufferAccess,
+            MeshObjectVertexData,
+        )>,
         device: &wgpu::Device,
         pipelines: &mut HashMap<PipelineKey, wgpu::RenderPipeline>,
         mesh_buffers: &MeshBuffers,
         buffer_data: &MeshObjectBufferData,
-    ) -> Option<Vec<RenderMesh>> {
-        Some(
-            self.mesh?
-                .objects
-                .iter() // TODO: par_iter?
-                .zip(accesses.into_iter())
-                .enumerate()
-                .filter_map(|(i, (mesh_object, access))| {
-                    // Some mesh objects have associated triangle adjacency.
-                    let adj_entry = self
-                        .adj
-                        .and_then(|adj| adj.entries.iter().find(|e| e.mesh_object_index == i));
-
-                    // Find rendering flags from the numshexb.
-                    let meshex_flags = self
-                        .meshex
-                        .and_then(|meshex| {
-                            meshex
-                                .mesh_object_groups
-                                .iter()
-                                .find(|g| g.mesh_object_full_name == mesh_object.name)
-                        })
-                        .and_then(|g| g.entry_flags.get(mesh_object.subindex as usize));
-
-                    self.create_render_mesh(
-                        device,
-                        mesh_object,
-                        adj_entry,
-                        meshex_flags.copied(),
-                        pipelines,
-                        mesh_buffers,
-                        access,
-                        buffer_data,
-                    )
-                    .map_err(|e| {
-                        error!(
-                            "Error creating render mesh for mesh {}: {}",
-                            mesh_object.name, e
-                        );
-                        e
+    ) -> Vec<RenderMesh> {
+        accesses
+            .into_iter() // TODO: par_iter?
+            .filter_map(|(i, mesh_object, access, vertex_data)| {
+                // Some mesh objects have associated triangle adjacency.
+                let adj_entry = self
+                    .adj
+                    .and_then(|adj| adj.entries.iter().find(|e| e.mesh_object_index == i));
+
+                // Find rendering flags from the numshexb.
+                let meshex_flags = self
+                    .meshex
+                    .and_then(|meshex| {
+                        meshex
+                            .mesh_object_groups
+                            .iter()
+                            .find(|g| g.mesh_object_full_name == mesh_object.name)
                     })
-                    .ok()
-                })
-                .collect(),
-        )
-    }
+                    .and_then(|g| g.entry_flags.get(mesh_object.subindex as usize));
 
-    fn create_textures(
-        &self,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-    ) -> Vec<(String, wgpu::Texture, wgpu::TextureViewDimension)> {
-        self.nutexbs
-            .iter()
-            .filter_map(|(name, nutexb)| {
-                let nutexb = nutexb
-                    .as_ref()
-                    .map_err(|e| {
-                        error!("Failed to read nutexb file {}: {}", name, e);
-                        e
-                    })
-                    .ok()?;
-                let (texture, dim) = nutexb_wgpu::create_texture(nutexb, device, queue)
-                    .map_err(|e| {
-                        error!("Failed to create nutexb texture {}: {}", name, e);
-                        e
-                    })
-                    .ok()?;
-                Some((name.clone(), texture, dim))
+                self.create_render_mesh(
+                    device,
+                    mesh_object,
+                    adj_entry,
+                    meshex_flags.copied(),
+                    pipelines,
+                    mesh_buffers,
+                    access,
+                    vertex_data,
+                    buffer_data,
+                )
+                .map_err(|e| {
+                    error!(
+                        "Error creating render mesh for mesh {}: {}",
+                        mesh_object.name, e
+                    );
+                    e
+                })
+                .ok()
             })
             .collect()
     }
@@ -406,6 +679,7 @@ impl<'a> RenderMeshSharedData<'a> {
         pipelines: &mut HashMap<PipelineKey, wgpu::RenderPipeline>,
         mesh_buffers: &MeshBuffers,
         access: MeshBufferAccess,
+        vertex_data: MeshObjectVertexData,
         buffer_data: &MeshObjectBufferData,
     ) -> Result<RenderMesh, Box<dyn Error>> {
         // TODO: These could be cleaner as functions.
@@ -501,7 +775,11 @@ impl<'a> RenderMeshSharedData<'a> {
                 device,
                 crate::shader::skinning::bind_groups::BindGroupLayout1 {
                     transforms: mesh_buffers.skinning_transforms.as_entire_buffer_binding(),
+                    transforms_inv_transpose: mesh_buffers
+                        .skinning_transforms_inv_transpose
+                        .as_entire_buffer_binding(),
                     world_transforms: mesh_buffers.world_transforms.as_entire_buffer_binding(),
+                    per_model: mesh_buffers.per_model.as_entire_buffer_binding(),
                 },
             );
 
@@ -546,6 +824,8 @@ impl<'a> RenderMeshSharedData<'a> {
             .chain(mesh_object.color_sets.iter().map(|a| a.name.clone()))
             .collect();
 
+        let bounding_sphere = BoundingSphere::from_points(&vertex_data.positions);
+
         // TODO: Set entry flags?
         Ok(RenderMesh {
             name: mesh_object.name.clone(),
@@ -568,20 +848,130 @@ impl<'a> RenderMeshSharedData<'a> {
             vertex_index_count: mesh_object.vertex_indices.len(),
             access,
             attribute_names,
+            bounding_sphere,
+            parent_index,
+            influence_bone_indices: influence_bone_indices(mesh_object, self.skel),
+            picking_data: MeshPickingData {
+                positions: vertex_data.positions,
+                vertex_indices: mesh_object.vertex_indices.clone(),
+                skin_weights: vertex_data.skin_weights,
+            },
         })
     }
 }
 
+fn create_textures(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    textures: Vec<(String, Result<TextureData, String>)>,
+    errors: &mut Vec<ModelValidationError>,
+) -> Vec<(String, wgpu::Texture, wgpu::TextureViewDimension)> {
+    textures
+        .into_iter()
+        .filter_map(|(name, data)| match data {
+            Ok(data) => {
+                let (texture, dim) = data.create_texture(device, queue);
+                Some((name, texture, dim))
+            }
+            Err(message) => {
+                error!("Failed to create nutexb texture {}: {}", name, message);
+                errors.push(ModelValidationError::InvalidTexture {
+                    file_name: name,
+                    message,
+                });
+                None
+            }
+        })
+        .collect()
+}
+
+pub fn create_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    name: &str,
+    nutexb: &Result<NutexbFile, Box<dyn Error>>,
+    errors: &mut Vec<ModelValidationError>,
+) -> Option<(String, wgpu::Texture, wgpu::TextureViewDimension)> {
+    let nutexb = nutexb
+        .as_ref()
+        .map_err(|e| {
+            error!("Failed to read nutexb file {}: {}", name, e);
+            errors.push(ModelValidationError::InvalidTexture {
+                file_name: name.to_string(),
+                message: e.to_string(),
+            });
+        })
+        .ok()?;
+    let (texture, dim) = nutexb_wgpu::create_texture(nutexb, device, queue)
+        .map_err(|e| {
+            error!("Failed to create nutexb texture {}: {}", name, e);
+            errors.push(ModelValidationError::InvalidTexture {
+                file_name: name.to_string(),
+                message: e.to_string(),
+            });
+        })
+        .ok()?;
+    Some((name.to_string(), texture, dim))
+}
+
+/// Creates a uniform buffer for the root `transform` applied after skinning.
+pub fn skinning_per_model_buffer(
+    device: &wgpu::Device,
+    label: &str,
+    transform: glam::Mat4,
+) -> wgpu::Buffer {
+    device.create_buffer_from_data(
+        label,
+        &[skinning_per_model(transform)],
+        wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    )
+}
+
+pub fn skinning_per_model(transform: glam::Mat4) -> crate::shader::skinning::PerModel {
+    // Scaling an attached bone to zero shouldn't produce NaN normals.
+    let transform_inv_transpose = if transform.determinant() == 0.0 {
+        glam::Mat4::IDENTITY
+    } else {
+        transform.inverse().transpose()
+    };
+
+    crate::shader::skinning::PerModel {
+        transform,
+        transform_inv_transpose,
+    }
+}
+
+/// Creates a storage buffer with a transform for each bone.
+pub fn bone_transforms_buffer(
+    device: &wgpu::Device,
+    label: &str,
+    transforms: &[glam::Mat4],
+) -> wgpu::Buffer {
+    // Empty buffers can't be bound, so models without bones use a single transform.
+    let transforms = if transforms.is_empty() {
+        &[glam::Mat4::IDENTITY]
+    } else {
+        transforms
+    };
+    device.create_buffer_from_data(
+        label,
+        transforms,
+        wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+    )
+}
+
 fn bone_bind_group1(
     device: &wgpu::Device,
     world_transforms: &wgpu::Buffer,
     bone_colors: &wgpu::Buffer,
+    per_model: &wgpu::Buffer,
 ) -> crate::shader::skeleton::bind_groups::BindGroup1 {
     crate::shader::skeleton::bind_groups::BindGroup1::from_bindings(
         device,
         crate::shader::skeleton::bind_groups::BindGroupLayout1 {
             world_transforms: world_transforms.as_entire_buffer_binding(),
             bone_colors: bone_colors.as_entire_buffer_binding(),
+            per_model: per_model.as_entire_buffer_binding(),
         },
     )
 }
@@ -606,6 +996,8 @@ pub fn material_data(
     MaterialData {
         material_uniforms_bind_group,
         uniforms_buffer,
+        textures: material.textures.clone(),
+        changed_textures_material: None,
     }
 }
 
@@ -623,6 +1015,8 @@ pub fn default_material_data(
     MaterialData {
         material_uniforms_bind_group,
         uniforms_buffer,
+        textures: Vec::new(),
+        changed_textures_material: None,
     }
 }
 
@@ -644,32 +1038,29 @@ fn append_mesh_object_buffer_data(
     model_skin_weights_data: &mut DynamicStorageBuffer<Vec<u8>>,
     model_index_data: &mut Vec<u32>,
     mesh_object: &MeshObjectData,
-    shared_data: &RenderMeshSharedData,
-) -> Result<MeshBufferAccess, ssbh_data::mesh_data::error::Error> {
+    vertex_data: &MeshObjectVertexData,
+) -> MeshBufferAccess {
     // DynamicStorageBuffer enforces the offset alignment for each mesh.
-    let buffer0_vertices = buffer0(mesh_object)?;
-    let buffer0_offset = model_buffer0_data.write(&buffer0_vertices).unwrap();
-
-    let buffer1_vertices = buffer1(mesh_object)?;
-    let buffer1_offset = model_buffer1_data.write(&buffer1_vertices).unwrap();
-
-    let skin_weights = skin_weights(mesh_object, shared_data.skel)?;
-    let weights_offset = model_skin_weights_data.write(&skin_weights).unwrap();
+    let buffer0_offset = model_buffer0_data.write(&vertex_data.buffer0).unwrap();
+    let buffer1_offset = model_buffer1_data.write(&vertex_data.buffer1).unwrap();
+    let weights_offset = model_skin_weights_data
+        .write(&vertex_data.skin_weights)
+        .unwrap();
 
     // Only the index buffer is tightly packed.
     let index_offset = (model_index_data.len() * std::mem::size_of::<u32>()) as u64;
     model_index_data.extend_from_slice(&mesh_object.vertex_indices);
 
-    Ok(MeshBufferAccess {
+    MeshBufferAccess {
         buffer0_start: buffer0_offset,
-        buffer0_size: buffer0_vertices.size().get(),
+        buffer0_size: vertex_data.buffer0.size().get(),
         buffer1_start: buffer1_offset,
-        buffer1_size: buffer1_vertices.size().get(),
+        buffer1_size: vertex_data.buffer1.size().get(),
         weights_start: weights_offset,
-        weights_size: skin_weights.size().get(),
+        weights_size: vertex_data.skin_weights.size().get(),
         indices_start: index_offset,
         indices_size: (model_index_data.len() * std::mem::size_of::<u32>()) as u64,
-    })
+    }
 }
 
 fn bone_bind_groups(
@@ -713,6 +1104,16 @@ fn parent_index(index: Option<usize>) -> i32 {
     index.map(|i| i as i32).unwrap_or(-1)
 }
 
+fn influence_bone_indices(mesh: &MeshObjectData, skel: Option<&SkelData>) -> Vec<usize> {
+    skel.map(|skel| {
+        mesh.bone_influences
+            .iter()
+            .filter_map(|i| skel.bones.iter().position(|b| b.name == i.bone_name))
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
 fn find_parent_index(mesh: &MeshObjectData, skel: Option<&SkelData>) -> i32 {
     // Only include a parent if there are no bone influences.
     // TODO: What happens if there are influences and a parent bone?
@@ -726,3 +1127,57 @@ fn find_parent_index(mesh: &MeshObjectData, skel: Option<&SkelData>) -> i32 {
         -1
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mesh_object(name: &str) -> MeshObjectData {
+        MeshObjectData {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn vertex_data() -> MeshObjectVertexData {
+        MeshObjectVertexData {
+            buffer0: Vec::new(),
+            buffer1: Vec::new(),
+            skin_weights: Vec::new(),
+            positions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn valid_mesh_objects_invalid_middle_object() {
+        let objects = vec![mesh_object("a"), mesh_object("b"), mesh_object("c")];
+        let mut errors = Vec::new();
+
+        let valid = valid_mesh_objects(
+            &objects,
+            vec![
+                Ok(vertex_data()),
+                Err("error".to_string()),
+                Ok(vertex_data()),
+            ],
+            &mut errors,
+        );
+
+        // The indices should still match the original mesh objects.
+        assert_eq!(
+            vec![(0, "a"), (2, "c")],
+            valid
+                .iter()
+                .map(|(i, o, _)| (*i, o.name.as_str()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![ModelValidationError::InvalidVertexData {
+                mesh_name: "b".to_string(),
+                subindex: 0,
+                message: "error".to_string()
+            }],
+            errors
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use std::num::NonZeroU64;
+
+use super::{
+    mesh_creation::{bone_transforms_buffer, skinning_per_model_buffer, MeshBuffers},
+    RenderMesh,
+};
+use crate::{animation::AnimationTransforms, vertex::MeshObjectBufferData};
+
+/// A copy of the skinned vertices for a model posed at a frame relative to the current frame.
+pub(crate) struct OnionSkinFrame {
+    pub frame_offset: f32,
+    pub mesh_buffers: MeshBuffers,
+    pub vertex_buffer0: wgpu::Buffer,
+    // Each mesh uses the same vertex weights and unskinned vertices as the current frame.
+    skinning_bind_groups: Vec<(
+        crate::shader::skinning::bind_groups::BindGroup0,
+        crate::shader::skinning::bind_groups::BindGroup1,
+    )>,
+}
+
+impl OnionSkinFrame {
+    pub fn new(
+        device: &wgpu::Device,
+        frame_offset: f32,
+        meshes: &[RenderMesh],
+        buffer_data: &MeshObjectBufferData,
+        transforms: &AnimationTransforms,
+        root_transform: glam::Mat4,
+    ) -> Self {
+        let mesh_buffers = MeshBuffers {
+            skinning_transforms: bone_transforms_buffer(
+                device,
+                "Onion Skin Bone Transforms Buffer",
+                &transforms.animated_world_transforms.transforms,
+            ),
+            skinning_transforms_inv_transpose: bone_transforms_buffer(
+                device,
+                "Onion Skin Bone Transforms Inv Transpose Buffer",
+                &transforms
+                    .animated_world_transforms
+                    .transforms_inv_transpose,
+            ),
+            world_transforms: bone_transforms_buffer(
+                device,
+                "Onion Skin World Transforms Buffer",
+                &transforms.world_transforms,
+            ),
+            per_model: skinning_per_model_buffer(
+                device,
+                "Onion Skin Skinning PerModel Buffer",
+                root_transform,
+            ),
+        };
+
+        // The skinning shader writes the posed vertices to this buffer.
+        let vertex_buffer0 = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Onion Skin Vertex Storage Buffer 0"),
+            size: buffer_data.vertex_buffer0.size(),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        // Render meshes are only created for meshes with vertices.
+        // This means the buffer sizes are always non zero.
+        let skinning_bind_groups = meshes
+            .iter()
+            .map(|mesh| {
+                let size = NonZeroU64::new(mesh.access.buffer0_size);
+                let weights_size = NonZeroU64::new(mesh.access.weights_size);
+
+                let bind_group0 = crate::shader::skinning::bind_groups::BindGroup0::from_bindings(
+                    device,
+                    crate::shader::skinning::bind_groups::BindGroupLayout0 {
+                        src: wgpu::BufferBinding {
+                            buffer: &buffer_data.vertex_buffer0_source,
+                            offset: mesh.access.buffer0_start,
+                            size,
+                        },
+                        vertex_weights: wgpu::BufferBinding {
+                            buffer: &buffer_data.skinning_buffer,
+                            offset: mesh.access.weights_start,
+                            size: weights_size,
+                        },
+                        dst: wgpu::BufferBinding {
+                            buffer: &vertex_buffer0,
+                            offset: mesh.access.buffer0_start,
+                            size,
+                        },
+                    },
+                );
+
+                let bind_group1 = crate::shader::skinning::bind_groups::BindGroup1::from_bindings(
+                    device,
+                    crate::shader::skinning::bind_groups::BindGroupLayout1 {
+                        transforms: mesh_buffers.skinning_transforms.as_entire_buffer_binding(),
+                        transforms_inv_transpose: mesh_buffers
+                            .skinning_transforms_inv_transpose
+                            .as_entire_buffer_binding(),
+                        world_transforms: mesh_buffers.world_transforms.as_entire_buffer_binding(),
+                        per_model: mesh_buffers.per_model.as_entire_buffer_binding(),
+                    },
+                );
+
+                (bind_group0, bind_group1)
+            })
+            .collect();
+
+        Self {
+            frame_offset,
+            mesh_buffers,
+            vertex_buffer0,
+            skinning_bind_groups,
+        }
+    }
+
+    pub fn dispatch_skinning<'a>(
+        &'a self,
+        meshes: &'a [RenderMesh],
+        compute_pass: &mut wgpu::ComputePass<'a>,
+        bind_group3: &'a crate::shader::skinning::bind_groups::BindGroup3,
+    ) {
+        // Assume the pipeline is already set.
+        for (mesh, (bind_group0, bind_group1)) in meshes.iter().zip(&self.skinning_bind_groups) {
+            crate::shader::skinning::bind_groups::set_bind_groups(
+                compute_pass,
+                crate::shader::skinning::bind_groups::BindGroups::<'a> {
+                    bind_group0,
+                    bind_group1,
+                    bind_group2: &mesh.mesh_object_info_bind_group,
+                    bind_group3,
+                },
+            );
+
+            // Round up with ceil to avoid skipping vertices.
+            let [workgroup_x, _, _] = crate::shader::skinning::compute::MAIN_WORKGROUP_SIZE;
+            let workgroup_count = (mesh.vertex_count as f64 / workgroup_x as f64).ceil() as u32;
+            compute_pass.dispatch_workgroups(workgroup_count, 1, 1);
+        }
+    }
+}
@@ -244,6 +244,67 @@ pub fn silhouette_pipeline(
     })
 }
 
+/// A translucent variant of [silhouette_pipeline] for onion skinning.
+///
+/// The output is tinted using the blend constant like a screen blend mode.
+pub fn onion_skin_pipeline(
+    device: &wgpu::Device,
+    surface_format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    let shader = crate::shader::model::create_shader_module(device);
+    let render_pipeline_layout = crate::shader::model::create_pipeline_layout(device);
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Model Onion Skin"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[
+                crate::shader::model::VertexInput0::vertex_buffer_layout(
+                    wgpu::VertexStepMode::Vertex,
+                ),
+                crate::shader::model::VertexInput1::vertex_buffer_layout(
+                    wgpu::VertexStepMode::Vertex,
+                ),
+            ],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_solid",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: surface_format,
+                // The solid color is white, so this computes constant + dst * (1 - constant).
+                // Preserve the existing alpha to avoid affecting the overlay.
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::Constant,
+                        dst_factor: wgpu::BlendFactor::OneMinusConstant,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::Zero,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        // Only the nearest surface of each silhouette should be tinted.
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: crate::renderer::DEPTH_STENCIL_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
 pub fn wireframe_pipeline(
     device: &wgpu::Device,
     surface_format: wgpu::TextureFormat,
@@ -0,0 +1,174 @@
+use std::num::NonZeroU32;
+
+use crate::RGBA_COLOR_FORMAT;
+
+/// A render target with [RGBA_COLOR_FORMAT] that can be read back to the CPU.
+///
+/// This is useful for taking screenshots or rendering without a window.
+/// The output texture and staging buffer are allocated once and reused for each read.
+pub struct OffscreenTarget {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+}
+
+impl OffscreenTarget {
+    /// Creates a new target with dimensions `width` x `height` in pixels.
+    ///
+    /// The [SsbhRenderer](crate::SsbhRenderer) should use the same dimensions.
+    pub fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Target"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: RGBA_COLOR_FORMAT,
+            usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Texture copies require each row to be aligned.
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Offscreen Staging Buffer"),
+            size: padded_bytes_per_row(width) as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            texture,
+            view,
+            buffer,
+            width,
+            height,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The view to use as the `output_view` for rendering.
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    /// Copies the output texture to the staging buffer.
+    /// This should be called after rendering and before [OffscreenTarget::read_image].
+    pub fn copy_to_buffer(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &self.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(padded_bytes_per_row(self.width)),
+                    rows_per_image: NonZeroU32::new(self.height),
+                },
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// Reads the staging buffer into an RGBA image.
+    /// The commands from [OffscreenTarget::copy_to_buffer] should be submitted first.
+    ///
+    /// This blocks until the GPU has finished all submitted work.
+    pub fn read_image(
+        &self,
+        device: &wgpu::Device,
+    ) -> Result<image::RgbaImage, wgpu::BufferAsyncError> {
+        // Adapted from WGPU Example https://github.com/gfx-rs/wgpu/tree/master/wgpu/examples/capture
+        let buffer_slice = self.buffer.slice(..);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            // The receiver is still alive since we block below.
+            let _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError))?;
+
+        let image = {
+            let data = buffer_slice.get_mapped_range();
+            bgra_to_rgba_image(&data, self.width, self.height)
+        };
+        self.buffer.unmap();
+
+        Ok(image)
+    }
+}
+
+fn padded_bytes_per_row(width: u32) -> u32 {
+    let bytes_per_row = width * 4;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    (bytes_per_row + align - 1) / align * align
+}
+
+fn bgra_to_rgba_image(data: &[u8], width: u32, height: u32) -> image::RgbaImage {
+    let padded_bytes_per_row = padded_bytes_per_row(width) as usize;
+    let bytes_per_row = width as usize * 4;
+
+    // Remove the row padding and convert BGRA to RGBA.
+    let mut pixels = Vec::with_capacity(bytes_per_row * height as usize);
+    for row in data.chunks(padded_bytes_per_row).take(height as usize) {
+        pixels.extend(
+            row[..bytes_per_row]
+                .chunks_exact(4)
+                .flat_map(|p| [p[2], p[1], p[0], p[3]]),
+        );
+    }
+
+    image::RgbaImage::from_raw(width, height, pixels).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padded_bytes_per_row_aligned() {
+        assert_eq!(256, padded_bytes_per_row(64));
+        assert_eq!(2048, padded_bytes_per_row(512));
+    }
+
+    #[test]
+    fn padded_bytes_per_row_unaligned() {
+        assert_eq!(256, padded_bytes_per_row(1));
+        assert_eq!(512, padded_bytes_per_row(65));
+        assert_eq!(2560, padded_bytes_per_row(600));
+    }
+
+    #[test]
+    fn bgra_to_rgba_image_padded_rows() {
+        // Each 1 pixel row is padded to 256 bytes.
+        let mut data = vec![0u8; 512];
+        data[..4].copy_from_slice(&[1, 2, 3, 4]);
+        data[256..260].copy_from_slice(&[5, 6, 7, 8]);
+
+        let image = bgra_to_rgba_image(&data, 1, 2);
+        assert_eq!(&[3, 2, 1, 4], &image.get_pixel(0, 0).0);
+        assert_eq!(&[7, 6, 5, 8], &image.get_pixel(0, 1).0);
+    }
+}
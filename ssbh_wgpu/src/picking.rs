@@ -0,0 +1,396 @@
+use crate::{animation::AnimationTransforms, shader::skinning::VertexWeight, viewport::Ray};
+
+/// The object found by [RenderModel::pick](crate::RenderModel::pick).
+#[derive(Debug, PartialEq, Clone)]
+pub enum PickTarget {
+    /// The [RenderMesh](crate::RenderMesh) with the given name and subindex.
+    Mesh { name: String, subindex: u64 },
+    /// The bone with the given index in the skel.
+    Bone { index: usize },
+}
+
+/// The closest object intersected by a picking ray.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PickResult {
+    pub target: PickTarget,
+    /// The distance along the ray from the near plane to the intersection.
+    pub distance: f32,
+}
+
+// The bones are rendered as spheres scaled by the distance to the camera.
+// Use a larger sphere to make small bones easier to select.
+const BONE_PICK_SCALE: f32 = 0.0025 * 2.0;
+
+/// The unanimated vertex data kept on the CPU for picking.
+pub struct MeshPickingData {
+    pub positions: Vec<glam::Vec3>,
+    pub vertex_indices: Vec<u32>,
+    pub skin_weights: Vec<VertexWeight>,
+}
+
+impl MeshPickingData {
+    /// The distance along `ray` to the closest triangle after applying `transforms`
+    /// and then `root_transform` or `None` if no triangles intersect the ray.
+    pub fn intersect(
+        &self,
+        ray: &Ray,
+        parent_index: i32,
+        transforms: &AnimationTransforms,
+        root_transform: glam::Mat4,
+    ) -> Option<f32> {
+        let positions: Vec<_> = self
+            .positions
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let position =
+                    animated_position(*p, self.skin_weights.get(i), parent_index, transforms);
+                root_transform.transform_point3(position)
+            })
+            .collect();
+
+        self.vertex_indices
+            .chunks_exact(3)
+            .filter_map(|triangle| {
+                let v0 = positions.get(triangle[0] as usize)?;
+                let v1 = positions.get(triangle[1] as usize)?;
+                let v2 = positions.get(triangle[2] as usize)?;
+                intersect_triangle(ray, *v0, *v1, *v2)
+            })
+            .reduce(f32::min)
+    }
+}
+
+/// The index and distance along `ray` of the closest joint in `joint_transforms`.
+///
+/// The joints are tested as spheres scaled by the distance to `camera_pos` like in the skeleton shader.
+pub fn pick_joint(
+    ray: &Ray,
+    joint_transforms: &[glam::Mat4],
+    camera_pos: glam::Vec3,
+) -> Option<(usize, f32)> {
+    joint_transforms
+        .iter()
+        .enumerate()
+        .filter_map(|(i, transform)| {
+            let position = transform.transform_point3(glam::Vec3::ZERO);
+            let radius = position.distance(camera_pos) * BONE_PICK_SCALE;
+            intersect_sphere(ray, position, radius).map(|distance| (i, distance))
+        })
+        .reduce(|a, b| if b.1 < a.1 { b } else { a })
+}
+
+fn animated_position(
+    position: glam::Vec3,
+    weight: Option<&VertexWeight>,
+    parent_index: i32,
+    transforms: &AnimationTransforms,
+) -> glam::Vec3 {
+    // Match the parenting and skinning logic in the skinning shader.
+    let parent_transform = usize::try_from(parent_index)
+        .ok()
+        .and_then(|i| transforms.world_transforms.get(i));
+
+    match weight.filter(|w| w.bone_indices.x >= 0) {
+        Some(weight) => (0..4)
+            .filter_map(|i| {
+                // Match the skinning shader, which supports any bone in the skel.
+                let transform =
+                    usize::try_from(weight.bone_indices[i])
+                        .ok()
+                        .and_then(|bone_index| {
+                            transforms
+                                .animated_world_transforms
+                                .transforms
+                                .get(bone_index)
+                        })?;
+                Some(transform.transform_point3(position) * weight.weights[i])
+            })
+            .sum(),
+        None => parent_transform
+            .map(|t| t.transform_point3(position))
+            .unwrap_or(position),
+    }
+}
+
+fn intersect_triangle(ray: &Ray, v0: glam::Vec3, v1: glam::Vec3, v2: glam::Vec3) -> Option<f32> {
+    // Möller–Trumbore intersection without backface culling.
+    // https://en.wikipedia.org/wiki/M%C3%B6ller%E2%80%93Trumbore_intersection_algorithm
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+
+    let p = ray.direction.cross(edge2);
+    let determinant = edge1.dot(p);
+    if determinant.abs() < f32::EPSILON {
+        // The ray is parallel to the triangle.
+        return None;
+    }
+    let inv_determinant = 1.0 / determinant;
+
+    let s = ray.origin - v0;
+    let u = s.dot(p) * inv_determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(edge1);
+    let v = ray.direction.dot(q) * inv_determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let distance = edge2.dot(q) * inv_determinant;
+    (distance >= 0.0).then_some(distance)
+}
+
+/// The distance along `ray` to the sphere or `None` if the ray misses the sphere.
+pub fn intersect_sphere(ray: &Ray, center: glam::Vec3, radius: f32) -> Option<f32> {
+    // Solve |origin + t * direction - center| = radius for t.
+    let offset = ray.origin - center;
+    let b = offset.dot(ray.direction);
+    let c = offset.length_squared() - radius * radius;
+
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    // The ray may start inside the sphere.
+    let far = -b + discriminant.sqrt();
+    let near = -b - discriminant.sqrt();
+    (far >= 0.0).then_some(near.max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_vector_relative_eq;
+
+    fn transforms(world: glam::Mat4, animated_world: glam::Mat4) -> AnimationTransforms {
+        let mut transforms = AnimationTransforms::identity();
+        transforms.resize(2);
+        transforms.world_transforms[1] = world;
+        transforms.animated_world_transforms.transforms[1] = animated_world;
+        transforms
+    }
+
+    fn ray_negative_z(x: f32, y: f32) -> Ray {
+        Ray {
+            origin: glam::vec3(x, y, 10.0),
+            direction: -glam::Vec3::Z,
+        }
+    }
+
+    fn triangle() -> MeshPickingData {
+        MeshPickingData {
+            positions: vec![
+                glam::vec3(0.0, 0.0, 0.0),
+                glam::vec3(1.0, 0.0, 0.0),
+                glam::vec3(0.0, 1.0, 0.0),
+            ],
+            vertex_indices: vec![0, 1, 2],
+            skin_weights: vec![VertexWeight::default(); 3],
+        }
+    }
+
+    #[test]
+    fn intersect_triangle_front_and_back() {
+        let (v0, v1, v2) = (glam::Vec3::ZERO, glam::Vec3::X, glam::Vec3::Y);
+        assert_eq!(
+            Some(10.0),
+            intersect_triangle(&ray_negative_z(0.25, 0.25), v0, v1, v2)
+        );
+        assert_eq!(
+            Some(10.0),
+            intersect_triangle(&ray_negative_z(0.25, 0.25), v0, v2, v1)
+        );
+    }
+
+    #[test]
+    fn intersect_triangle_miss() {
+        let (v0, v1, v2) = (glam::Vec3::ZERO, glam::Vec3::X, glam::Vec3::Y);
+        assert_eq!(
+            None,
+            intersect_triangle(&ray_negative_z(0.75, 0.75), v0, v1, v2)
+        );
+        assert_eq!(
+            None,
+            intersect_triangle(&ray_negative_z(-0.1, 0.5), v0, v1, v2)
+        );
+    }
+
+    #[test]
+    fn intersect_triangle_behind_ray() {
+        let ray = Ray {
+            origin: glam::vec3(0.25, 0.25, 10.0),
+            direction: glam::Vec3::Z,
+        };
+        assert_eq!(
+            None,
+            intersect_triangle(&ray, glam::Vec3::ZERO, glam::Vec3::X, glam::Vec3::Y)
+        );
+    }
+
+    #[test]
+    fn intersect_sphere_outside_inside() {
+        let center = glam::vec3(0.0, 0.0, 2.0);
+        assert_eq!(
+            Some(7.0),
+            intersect_sphere(&ray_negative_z(0.0, 0.0), center, 1.0)
+        );
+        assert_eq!(
+            None,
+            intersect_sphere(&ray_negative_z(1.5, 0.0), center, 1.0)
+        );
+        assert_eq!(
+            Some(0.0),
+            intersect_sphere(&ray_negative_z(0.0, 0.0), center, 10.0)
+        );
+    }
+
+    #[test]
+    fn animated_position_parent() {
+        let transforms = transforms(
+            glam::Mat4::from_translation(glam::vec3(1.0, 2.0, 3.0)),
+            glam::Mat4::IDENTITY,
+        );
+        let position = animated_position(
+            glam::vec3(1.0, 1.0, 1.0),
+            Some(&VertexWeight::default()),
+            1,
+            &transforms,
+        );
+        assert_vector_relative_eq!([2.0, 3.0, 4.0], position.to_array());
+    }
+
+    #[test]
+    fn animated_position_skinning_ignores_parent() {
+        let transforms = transforms(
+            glam::Mat4::from_translation(glam::vec3(1.0, 2.0, 3.0)),
+            glam::Mat4::from_translation(glam::vec3(2.0, 0.0, 0.0)),
+        );
+        let weight = VertexWeight {
+            bone_indices: glam::ivec4(0, 1, -1, -1),
+            weights: glam::vec4(0.5, 0.5, 0.0, 0.0),
+        };
+        let position = animated_position(glam::vec3(1.0, 1.0, 1.0), Some(&weight), 1, &transforms);
+        assert_vector_relative_eq!([2.0, 1.0, 1.0], position.to_array());
+    }
+
+    #[test]
+    fn intersect_mesh_closest_triangle() {
+        let mut data = triangle();
+        data.positions.extend([
+            glam::vec3(0.0, 0.0, 1.0),
+            glam::vec3(1.0, 0.0, 1.0),
+            glam::vec3(0.0, 1.0, 1.0),
+        ]);
+        data.vertex_indices.extend([3, 4, 5]);
+        data.skin_weights.extend([VertexWeight::default(); 3]);
+
+        let transforms = transforms(glam::Mat4::IDENTITY, glam::Mat4::IDENTITY);
+        assert_eq!(
+            Some(9.0),
+            data.intersect(
+                &ray_negative_z(0.25, 0.25),
+                -1,
+                &transforms,
+                glam::Mat4::IDENTITY
+            )
+        );
+    }
+
+    #[test]
+    fn intersect_mesh_skinned() {
+        let mut data = triangle();
+        data.skin_weights = vec![
+            VertexWeight {
+                bone_indices: glam::ivec4(1, -1, -1, -1),
+                weights: glam::vec4(1.0, 0.0, 0.0, 0.0),
+            };
+            3
+        ];
+
+        // The triangle moves out from under the ray.
+        let transforms = transforms(
+            glam::Mat4::IDENTITY,
+            glam::Mat4::from_translation(glam::vec3(5.0, 0.0, 0.0)),
+        );
+        let ray = ray_negative_z(0.25, 0.25);
+        assert_eq!(
+            None,
+            data.intersect(&ray, -1, &transforms, glam::Mat4::IDENTITY)
+        );
+        assert_eq!(
+            Some(10.0),
+            data.intersect(
+                &ray_negative_z(5.25, 0.25),
+                -1,
+                &transforms,
+                glam::Mat4::IDENTITY
+            )
+        );
+    }
+
+    #[test]
+    fn intersect_mesh_root_transform() {
+        let data = triangle();
+
+        // The triangle moves out from under the ray.
+        let transforms = transforms(glam::Mat4::IDENTITY, glam::Mat4::IDENTITY);
+        let root_transform = glam::Mat4::from_translation(glam::vec3(5.0, 0.0, 0.0));
+        let ray = ray_negative_z(0.25, 0.25);
+        assert_eq!(None, data.intersect(&ray, -1, &transforms, root_transform));
+        assert_eq!(
+            Some(10.0),
+            data.intersect(&ray_negative_z(5.25, 0.25), -1, &transforms, root_transform)
+        );
+    }
+
+    #[test]
+    fn intersect_mesh_invalid_indices() {
+        let mut data = triangle();
+        data.vertex_indices = vec![0, 1, 3];
+
+        let transforms = transforms(glam::Mat4::IDENTITY, glam::Mat4::IDENTITY);
+        assert_eq!(
+            None,
+            data.intersect(
+                &ray_negative_z(0.25, 0.25),
+                -1,
+                &transforms,
+                glam::Mat4::IDENTITY
+            )
+        );
+    }
+
+    #[test]
+    fn pick_joint_closest() {
+        let joints = [
+            glam::Mat4::from_translation(glam::vec3(0.0, 0.0, -5.0)),
+            glam::Mat4::from_translation(glam::vec3(0.0, 0.0, 5.0)),
+            glam::Mat4::from_translation(glam::vec3(3.0, 0.0, 0.0)),
+        ];
+        let (index, distance) = pick_joint(
+            &ray_negative_z(0.0, 0.0),
+            &joints,
+            glam::vec3(0.0, 0.0, 10.0),
+        )
+        .unwrap();
+        assert_eq!(1, index);
+        assert!(distance < 5.0);
+    }
+
+    #[test]
+    fn pick_joint_miss() {
+        let joints = [glam::Mat4::from_translation(glam::vec3(3.0, 0.0, 0.0))];
+        assert_eq!(
+            None,
+            pick_joint(
+                &ray_negative_z(0.0, 0.0),
+                &joints,
+                glam::vec3(0.0, 0.0, 10.0)
+            )
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use log::error;
+use ssbh_data::prelude::*;
+use xmb_lib::XmbFile;
+
+use crate::{ModelFiles, ModelFolder, NutexbFile};
+
+// The file extensions loaded by ModelFolder::load_folder.
+const MODEL_EXTENSIONS: [&str; 10] = [
+    "numshb", "numshexb", "nusktb", "numatb", "numdlb", "nuanmb", "adjb", "nuhlpb", "nutexb", "xmb",
+];
+
+/// The names of files in a [ModelFolder] that were added, modified, or removed.
+///
+/// Apply the changes with [ModelFolder::reload_files] and [RenderModel::update_from_changes](crate::RenderModel::update_from_changes).
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct ModelFolderDiff {
+    /// File names like `"model.numatb"` without the parent folder.
+    pub file_names: Vec<String>,
+}
+
+impl ModelFolderDiff {
+    /// Returns `true` if no files changed.
+    pub fn is_empty(&self) -> bool {
+        self.file_names.is_empty()
+    }
+
+    /// Returns `true` if `file_name` changed.
+    pub fn contains(&self, file_name: &str) -> bool {
+        self.file_names.iter().any(|f| f == file_name)
+    }
+
+    /// The changed file names with the given `extension` like `"nutexb"`.
+    pub fn with_extension<'a>(&'a self, extension: &'a str) -> impl Iterator<Item = &'a str> {
+        self.file_names
+            .iter()
+            .filter(move |f| file_extension(f) == Some(extension))
+            .map(|f| f.as_str())
+    }
+
+    /// Returns `true` if the changes require recreating the meshes and vertex buffers.
+    pub fn requires_mesh_rebuild(&self) -> bool {
+        // Mesh objects can be parented to bones, so skel changes also affect the meshes.
+        [
+            "model.numshb",
+            "model.numshexb",
+            "model.adjb",
+            "model.nusktb",
+        ]
+        .iter()
+        .any(|f| self.contains(f))
+    }
+
+    /// Returns `true` if the changes require recreating the materials.
+    pub fn requires_material_update(&self) -> bool {
+        self.contains("model.numatb")
+            || self.contains("model.numdlb")
+            || self.with_extension("nutexb").next().is_some()
+    }
+}
+
+impl ModelFolder {
+    /// Reads only the files in `diff` from `folder` and replaces the previous data.
+    /// Files that no longer exist in `folder` are removed.
+    pub fn reload_files<P: AsRef<Path>>(&mut self, folder: P, diff: &ModelFolderDiff) {
+        let folder = folder.as_ref();
+        for name in &diff.file_names {
+            let path = folder.join(name);
+            match file_extension(name) {
+                Some("numshb") => reload_file(&mut self.meshes, name, path, MeshData::from_file),
+                Some("numshexb") => {
+                    reload_file(&mut self.meshexes, name, path, MeshExData::from_file)
+                }
+                Some("nusktb") => reload_file(&mut self.skels, name, path, SkelData::from_file),
+                Some("numatb") => reload_file(&mut self.matls, name, path, MatlData::from_file),
+                Some("numdlb") => reload_file(&mut self.modls, name, path, ModlData::from_file),
+                Some("nuanmb") => reload_file(&mut self.anims, name, path, AnimData::from_file),
+                Some("adjb") => reload_file(&mut self.adjs, name, path, AdjData::from_file),
+                Some("nuhlpb") => reload_file(&mut self.hlpbs, name, path, HlpbData::from_file),
+                Some("nutexb") => {
+                    reload_file(&mut self.nutexbs, name, path, NutexbFile::read_from_file)
+                }
+                Some("xmb") => reload_file(&mut self.xmbs, name, path, XmbFile::from_file),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Detects changes to the files in a model folder by polling the file modified times.
+///
+/// Call [ModelFolderWatcher::changes] periodically like once per second
+/// to avoid checking the file system every frame.
+pub struct ModelFolderWatcher {
+    folder: PathBuf,
+    modified_times: HashMap<String, SystemTime>,
+}
+
+impl ModelFolderWatcher {
+    /// Starts watching `folder` using the current state of the files.
+    pub fn new<P: AsRef<Path>>(folder: P) -> Self {
+        let folder = folder.as_ref().to_owned();
+        let modified_times = modified_times(&folder);
+        Self {
+            folder,
+            modified_times,
+        }
+    }
+
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// The files added, modified, or removed since the last call to this method
+    /// or since creating the watcher.
+    pub fn changes(&mut self) -> ModelFolderDiff {
+        let modified_times = modified_times(&self.folder);
+        let diff = diff_modified_times(&self.modified_times, &modified_times);
+        self.modified_times = modified_times;
+        diff
+    }
+}
+
+fn modified_times(folder: &Path) -> HashMap<String, SystemTime> {
+    std::fs::read_dir(folder)
+        .map(|dir| {
+            dir.filter_map(|entry| {
+                let entry = entry.ok()?;
+                let name = entry.file_name().to_string_lossy().to_string();
+                if !is_model_file(&name) {
+                    return None;
+                }
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                Some((name, modified))
+            })
+            .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn diff_modified_times(
+    previous: &HashMap<String, SystemTime>,
+    current: &HashMap<String, SystemTime>,
+) -> ModelFolderDiff {
+    let added_or_modified = current
+        .iter()
+        .filter(|(name, modified)| previous.get(*name) != Some(*modified))
+        .map(|(name, _)| name.clone());
+
+    let removed = previous
+        .keys()
+        .filter(|name| !current.contains_key(*name))
+        .cloned();
+
+    let mut file_names: Vec<_> = added_or_modified.chain(removed).collect();
+    // Sort for consistent results since HashMap iteration order is random.
+    file_names.sort();
+
+    ModelFolderDiff { file_names }
+}
+
+fn reload_file<T, F>(files: &mut ModelFiles<T>, name: &str, path: PathBuf, read_t: F)
+where
+    F: Fn(PathBuf) -> Result<T, Box<dyn Error>>,
+{
+    let index = files.iter().position(|(f, _)| f == name);
+
+    if path.exists() {
+        let file = read_t(path.clone()).map_err(|e| {
+            error!("Error reading {:?}: {}", path, e);
+            e
+        });
+        match index {
+            Some(index) => files[index].1 = file,
+            None => files.push((name.to_string(), file)),
+        }
+    } else if let Some(index) = index {
+        files.remove(index);
+    }
+}
+
+fn file_extension(file_name: &str) -> Option<&str> {
+    Path::new(file_name).extension().and_then(|e| e.to_str())
+}
+
+fn is_model_file(file_name: &str) -> bool {
+    file_extension(file_name).map_or(false, |e| MODEL_EXTENSIONS.contains(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn diff(file_names: &[&str]) -> ModelFolderDiff {
+        ModelFolderDiff {
+            file_names: file_names.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    fn times(files: &[(&str, u64)]) -> HashMap<String, SystemTime> {
+        files
+            .iter()
+            .map(|(f, t)| {
+                (
+                    f.to_string(),
+                    SystemTime::UNIX_EPOCH + Duration::from_secs(*t),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diff_material_changes() {
+        let diff = diff(&["model.numatb", "def_mario_001_col.nutexb"]);
+        assert!(diff.requires_material_update());
+        assert!(!diff.requires_mesh_rebuild());
+        assert_eq!(
+            vec!["def_mario_001_col.nutexb"],
+            diff.with_extension("nutexb").collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn diff_mesh_changes() {
+        assert!(diff(&["model.nusktb"]).requires_mesh_rebuild());
+        assert!(diff(&["model.numshb"]).requires_mesh_rebuild());
+        assert!(!diff(&["model.numshb"]).requires_material_update());
+        assert!(!diff(&["model.nuhlpb", "model.xmb"]).requires_mesh_rebuild());
+    }
+
+    #[test]
+    fn model_files() {
+        assert!(is_model_file("model.numshb"));
+        assert!(is_model_file("a.nutexb"));
+        assert!(!is_model_file("model.numshb.bak"));
+        assert!(!is_model_file("model"));
+    }
+
+    #[test]
+    fn diff_modified_times_unchanged() {
+        let previous = times(&[("model.numshb", 1), ("model.numatb", 2)]);
+        assert!(diff_modified_times(&previous, &previous).is_empty());
+    }
+
+    #[test]
+    fn diff_modified_times_added_modified_removed() {
+        let previous = times(&[("model.numshb", 1), ("model.numatb", 2), ("a.nutexb", 3)]);
+        let current = times(&[("model.numshb", 1), ("model.numatb", 4), ("b.nutexb", 3)]);
+        assert_eq!(
+            diff(&["a.nutexb", "b.nutexb", "model.numatb"]),
+            diff_modified_times(&previous, &current)
+        );
+    }
+
+    #[test]
+    fn reload_file_replace_add_remove() {
+        let mut files: ModelFiles<u32> = vec![("a".to_string(), Ok(1)), ("b".to_string(), Ok(2))];
+        let folder = std::env::temp_dir();
+
+        // Use an existing path to simulate modified and added files.
+        reload_file(&mut files, "a", folder.clone(), |_| Ok(3));
+        reload_file(&mut files, "c", folder, |_| Ok(4));
+        reload_file(&mut files, "b", PathBuf::from("does/not/exist"), |_| Ok(5));
+
+        let values: Vec<_> = files
+            .iter()
+            .map(|(f, v)| (f.as_str(), *v.as_ref().unwrap()))
+            .collect();
+        assert_eq!(vec![("a", 3), ("c", 4)], values);
+    }
+}
@@ -186,4 +186,74 @@ pub struct ModelRenderOptions {
     pub draw_wireframe: bool,
     /// Draw an infinite grid on the XZ-axis when `true`.
     pub draw_floor_grid: bool,
+    /// Draw tinted silhouettes for the frames in
+    /// [RenderModel::set_onion_skin_frames](crate::RenderModel::set_onion_skin_frames)
+    /// or disable onion skinning when `None`.
+    pub onion_skin: Option<OnionSkinOptions>,
+}
+
+/// Colors for the onion skin silhouettes of neighboring animation frames.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct OnionSkinOptions {
+    /// The RGB tint for frames before the current frame.
+    pub previous_color: [f32; 3],
+    /// The RGB tint for frames after the current frame.
+    pub next_color: [f32; 3],
+    /// The opacity from `0.0` to `1.0` for the frames closest to the current frame.
+    /// Frames further from the current frame fade out.
+    pub opacity: f32,
+}
+
+impl Default for OnionSkinOptions {
+    fn default() -> Self {
+        Self {
+            previous_color: [1.0, 0.3, 0.3],
+            next_color: [0.3, 1.0, 0.3],
+            opacity: 0.5,
+        }
+    }
+}
+
+impl OnionSkinOptions {
+    /// The tint multiplied by the opacity for the silhouette at `frame_offset`
+    /// when the furthest frame is `max_offset` frames from the current frame.
+    pub fn frame_color(&self, frame_offset: f32, max_offset: f32) -> [f32; 3] {
+        let color = if frame_offset < 0.0 {
+            self.previous_color
+        } else {
+            self.next_color
+        };
+
+        // Leave some contribution for the furthest frames.
+        let fade = 1.0 - frame_offset.abs() / (max_offset.abs() + 1.0);
+        let opacity = self.opacity.clamp(0.0, 1.0) * fade.clamp(0.0, 1.0);
+        color.map(|c| c * opacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn onion_skin_frame_color_previous_next() {
+        let options = OnionSkinOptions {
+            previous_color: [1.0, 0.0, 0.0],
+            next_color: [0.0, 0.0, 1.0],
+            opacity: 1.0,
+        };
+        assert_eq!([0.5, 0.0, 0.0], options.frame_color(-1.0, 1.0));
+        assert_eq!([0.0, 0.0, 0.5], options.frame_color(1.0, 1.0));
+    }
+
+    #[test]
+    fn onion_skin_frame_color_fade() {
+        let options = OnionSkinOptions {
+            previous_color: [1.0, 1.0, 1.0],
+            next_color: [1.0, 1.0, 1.0],
+            opacity: 0.5,
+        };
+        assert_eq!([0.375, 0.375, 0.375], options.frame_color(-1.0, 3.0));
+        assert_eq!([0.125, 0.125, 0.125], options.frame_color(3.0, 3.0));
+    }
 }
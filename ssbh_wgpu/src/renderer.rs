@@ -8,7 +8,7 @@ use crate::{
     render_settings::*,
     swing_rendering::swing_pipeline,
     texture::{load_default_lut, uv_pattern, TextureSamplerView},
-    CameraTransforms, DeviceBufferExt, QueueExt, RenderModel, ShaderDatabase,
+    CameraTransforms, DeviceBufferExt, OffscreenTarget, QueueExt, RenderModel, ShaderDatabase,
 };
 use glyph_brush::DefaultSectionHasher;
 use nutexb_wgpu::NutexbFile;
@@ -114,6 +114,7 @@ pub struct SsbhRenderer {
     invalid_attributes_pipeline: wgpu::RenderPipeline,
     debug_pipeline: wgpu::RenderPipeline,
     silhouette_pipeline: wgpu::RenderPipeline,
+    onion_skin_pipeline: wgpu::RenderPipeline,
     outline_pipeline: wgpu::RenderPipeline,
     uv_pipeline: wgpu::RenderPipeline,
     overlay_pipeline: wgpu::RenderPipeline,
@@ -326,6 +327,7 @@ impl SsbhRenderer {
         let invalid_attributes_pipeline = invalid_attributes_pipeline(device, RGBA_COLOR_FORMAT);
         let debug_pipeline = debug_pipeline(device, RGBA_COLOR_FORMAT);
         let silhouette_pipeline = silhouette_pipeline(device, RGBA_COLOR_FORMAT);
+        let onion_skin_pipeline = onion_skin_pipeline(device, RGBA_COLOR_FORMAT);
         let outline_pipeline = create_outline_pipeline(device, RGBA_COLOR_FORMAT);
         let uv_pipeline = uv_pipeline(device, RGBA_COLOR_FORMAT);
         let wireframe_pipeline = wireframe_pipeline(device, RGBA_COLOR_FORMAT);
@@ -403,6 +405,7 @@ impl SsbhRenderer {
             invalid_attributes_pipeline,
             debug_pipeline,
             silhouette_pipeline,
+            onion_skin_pipeline,
             outline_pipeline,
             uv_pipeline,
             render_settings,
@@ -577,7 +580,7 @@ impl SsbhRenderer {
 
         // Transform the vertex positions and normals.
         // Always run compute passes to preserve vertex positions when switching to debug shading.
-        self.skinning_pass(encoder, render_models.iter());
+        self.skinning_pass(encoder, render_models.iter(), options.onion_skin.is_some());
         self.renormal_pass(encoder, render_models.iter());
 
         // TODO: Benchmark and investigate compute shaders for post processing.
@@ -629,6 +632,11 @@ impl SsbhRenderer {
             self.post_processing_pass(encoder, &self.pass_info.color_final.view);
         }
 
+        // Draw neighboring frames on top of the current frame to show the motion.
+        if let Some(onion_skin) = &options.onion_skin {
+            self.onion_skin_pass(encoder, render_models.iter(), onion_skin);
+        }
+
         // The skeleton pass needs to happen before the silhouettes.
         // This allows reusing the depth/stencil textures.
         // TODO: How to also use this for silhouettes?
@@ -680,6 +688,39 @@ impl SsbhRenderer {
         self.overlay_pass(encoder, output_view)
     }
 
+    /// Renders `render_models` to `target` and reads the result back to the CPU.
+    ///
+    /// The renderer should have the same dimensions as `target`.
+    /// See [SsbhRenderer::resize] for changing the output dimensions.
+    /// This blocks until rendering has finished, so prefer [SsbhRenderer::render_models]
+    /// for drawing to a window each frame.
+    pub fn render_to_image(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        target: &OffscreenTarget,
+        render_models: &[RenderModel],
+        shader_database: &ShaderDatabase,
+        options: &ModelRenderOptions,
+    ) -> Result<image::RgbaImage, wgpu::BufferAsyncError> {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Render To Image Encoder"),
+        });
+
+        // The final pass needs to be dropped before copying the output.
+        self.render_models(
+            &mut encoder,
+            target.view(),
+            render_models,
+            shader_database,
+            options,
+        );
+        target.copy_to_buffer(&mut encoder);
+        queue.submit([encoder.finish()]);
+
+        target.read_image(device)
+    }
+
     /// Renders UVs for all of the meshes with `is_selected` set to `true`.
     pub fn render_models_uv<'a>(
         &'a self,
@@ -713,6 +754,23 @@ impl SsbhRenderer {
         );
     }
 
+    /// Render the bone trajectories for `render_model` on top of the existing output.
+    ///
+    /// The paths should be initialized first using [RenderModel::update_motion_paths].
+    /// The `render_pass` should have a single color attachment with the format [RGBA_COLOR_FORMAT]
+    /// and no depth attachment like the pass returned by [SsbhRenderer::render_models].
+    pub fn render_motion_paths<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        render_model: &'a RenderModel,
+    ) {
+        render_model.draw_motion_paths(
+            render_pass,
+            &self.skeleton_camera_bind_group,
+            &self.bone_pipelines.motion_path_pipeline,
+        );
+    }
+
     /// Renders the bone names for skeleton in `skels` for each model in `render_models` to `output_view`.
     ///
     /// The `output_view` should have the format [RGBA_COLOR_FORMAT].
@@ -849,6 +907,7 @@ impl SsbhRenderer {
         &self,
         encoder: &mut wgpu::CommandEncoder,
         render_models: impl Iterator<Item = &'a RenderModel>,
+        onion_skin: bool,
     ) {
         // Skin the render meshes using a compute pass instead of in the vertex shader.
         // Compute shaders give more flexibility compared to vertex shaders.
@@ -864,6 +923,14 @@ impl SsbhRenderer {
                 &mut skinning_pass,
                 &self.skinning_settings_bind_group,
             );
+
+            if onion_skin {
+                crate::model::dispatch_onion_skin_skinning(
+                    model,
+                    &mut skinning_pass,
+                    &self.skinning_settings_bind_group,
+                );
+            }
         }
     }
 
@@ -994,6 +1061,41 @@ impl SsbhRenderer {
         active
     }
 
+    fn onion_skin_pass<'a>(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_models: impl Iterator<Item = &'a RenderModel>,
+        options: &OnionSkinOptions,
+    ) {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Onion Skin Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.pass_info.color_final.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            // The silhouette pass clears this texture later, so it's safe to reuse here.
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.pass_info.silhouette_stencil.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+
+        self.set_scissor(&mut pass);
+
+        pass.set_pipeline(&self.onion_skin_pipeline);
+        for model in render_models.filter(|m| m.is_visible) {
+            model.draw_onion_skin(&mut pass, &self.per_frame_bind_group, options);
+        }
+    }
+
     fn model_debug_pass(
         &self,
         encoder: &mut wgpu::CommandEncoder,
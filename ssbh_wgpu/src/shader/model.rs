@@ -266,9 +266,12 @@ pub struct PerMaterial {
     pub shader_settings: glam::UVec4,
     pub lighting_settings: glam::UVec4,
     pub shader_complexity: glam::Vec4,
+    pub anim_uv_transforms: [glam::Vec4; 4],
+    pub anim_uv_rotations: glam::Vec4,
+    pub has_anim_uv_transforms: glam::UVec4,
 }
 const _: () = assert!(
-    std::mem::size_of:: < PerMaterial > () == 3712,
+    std::mem::size_of:: < PerMaterial > () == 3808,
     "size of PerMaterial does not match WGSL"
 );
 const _: () = assert!(
@@ -319,6 +322,18 @@ const _: () = assert!(
     memoffset::offset_of!(PerMaterial, shader_complexity) == 3696,
     "offset of PerMaterial.shader_complexity does not match WGSL"
 );
+const _: () = assert!(
+    memoffset::offset_of!(PerMaterial, anim_uv_transforms) == 3712,
+    "offset of PerMaterial.anim_uv_transforms does not match WGSL"
+);
+const _: () = assert!(
+    memoffset::offset_of!(PerMaterial, anim_uv_rotations) == 3776,
+    "offset of PerMaterial.anim_uv_rotations does not match WGSL"
+);
+const _: () = assert!(
+    memoffset::offset_of!(PerMaterial, has_anim_uv_transforms) == 3792,
+    "offset of PerMaterial.has_anim_uv_transforms does not match WGSL"
+);
 #[repr(C)]
 #[derive(
     Debug,
@@ -170,7 +170,13 @@ struct PerMaterial {
     has_color_set567: vec4<u32>,
     shader_settings: vec4<u32>, // discard, premultiplied, 0, 0
     lighting_settings: vec4<u32>, // lighting, sh, receives_shadow, 0
-    shader_complexity: vec4<f32>
+    shader_complexity: vec4<f32>,
+    // UV transforms from material animations for map1, uvSet, uvSet1, and the dual normal map.
+    // Each transform is scale u, scale v, translate u, translate v.
+    anim_uv_transforms: array<vec4<f32>, 4>,
+    // The rotation in radians for each animated UV transform.
+    anim_uv_rotations: vec4<f32>,
+    has_anim_uv_transforms: vec4<u32>
 };
 
 @group(2) @binding(30)
@@ -250,6 +256,15 @@ fn TransformUv(uv: vec2<f32>, transform: vec4<f32>) -> vec2<f32>
     return vec2(x, y);
 }
 
+fn RotateUv(uv: vec2<f32>, angle: f32) -> vec2<f32>
+{
+    // Rotate about the center of the texture.
+    let c = cos(angle);
+    let s = sin(angle);
+    let centered = uv - vec2(0.5);
+    return vec2(c * centered.x - s * centered.y, s * centered.x + c * centered.y) + vec2(0.5);
+}
+
 // TODO: Rework texture blending to match the in game behavior.
 // The game usually uses white for missing required textures.
 // We use a single shader for all possible shaders.
@@ -721,8 +736,23 @@ fn vs_main(
         uvTransformDualNormal = per_material.custom_vector[34];
     }
 
-    var map1 = TransformUv(buffer1.map1_uvset.xy, uvTransform1);
-    var map1_dual = TransformUv(buffer1.map1_uvset.xy, uvTransformDualNormal);
+    // Material animations override the UV transforms from the material.
+    if (per_material.has_anim_uv_transforms.x == 1u) {
+        uvTransform1 = per_material.anim_uv_transforms[0];
+    }
+    if (per_material.has_anim_uv_transforms.y == 1u) {
+        uvTransform2 = per_material.anim_uv_transforms[1];
+    }
+    if (per_material.has_anim_uv_transforms.z == 1u) {
+        uvTransform3 = per_material.anim_uv_transforms[2];
+    }
+    if (per_material.has_anim_uv_transforms.w == 1u) {
+        uvTransformDualNormal = per_material.anim_uv_transforms[3];
+    }
+    let uvRotations = per_material.anim_uv_rotations;
+
+    var map1 = TransformUv(RotateUv(buffer1.map1_uvset.xy, uvRotations.x), uvTransform1);
+    var map1_dual = TransformUv(RotateUv(buffer1.map1_uvset.xy, uvRotations.w), uvTransformDualNormal);
 
     // Sprite sheet params.
     // Perform this in the fragment shader to avoid affecting debug modes.
@@ -744,10 +774,10 @@ fn vs_main(
         map1.y += (1.0 / rowCount) * floor(spriteIndex / columnCount);
     }
 
-    let uvSet = TransformUv(buffer1.map1_uvset.zw, uvTransform2);
-    let uvSet1 = TransformUv(buffer1.uv_set1_uv_set2.xy, uvTransform3);
+    let uvSet = TransformUv(RotateUv(buffer1.map1_uvset.zw, uvRotations.y), uvTransform2);
+    let uvSet1 = TransformUv(RotateUv(buffer1.uv_set1_uv_set2.xy, uvRotations.z), uvTransform3);
     // TODO: Transform for uvSet2?
-    let uvSet2 = TransformUv(buffer1.uv_set1_uv_set2.xy, uvTransform3);
+    let uvSet2 = TransformUv(RotateUv(buffer1.uv_set1_uv_set2.xy, uvRotations.z), uvTransform3);
 
     out.map1 = vec4(map1, map1_dual);
     out.uv_set_uv_set1 = vec4(uvSet, uvSet1);
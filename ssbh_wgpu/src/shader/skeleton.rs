@@ -77,37 +77,20 @@ const _: () = assert!(
     bytemuck::Zeroable,
     encase::ShaderType
 )]
-pub struct WorldTransforms {
-    pub transforms: [glam::Mat4; 512],
+pub struct PerModel {
+    pub transform: glam::Mat4,
+    pub transform_inv_transpose: glam::Mat4,
 }
 const _: () = assert!(
-    std::mem::size_of:: < WorldTransforms > () == 32768,
-    "size of WorldTransforms does not match WGSL"
+    std::mem::size_of:: < PerModel > () == 128, "size of PerModel does not match WGSL"
 );
 const _: () = assert!(
-    memoffset::offset_of!(WorldTransforms, transforms) == 0,
-    "offset of WorldTransforms.transforms does not match WGSL"
-);
-#[repr(C)]
-#[derive(
-    Debug,
-    Copy,
-    Clone,
-    PartialEq,
-    bytemuck::Pod,
-    bytemuck::Zeroable,
-    encase::ShaderType
-)]
-pub struct BoneColors {
-    pub colors: [glam::Vec4; 512],
-}
-const _: () = assert!(
-    std::mem::size_of:: < BoneColors > () == 8192,
-    "size of BoneColors does not match WGSL"
+    memoffset::offset_of!(PerModel, transform) == 0,
+    "offset of PerModel.transform does not match WGSL"
 );
 const _: () = assert!(
-    memoffset::offset_of!(BoneColors, colors) == 0,
-    "offset of BoneColors.colors does not match WGSL"
+    memoffset::offset_of!(PerModel, transform_inv_transpose) == 64,
+    "offset of PerModel.transform_inv_transpose does not match WGSL"
 );
 #[repr(C)]
 #[derive(
@@ -178,6 +161,7 @@ pub mod bind_groups {
     pub struct BindGroupLayout1<'a> {
         pub world_transforms: wgpu::BufferBinding<'a>,
         pub bone_colors: wgpu::BufferBinding<'a>,
+        pub per_model: wgpu::BufferBinding<'a>,
     }
     const LAYOUT_DESCRIPTOR1: wgpu::BindGroupLayoutDescriptor = wgpu::BindGroupLayoutDescriptor {
         label: None,
@@ -186,7 +170,9 @@ pub mod bind_groups {
                 binding: 0,
                 visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
                 ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
+                    ty: wgpu::BufferBindingType::Storage {
+                        read_only: true,
+                    },
                     has_dynamic_offset: false,
                     min_binding_size: None,
                 },
@@ -195,6 +181,18 @@ pub mod bind_groups {
             wgpu::BindGroupLayoutEntry {
                 binding: 1,
                 visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage {
+                        read_only: true,
+                    },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
                 ty: wgpu::BindingType::Buffer {
                     ty: wgpu::BufferBindingType::Uniform,
                     has_dynamic_offset: false,
@@ -227,6 +225,10 @@ pub mod bind_groups {
                                     bindings.bone_colors,
                                 ),
                             },
+                            wgpu::BindGroupEntry {
+                                binding: 2,
+                                resource: wgpu::BindingResource::Buffer(bindings.per_model),
+                            },
                         ],
                         label: None,
                     },
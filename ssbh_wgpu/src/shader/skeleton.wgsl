@@ -17,14 +17,9 @@ struct CameraTransforms {
     screen_dimensions: vec4<f32>, // width, height, scale, _
 };
 
-struct WorldTransforms {
-    transforms: array<mat4x4<f32>, 512>
-};
-
-struct BoneColors {
-    // The world transform of each bone.
-    // This is used for parenting objects to bones.
-    colors: array<vec4<f32>, 512>
+struct PerModel {
+    transform: mat4x4<f32>,
+    transform_inv_transpose: mat4x4<f32>,
 };
 
 struct PerBone {
@@ -36,11 +31,16 @@ struct PerBone {
 @group(0) @binding(0)
 var<uniform> camera: CameraTransforms;
 
+// The world transform of each bone.
 @group(1) @binding(0)
-var<uniform> world_transforms: WorldTransforms;
+var<storage, read> world_transforms: array<mat4x4<f32>>;
 
 @group(1) @binding(1)
-var<uniform> bone_colors: BoneColors;
+var<storage, read> bone_colors: array<vec4<f32>>;
+
+// The model-to-world transform applied to all bones.
+@group(1) @binding(2)
+var<uniform> per_model: PerModel;
 
 // TODO: Just use instancing?
 @group(2) @binding(0)
@@ -50,9 +50,10 @@ var<uniform> per_bone: PerBone;
 fn vs_axes(in: VertexInput) -> VertexOutput {
     let bone_index = per_bone.indices.x;
     var out: VertexOutput;
-    if (bone_index >= 0 && bone_index < 512) {
+    if (bone_index >= 0 && u32(bone_index) < arrayLength(&world_transforms)) {
         let position = vec4(in.position.xyz, 1.0);
-        out.clip_position = camera.mvp_matrix * world_transforms.transforms[bone_index] * position;
+        let world_transform = per_model.transform * world_transforms[bone_index];
+        out.clip_position = camera.mvp_matrix * world_transform * position;
         out.position = vec4(in.position.xyz, 1.0);
         // Use the normal as the color.
         out.normal = vec4(in.position.xyz, 0.0);
@@ -61,20 +62,32 @@ fn vs_axes(in: VertexInput) -> VertexOutput {
     return out;
 }
 
+@vertex
+fn vs_path(in: VertexInput) -> VertexOutput {
+    // Paths are in model space.
+    var out: VertexOutput;
+    out.clip_position = camera.mvp_matrix * per_model.transform * vec4(in.position.xyz, 1.0);
+    out.position = vec4(in.position.xyz, 1.0);
+    // Use the normal as the color.
+    out.normal = vec4(in.normal.xyz, 0.0);
+    return out;
+}
+
 @vertex
 fn vs_bone(in: VertexInput) -> VertexOutput {
     let bone_index = per_bone.indices.x;
     var out: VertexOutput;
-    if (bone_index >= 0 && bone_index < 512) {
-        let bone_pos = world_transforms.transforms[per_bone.indices.x] * vec4(0.0, 0.0, 0.0, 1.0);
+    if (bone_index >= 0 && u32(bone_index) < arrayLength(&world_transforms)) {
+        let world_transform = per_model.transform * world_transforms[bone_index];
+        let bone_pos = world_transform * vec4(0.0, 0.0, 0.0, 1.0);
 
         // Keep a constant size in pixels on screen.
         let scale_factor = distance(bone_pos.xyz, camera.camera_pos.xyz) * 0.0025;
         let position = vec4(in.position.xyz * scale_factor, 1.0);
 
-        out.clip_position = camera.mvp_matrix * world_transforms.transforms[per_bone.indices.x] * position;
+        out.clip_position = camera.mvp_matrix * world_transform * position;
         out.position = in.position;
-        out.normal = world_transforms.transforms[per_bone.indices.x] * vec4(in.normal.xyz, 0.0);
+        out.normal = world_transform * vec4(in.normal.xyz, 0.0);
     }
     return out;
 }
@@ -83,17 +96,18 @@ fn vs_bone(in: VertexInput) -> VertexOutput {
 fn vs_joint(in: VertexInput) -> VertexOutput {
     let bone_index = per_bone.indices.x;
     var out: VertexOutput;
-    if (bone_index >= 0 && bone_index < 512) {
-        let bone_pos = world_transforms.transforms[bone_index] * vec4(0.0, 0.0, 0.0, 1.0);
+    if (bone_index >= 0 && u32(bone_index) < arrayLength(&world_transforms)) {
+        let world_transform = per_model.transform * world_transforms[bone_index];
+        let bone_pos = world_transform * vec4(0.0, 0.0, 0.0, 1.0);
 
         // Keep a constant size in pixels on screen.
         // Only scale the ends of the joint without affecting the height.
         let scale_factor = distance(bone_pos.xyz, camera.camera_pos.xyz) * 0.005;
         let position = vec4(in.position.xyz * vec3(scale_factor, 1.0, scale_factor), 1.0);
 
-        out.clip_position = camera.mvp_matrix * world_transforms.transforms[bone_index] * position;
+        out.clip_position = camera.mvp_matrix * world_transform * position;
         out.position = in.position;
-        out.normal = world_transforms.transforms[bone_index] * vec4(in.normal.xyz, 0.0);
+        out.normal = world_transform * vec4(in.normal.xyz, 0.0);
     }
     return out;
 }
@@ -104,8 +118,8 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let shading = mix(0.5, 1.0, dot(viewVector, normalize(in.normal.xyz)));
     var color = vec3(0.0);
     let bone_index = per_bone.indices.x;
-    if (bone_index >= 0 && bone_index < 512) {
-        color = bone_colors.colors[bone_index].xyz * shading;
+    if (bone_index >= 0 && u32(bone_index) < arrayLength(&bone_colors)) {
+        color = bone_colors[bone_index].xyz * shading;
     }
     return vec4(pow(color, vec3(2.2)), 1.0);
 }
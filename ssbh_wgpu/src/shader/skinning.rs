@@ -67,21 +67,16 @@ const _: () = assert!(
     bytemuck::Zeroable,
     encase::ShaderType
 )]
-pub struct AnimatedWorldTransforms {
-    pub transforms: [glam::Mat4; 512],
-    pub transforms_inv_transpose: [glam::Mat4; 512],
+pub struct MeshObjectInfo {
+    pub parent_index: glam::IVec4,
 }
 const _: () = assert!(
-    std::mem::size_of:: < AnimatedWorldTransforms > () == 65536,
-    "size of AnimatedWorldTransforms does not match WGSL"
-);
-const _: () = assert!(
-    memoffset::offset_of!(AnimatedWorldTransforms, transforms) == 0,
-    "offset of AnimatedWorldTransforms.transforms does not match WGSL"
+    std::mem::size_of:: < MeshObjectInfo > () == 16,
+    "size of MeshObjectInfo does not match WGSL"
 );
 const _: () = assert!(
-    memoffset::offset_of!(AnimatedWorldTransforms, transforms_inv_transpose) == 32768,
-    "offset of AnimatedWorldTransforms.transforms_inv_transpose does not match WGSL"
+    memoffset::offset_of!(MeshObjectInfo, parent_index) == 0,
+    "offset of MeshObjectInfo.parent_index does not match WGSL"
 );
 #[repr(C)]
 #[derive(
@@ -93,37 +88,20 @@ const _: () = assert!(
     bytemuck::Zeroable,
     encase::ShaderType
 )]
-pub struct WorldTransforms {
-    pub transforms: [glam::Mat4; 512],
+pub struct PerModel {
+    pub transform: glam::Mat4,
+    pub transform_inv_transpose: glam::Mat4,
 }
 const _: () = assert!(
-    std::mem::size_of:: < WorldTransforms > () == 32768,
-    "size of WorldTransforms does not match WGSL"
+    std::mem::size_of:: < PerModel > () == 128, "size of PerModel does not match WGSL"
 );
 const _: () = assert!(
-    memoffset::offset_of!(WorldTransforms, transforms) == 0,
-    "offset of WorldTransforms.transforms does not match WGSL"
+    memoffset::offset_of!(PerModel, transform) == 0,
+    "offset of PerModel.transform does not match WGSL"
 );
-#[repr(C)]
-#[derive(
-    Debug,
-    Copy,
-    Clone,
-    PartialEq,
-    bytemuck::Pod,
-    bytemuck::Zeroable,
-    encase::ShaderType
-)]
-pub struct MeshObjectInfo {
-    pub parent_index: glam::IVec4,
-}
 const _: () = assert!(
-    std::mem::size_of:: < MeshObjectInfo > () == 16,
-    "size of MeshObjectInfo does not match WGSL"
-);
-const _: () = assert!(
-    memoffset::offset_of!(MeshObjectInfo, parent_index) == 0,
-    "offset of MeshObjectInfo.parent_index does not match WGSL"
+    memoffset::offset_of!(PerModel, transform_inv_transpose) == 64,
+    "offset of PerModel.transform_inv_transpose does not match WGSL"
 );
 #[repr(C)]
 #[derive(
@@ -237,7 +215,9 @@ pub mod bind_groups {
     pub struct BindGroup1(wgpu::BindGroup);
     pub struct BindGroupLayout1<'a> {
         pub transforms: wgpu::BufferBinding<'a>,
+        pub transforms_inv_transpose: wgpu::BufferBinding<'a>,
         pub world_transforms: wgpu::BufferBinding<'a>,
+        pub per_model: wgpu::BufferBinding<'a>,
     }
     const LAYOUT_DESCRIPTOR1: wgpu::BindGroupLayoutDescriptor = wgpu::BindGroupLayoutDescriptor {
         label: None,
@@ -246,7 +226,9 @@ pub mod bind_groups {
                 binding: 0,
                 visibility: wgpu::ShaderStages::COMPUTE,
                 ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
+                    ty: wgpu::BufferBindingType::Storage {
+                        read_only: true,
+                    },
                     has_dynamic_offset: false,
                     min_binding_size: None,
                 },
@@ -255,6 +237,30 @@ pub mod bind_groups {
             wgpu::BindGroupLayoutEntry {
                 binding: 1,
                 visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage {
+                        read_only: true,
+                    },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage {
+                        read_only: true,
+                    },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::COMPUTE,
                 ty: wgpu::BindingType::Buffer {
                     ty: wgpu::BufferBindingType::Uniform,
                     has_dynamic_offset: false,
@@ -281,10 +287,20 @@ pub mod bind_groups {
                             },
                             wgpu::BindGroupEntry {
                                 binding: 1,
+                                resource: wgpu::BindingResource::Buffer(
+                                    bindings.transforms_inv_transpose,
+                                ),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 2,
                                 resource: wgpu::BindingResource::Buffer(
                                     bindings.world_transforms,
                                 ),
                             },
+                            wgpu::BindGroupEntry {
+                                binding: 3,
+                                resource: wgpu::BindingResource::Buffer(bindings.per_model),
+                            },
                         ],
                         label: None,
                     },
@@ -10,24 +10,6 @@ struct VertexWeight {
     weights: vec4<f32>,
 };
 
-// The in game buffer is vec4[4096] with the first vec4 containing a u32 bone count.
-// This allows at most 511 bones with 2 matrices per bone.
-// TODO: What two matrices are stored per bone?
-// Remove the length field to improve compatibility.
-// This gives a more generous alignment without exceeding 65536 bytes.
-struct AnimatedWorldTransforms {
-    // bone_world.inv() * animated_bone_world
-    transforms: array<mat4x4<f32>, 512>,
-    // Inverse transpose of above to use for normals and tangents.
-    transforms_inv_transpose: array<mat4x4<f32>, 512>,
-};
-
-struct WorldTransforms {
-    // The world transform of each bone.
-    // This is used for parenting objects to bones.
-    transforms: array<mat4x4<f32>, 512>
-};
-
 // TODO: Rework this to PerMesh?
 struct MeshObjectInfo {
     // TODO: Alignment?
@@ -35,6 +17,12 @@ struct MeshObjectInfo {
     parent_index: vec4<i32>
 };
 
+struct PerModel {
+    // The root transform of the model applied after skinning.
+    transform: mat4x4<f32>,
+    transform_inv_transpose: mat4x4<f32>,
+};
+
 struct SkinningSettings {
     enable_parenting: vec4<u32>,
     enable_skinning: vec4<u32>
@@ -44,8 +32,20 @@ struct SkinningSettings {
 @group(0) @binding(1) var<storage, read> vertex_weights : array<VertexWeight>;
 @group(0) @binding(2) var<storage, read_write> dst : array<VertexInput0>;
 
-@group(1) @binding(0) var<uniform> transforms: AnimatedWorldTransforms;
-@group(1) @binding(1) var<uniform> world_transforms: WorldTransforms;
+// The in game buffer is vec4[4096] with the first vec4 containing a u32 bone count.
+// This allows at most 511 bones with 2 matrices per bone.
+// TODO: What two matrices are stored per bone?
+// Use storage buffers instead to support any number of bones.
+
+// bone_world.inv() * animated_bone_world
+@group(1) @binding(0) var<storage, read> transforms: array<mat4x4<f32>>;
+// Inverse transpose of above to use for normals and tangents.
+@group(1) @binding(1) var<storage, read> transforms_inv_transpose: array<mat4x4<f32>>;
+// The world transform of each bone.
+// This is used for parenting objects to bones.
+@group(1) @binding(2) var<storage, read> world_transforms: array<mat4x4<f32>>;
+// Models attached to a bone of another model move with the bone.
+@group(1) @binding(3) var<uniform> per_model: PerModel;
 
 @group(2) @binding(0) var<uniform> mesh_object_info: MeshObjectInfo;
 
@@ -74,10 +74,10 @@ fn main(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     // Assume the object won't also have vertex weights.
     // The application of vertex weights "resets" the vectors.
     let parent_index = mesh_object_info.parent_index.x;
-    if (settings.enable_parenting.x == 1u && parent_index >= 0 && parent_index < 512) {
-        position = (world_transforms.transforms[parent_index] * vec4(position, 1.0)).xyz;
-        normal = (world_transforms.transforms[parent_index] * vec4(normal, 0.0)).xyz;
-        tangent = (world_transforms.transforms[parent_index] * vec4(tangent, 0.0)).xyz;
+    if (settings.enable_parenting.x == 1u && parent_index >= 0 && u32(parent_index) < arrayLength(&world_transforms)) {
+        position = (world_transforms[parent_index] * vec4(position, 1.0)).xyz;
+        normal = (world_transforms[parent_index] * vec4(normal, 0.0)).xyz;
+        tangent = (world_transforms[parent_index] * vec4(tangent, 0.0)).xyz;
     }
     
     // Disabling skinning if the first influence is unused.
@@ -89,16 +89,21 @@ fn main(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
         tangent = vec3(0.0);
 
         for (var i = 0; i < 4; i = i + 1) {
-            // Only 511 influences are supported in game.
+            // Only 511 bones are supported in game, but allow any bone in the skel.
             let bone_index = influence.bone_indices[i];
-            if (bone_index >= 0 && bone_index < 511) {
-                position = position + (transforms.transforms[bone_index] * vec4(vertex.position0.xyz, 1.0) * influence.weights[i]).xyz;
-                normal = normal + (transforms.transforms_inv_transpose[bone_index] * vec4(vertex.normal0.xyz, 0.0) * influence.weights[i]).xyz;
-                tangent = tangent + (transforms.transforms_inv_transpose[bone_index] * vec4(vertex.tangent0.xyz, 0.0) * influence.weights[i]).xyz;
+            if (bone_index >= 0 && u32(bone_index) < arrayLength(&transforms)) {
+                position = position + (transforms[bone_index] * vec4(vertex.position0.xyz, 1.0) * influence.weights[i]).xyz;
+                normal = normal + (transforms_inv_transpose[bone_index] * vec4(vertex.normal0.xyz, 0.0) * influence.weights[i]).xyz;
+                tangent = tangent + (transforms_inv_transpose[bone_index] * vec4(vertex.tangent0.xyz, 0.0) * influence.weights[i]).xyz;
             }
         }
     }
 
+    // Apply the root transform last to move the entire model.
+    position = (per_model.transform * vec4(position, 1.0)).xyz;
+    normal = (per_model.transform_inv_transpose * vec4(normal, 0.0)).xyz;
+    tangent = (per_model.transform_inv_transpose * vec4(tangent, 0.0)).xyz;
+
     var out: VertexInput0;
     out.position0 = vec4(position, 1.0);
     out.normal0 = vec4(normalize(normal), 0.0);
@@ -77,16 +77,25 @@ const _: () = assert!(
     bytemuck::Zeroable,
     encase::ShaderType
 )]
-pub struct WorldTransforms {
-    pub transforms: [glam::Mat4; 512],
+pub struct PerShape {
+    pub bone_indices: glam::IVec4,
+    pub start_transform: glam::Mat4,
+    pub color: glam::Vec4,
 }
 const _: () = assert!(
-    std::mem::size_of:: < WorldTransforms > () == 32768,
-    "size of WorldTransforms does not match WGSL"
+    std::mem::size_of:: < PerShape > () == 96, "size of PerShape does not match WGSL"
+);
+const _: () = assert!(
+    memoffset::offset_of!(PerShape, bone_indices) == 0,
+    "offset of PerShape.bone_indices does not match WGSL"
+);
+const _: () = assert!(
+    memoffset::offset_of!(PerShape, start_transform) == 16,
+    "offset of PerShape.start_transform does not match WGSL"
 );
 const _: () = assert!(
-    memoffset::offset_of!(WorldTransforms, transforms) == 0,
-    "offset of WorldTransforms.transforms does not match WGSL"
+    memoffset::offset_of!(PerShape, color) == 80,
+    "offset of PerShape.color does not match WGSL"
 );
 #[repr(C)]
 #[derive(
@@ -98,25 +107,20 @@ const _: () = assert!(
     bytemuck::Zeroable,
     encase::ShaderType
 )]
-pub struct PerShape {
-    pub bone_indices: glam::IVec4,
-    pub start_transform: glam::Mat4,
-    pub color: glam::Vec4,
+pub struct PerModel {
+    pub transform: glam::Mat4,
+    pub transform_inv_transpose: glam::Mat4,
 }
 const _: () = assert!(
-    std::mem::size_of:: < PerShape > () == 96, "size of PerShape does not match WGSL"
+    std::mem::size_of:: < PerModel > () == 128, "size of PerModel does not match WGSL"
 );
 const _: () = assert!(
-    memoffset::offset_of!(PerShape, bone_indices) == 0,
-    "offset of PerShape.bone_indices does not match WGSL"
-);
-const _: () = assert!(
-    memoffset::offset_of!(PerShape, start_transform) == 16,
-    "offset of PerShape.start_transform does not match WGSL"
+    memoffset::offset_of!(PerModel, transform) == 0,
+    "offset of PerModel.transform does not match WGSL"
 );
 const _: () = assert!(
-    memoffset::offset_of!(PerShape, color) == 80,
-    "offset of PerShape.color does not match WGSL"
+    memoffset::offset_of!(PerModel, transform_inv_transpose) == 64,
+    "offset of PerModel.transform_inv_transpose does not match WGSL"
 );
 pub mod bind_groups {
     pub struct BindGroup0(wgpu::BindGroup);
@@ -166,6 +170,7 @@ pub mod bind_groups {
     pub struct BindGroup1(wgpu::BindGroup);
     pub struct BindGroupLayout1<'a> {
         pub world_transforms: wgpu::BufferBinding<'a>,
+        pub per_model: wgpu::BufferBinding<'a>,
     }
     const LAYOUT_DESCRIPTOR1: wgpu::BindGroupLayoutDescriptor = wgpu::BindGroupLayoutDescriptor {
         label: None,
@@ -173,6 +178,18 @@ pub mod bind_groups {
             wgpu::BindGroupLayoutEntry {
                 binding: 0,
                 visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage {
+                        read_only: true,
+                    },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
                 ty: wgpu::BindingType::Buffer {
                     ty: wgpu::BufferBindingType::Uniform,
                     has_dynamic_offset: false,
@@ -199,6 +216,10 @@ pub mod bind_groups {
                                     bindings.world_transforms,
                                 ),
                             },
+                            wgpu::BindGroupEntry {
+                                binding: 1,
+                                resource: wgpu::BindingResource::Buffer(bindings.per_model),
+                            },
                         ],
                         label: None,
                     },
@@ -15,10 +15,6 @@ struct CameraTransforms {
     screen_dimensions: vec4<f32>, // width, height, scale, _
 };
 
-struct WorldTransforms {
-    transforms: array<mat4x4<f32>, 512>
-};
-
 // Swing collisions can use two bones like capsules.
 // Some shapes like spheres will use only one bone.
 struct PerShape {
@@ -27,11 +23,20 @@ struct PerShape {
     color: vec4<f32>
 }
 
+struct PerModel {
+    transform: mat4x4<f32>,
+    transform_inv_transpose: mat4x4<f32>,
+};
+
 @group(0) @binding(0)
 var<uniform> camera: CameraTransforms;
 
 @group(1) @binding(0)
-var<uniform> world_transforms: WorldTransforms;
+var<storage, read> world_transforms: array<mat4x4<f32>>;
+
+// The model-to-world transform applied to all shapes.
+@group(1) @binding(1)
+var<uniform> per_model: PerModel;
 
 @group(2) @binding(0)
 var<uniform> per_shape: PerShape;
@@ -46,9 +51,11 @@ fn vs_main(in: VertexInput) -> VertexOutput {
     var out: VertexOutput;
 
     var world_position = per_shape.start_transform * vec4(in.position.xyz, 1.0);
-    if (per_shape.bone_indices.x >= 0 && per_shape.bone_indices.x < 512) {
-        world_position = world_transforms.transforms[per_shape.bone_indices.x] * world_position;
+    let bone_index = per_shape.bone_indices.x;
+    if (bone_index >= 0 && u32(bone_index) < arrayLength(&world_transforms)) {
+        world_position = world_transforms[bone_index] * world_position;
     }
+    world_position = per_model.transform * world_position;
 
     out.clip_position = camera.mvp_matrix * world_position;
     return out;
@@ -198,6 +198,41 @@ fn capsule_indices(sector_count: u32, stack_count: u32) -> Vec<u32> {
         .collect()
 }
 
+pub fn oval_mesh_buffers(device: &wgpu::Device, length: f32, radius: f32) -> IndexedMeshBuffers {
+    IndexedMeshBuffers::from_vertices(
+        device,
+        &oval_vertices(8, 8, length, radius),
+        &sphere_indices(8, 8, SphereRange::Full),
+    )
+}
+
+/// Generates an oval along the Z-axis with its tips at `-length / 2` and `length / 2`
+/// and a cross section with `radius` in the XY plane.
+/// The indices are the same as [sphere_indices] for [SphereRange::Full].
+pub fn oval_vertices(
+    sector_count: u32,
+    stack_count: u32,
+    length: f32,
+    radius: f32,
+) -> Vec<[f32; 4]> {
+    // Scale a unit sphere to get an ellipsoid with semi-axes (radius, radius, length / 2).
+    let half_length = length / 2.0;
+
+    sphere_vertices(sector_count, stack_count, SphereRange::Full)
+        .chunks_exact(2)
+        .flat_map(|v| {
+            let [x, y, z, _] = v[0];
+            let position = [x * radius, y * radius, z * half_length, 1.0];
+
+            // Scaling by S transforms normals by the inverse transpose of S.
+            // Multiply by radius * half_length to avoid dividing by zero for flat ovals.
+            let normal =
+                glam::vec3(x * half_length, y * half_length, z * radius).normalize_or_zero();
+            [position, normal.extend(1.0).to_array()]
+        })
+        .collect()
+}
+
 fn unit_circle_vertices(sector_count: u32) -> Vec<[f32; 4]> {
     let mut vertices = Vec::new();
     let sector_step = 2.0 * PI / sector_count as f32;
@@ -278,3 +313,63 @@ fn plane_indices() -> Vec<u32> {
     // TODO: Is this the correct winding order?
     vec![0, 1, 2, 2, 1, 3]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_vector_relative_eq;
+
+    #[test]
+    fn oval_vertices_count() {
+        assert_eq!(
+            sphere_vertices(8, 8, SphereRange::Full).len(),
+            oval_vertices(8, 8, 4.0, 1.0).len()
+        );
+    }
+
+    #[test]
+    fn oval_vertices_tips_and_equator() {
+        let vertices = oval_vertices(4, 4, 6.0, 2.0);
+
+        // The first and last stacks are the tips.
+        assert_vector_relative_eq!([0.0, 0.0, 3.0, 1.0], vertices[0]);
+        assert_vector_relative_eq!([0.0, 0.0, 1.0, 1.0], vertices[1]);
+        assert_vector_relative_eq!([0.0, 0.0, -3.0, 1.0], vertices[vertices.len() - 2]);
+        assert_vector_relative_eq!([0.0, 0.0, -1.0, 1.0], vertices[vertices.len() - 1]);
+
+        // The middle stack is the widest part of the oval.
+        let equator = 2 * 2 * 5;
+        assert_vector_relative_eq!([2.0, 0.0, 0.0, 1.0], vertices[equator]);
+        assert_vector_relative_eq!([1.0, 0.0, 0.0, 1.0], vertices[equator + 1]);
+    }
+
+    #[test]
+    fn oval_vertices_normals() {
+        let vertices = oval_vertices(8, 8, 4.0, 1.0);
+        for v in vertices.chunks_exact(2) {
+            let position = glam::Vec4::from(v[0]).truncate();
+            let normal = glam::Vec4::from(v[1]).truncate();
+            assert!((normal.length() - 1.0).abs() < 0.0001);
+
+            // The normal should face away from the center of the oval.
+            assert!(position.dot(normal) >= 0.0);
+        }
+    }
+
+    #[test]
+    fn oval_vertices_matches_sphere() {
+        let sphere = sphere_vertices(8, 8, SphereRange::Full);
+        let oval = oval_vertices(8, 8, 2.0, 1.0);
+        for (s, o) in sphere.iter().zip(oval.iter()) {
+            assert_vector_relative_eq!(s, o);
+        }
+    }
+
+    #[test]
+    fn oval_vertices_flat() {
+        // A zero length oval is a disk with normals along the Z-axis.
+        let vertices = oval_vertices(4, 4, 0.0, 1.0);
+        assert_vector_relative_eq!([0.0, 0.0, 1.0, 1.0], vertices[3]);
+        assert_eq!(0.0, vertices[2][2]);
+    }
+}
@@ -5,6 +5,9 @@ use prc::{
 };
 use std::{io::SeekFrom, path::Path};
 
+mod simulation;
+pub use simulation::{Simulator, DEFAULT_GRAVITY};
+
 #[derive(Debug, Prc, Clone)]
 pub struct SwingPrc {
     pub swingbones: Vec<SwingBone>,
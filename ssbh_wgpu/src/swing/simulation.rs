@@ -0,0 +1,985 @@
+use std::collections::HashMap;
+
+use glam::Vec4Swizzles;
+use prc::hash40::{hash40, Hash40};
+use ssbh_data::skel_data::SkelData;
+
+use super::{Param, SwingBone, SwingPrc};
+use crate::animation::AnimationTransforms;
+
+/// The default acceleration for [Simulator::gravity] in units per frame squared.
+pub const DEFAULT_GRAVITY: glam::Vec3 = glam::Vec3::new(0.0, -0.05, 0.0);
+
+// Reset instead of simulating long gaps like when seeking to avoid stalling.
+const MAX_STEPS_PER_UPDATE: usize = 10;
+
+const COLLISION_ITERATIONS: usize = 4;
+
+/// Simulates the swing bone chains from a [SwingPrc] on the CPU.
+///
+/// Each chain follows the animated pose as a goal while reacting to gravity,
+/// air resistance, angle limits, and the collision shapes in the [SwingPrc].
+/// This only approximates the in game simulation.
+/// Water resistance, wind, and ground collisions are not simulated.
+pub struct Simulator {
+    /// The acceleration applied to each swing bone in units per frame squared.
+    pub gravity: glam::Vec3,
+    chains: Vec<SwingChain>,
+    collisions: Vec<Collision>,
+    parent_indices: Vec<Option<usize>>,
+    previous_frame: Option<f32>,
+}
+
+struct SwingChain {
+    // The bones from the start bone to the end bone.
+    bone_indices: Vec<usize>,
+    // The child of the end bone used to orient the end bone if present.
+    tip_index: Option<usize>,
+    params: Vec<ChainParam>,
+    // The simulated world position of each joint.
+    positions: Vec<glam::Vec3>,
+    previous_positions: Vec<glam::Vec3>,
+}
+
+struct ChainParam {
+    param: Param,
+    // Indices into the simulator's collisions.
+    collisions: Vec<usize>,
+}
+
+enum Collision {
+    Sphere {
+        bone: usize,
+        center: glam::Vec3,
+        radius: f32,
+    },
+    Oval {
+        start_bone: usize,
+        end_bone: usize,
+        start_offset: glam::Vec3,
+        end_offset: glam::Vec3,
+        radius: f32,
+    },
+    Ellipsoid {
+        bone: usize,
+        center: glam::Vec3,
+        scale: glam::Vec3,
+    },
+    Capsule {
+        start_bone: usize,
+        end_bone: usize,
+        start_offset: glam::Vec3,
+        end_offset: glam::Vec3,
+        start_radius: f32,
+        end_radius: f32,
+    },
+    Plane {
+        bone: usize,
+        normal: glam::Vec3,
+        distance: f32,
+    },
+}
+
+impl Simulator {
+    /// Finds the swing bone chains and collisions in `swing_prc` using the bones in `skel`.
+    /// Chains and collisions with missing bones are ignored.
+    pub fn new(swing_prc: &SwingPrc, skel: &SkelData) -> Self {
+        let bone_hashes: Vec<_> = skel
+            .bones
+            .iter()
+            .map(|b| hash40(&b.name.to_lowercase()))
+            .collect();
+        let parent_indices = skel.bones.iter().map(|b| b.parent_index).collect();
+        Self::from_bones(swing_prc, &bone_hashes, parent_indices)
+    }
+
+    fn from_bones(
+        swing_prc: &SwingPrc,
+        bone_hashes: &[Hash40],
+        parent_indices: Vec<Option<usize>>,
+    ) -> Self {
+        let (collisions, collision_indices) = collisions(swing_prc, bone_hashes);
+
+        let chains = swing_prc
+            .swingbones
+            .iter()
+            .filter_map(|s| SwingChain::new(s, bone_hashes, &parent_indices, &collision_indices))
+            .collect();
+
+        Self {
+            gravity: DEFAULT_GRAVITY,
+            chains,
+            collisions,
+            parent_indices,
+            previous_frame: None,
+        }
+    }
+
+    /// Moves the swing bones back to the animated pose on the next update.
+    pub fn reset(&mut self) {
+        self.previous_frame = None;
+        for chain in &mut self.chains {
+            chain.positions.clear();
+            chain.previous_positions.clear();
+        }
+    }
+
+    /// Advances the simulation to `current_frame` and applies the swing bone motion to `transforms`.
+    ///
+    /// The `transforms` should already contain the animated pose for `current_frame`.
+    /// The simulation resets if `current_frame` moves backwards like when looping.
+    pub fn update(&mut self, transforms: &mut AnimationTransforms, current_frame: f32) {
+        // The transforms may be for a different skel than the one used to create the simulator.
+        if transforms.bone_count() < self.parent_indices.len() {
+            return;
+        }
+
+        let step_count = match self.previous_frame {
+            Some(previous) if current_frame >= previous => {
+                // Only step on whole frames to support fractional playback speeds.
+                (current_frame.floor() - previous.floor()) as usize
+            }
+            _ => {
+                self.reset();
+                0
+            }
+        };
+        let step_count = if step_count > MAX_STEPS_PER_UPDATE {
+            // Start from the animated pose instead of simulating the skipped frames.
+            self.reset();
+            0
+        } else {
+            step_count
+        };
+        self.previous_frame = Some(current_frame);
+
+        // Collisions use the animated pose before any swing bones are moved.
+        let world_transforms = &transforms.world_transforms;
+        for chain in &mut self.chains {
+            chain.simulate(world_transforms, &self.collisions, self.gravity, step_count);
+        }
+
+        self.apply(transforms);
+    }
+
+    fn apply(&self, transforms: &mut AnimationTransforms) {
+        let world_transforms = transforms.world_transforms.clone();
+
+        // Find the change in each swing bone's world transform.
+        let mut deltas = vec![None; self.parent_indices.len()];
+        for chain in &self.chains {
+            let joints = chain.joints();
+            if chain.positions.len() != joints.len() {
+                continue;
+            }
+
+            let mut parent_delta = self
+                .ancestor_delta(&deltas, chain.bone_indices[0])
+                .unwrap_or(glam::Mat4::IDENTITY);
+
+            for (j, bone) in chain.bone_indices.iter().enumerate() {
+                let mut world = parent_delta * world_transforms[*bone];
+
+                // Rotate the bone about its position to point to the simulated child position.
+                if let (Some(child), Some(target)) = (joints.get(j + 1), chain.positions.get(j + 1))
+                {
+                    let position = world.col(3).xyz();
+                    let child_position = (parent_delta * world_transforms[*child]).col(3).xyz();
+                    let rotation = glam::Quat::from_rotation_arc(
+                        (child_position - position).normalize_or_zero(),
+                        (*target - position).normalize_or_zero(),
+                    );
+                    world = glam::Mat4::from_translation(position)
+                        * glam::Mat4::from_quat(rotation)
+                        * glam::Mat4::from_translation(-position)
+                        * world;
+                }
+
+                let delta = world * world_transforms[*bone].inverse();
+                deltas[*bone] = Some(delta);
+                parent_delta = delta;
+            }
+        }
+
+        // Children of swing bones should move with their parents.
+        let deltas: Vec<_> = (0..deltas.len())
+            .map(|i| deltas[i].or_else(|| self.ancestor_delta(&deltas, i)))
+            .collect();
+
+        for (i, delta) in deltas.iter().enumerate() {
+            if let Some(delta) = delta {
+                transforms.world_transforms[i] = *delta * world_transforms[i];
+
+                let transform = *delta * transforms.animated_world_transforms.transforms[i];
+                transforms.animated_world_transforms.transforms[i] = transform;
+                transforms
+                    .animated_world_transforms
+                    .transforms_inv_transpose[i] = transform.inverse().transpose();
+            }
+        }
+    }
+
+    fn ancestor_delta(&self, deltas: &[Option<glam::Mat4>], index: usize) -> Option<glam::Mat4> {
+        // Limit the iterations in case of cycles in the skeleton.
+        let mut current = self.parent_indices.get(index).copied().flatten();
+        for _ in 0..self.parent_indices.len() {
+            let parent = current?;
+            if let Some(delta) = deltas.get(parent).copied().flatten() {
+                return Some(delta);
+            }
+            current = self.parent_indices.get(parent).copied().flatten();
+        }
+        None
+    }
+}
+
+impl SwingChain {
+    fn new(
+        swing_bone: &SwingBone,
+        bone_hashes: &[Hash40],
+        parent_indices: &[Option<usize>],
+        collision_indices: &HashMap<u64, usize>,
+    ) -> Option<Self> {
+        let start = bone_position(bone_hashes, swing_bone.start_bonename)?;
+        let end = bone_position(bone_hashes, swing_bone.end_bonename)?;
+
+        // Find the path from the end bone up to the start bone.
+        let mut bone_indices = vec![end];
+        let mut current = end;
+        while current != start {
+            current = parent_indices.get(current).copied().flatten()?;
+            bone_indices.push(current);
+            if bone_indices.len() > parent_indices.len() {
+                return None;
+            }
+        }
+        bone_indices.reverse();
+
+        let tip_index = parent_indices.iter().position(|p| *p == Some(end));
+
+        // Use the last params for any remaining bones.
+        let params: Vec<_> = (0..bone_indices.len())
+            .filter_map(|i| {
+                let param = swing_bone
+                    .params
+                    .get(i)
+                    .or_else(|| swing_bone.params.last())?;
+                Some(ChainParam {
+                    param: param.clone(),
+                    collisions: param
+                        .collisions
+                        .iter()
+                        .filter_map(|c| collision_indices.get(&c.0).copied())
+                        .collect(),
+                })
+            })
+            .collect();
+        if params.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            bone_indices,
+            tip_index,
+            params,
+            positions: Vec::new(),
+            previous_positions: Vec::new(),
+        })
+    }
+
+    fn joints(&self) -> Vec<usize> {
+        self.bone_indices
+            .iter()
+            .copied()
+            .chain(self.tip_index)
+            .collect()
+    }
+
+    fn simulate(
+        &mut self,
+        world_transforms: &[glam::Mat4],
+        collisions: &[Collision],
+        gravity: glam::Vec3,
+        step_count: usize,
+    ) {
+        let joints = self.joints();
+        let targets: Vec<_> = joints
+            .iter()
+            .map(|i| world_transforms[*i].col(3).xyz())
+            .collect();
+
+        if self.positions.len() != targets.len() {
+            self.positions = targets.clone();
+            self.previous_positions = targets.clone();
+        }
+
+        for _ in 0..step_count {
+            // The start of the chain follows the animation.
+            self.positions[0] = targets[0];
+            self.previous_positions[0] = targets[0];
+
+            for k in 1..joints.len() {
+                // Each joint is the end of the previous bone.
+                let param = &self.params[(k - 1).min(self.params.len() - 1)];
+                let bone_rotation = world_transforms[joints[k - 1]]
+                    .to_scale_rotation_translation()
+                    .1;
+
+                let position = self.positions[k];
+                let parent = self.positions[k - 1];
+                let length = targets[k].distance(targets[k - 1]);
+                let rest_direction = (targets[k] - targets[k - 1]).normalize_or_zero();
+
+                let velocity = (position - self.previous_positions[k])
+                    / (1.0 + param.param.airresistance.max(0.0));
+                let mut new_position = position + velocity + gravity * param.param.localgravity;
+
+                // Pull the joint towards the animated pose.
+                let goal_strength = param.param.goalstrength.max(0.0);
+                new_position = new_position.lerp(targets[k], goal_strength / (1.0 + goal_strength));
+
+                let direction = limit_angles(
+                    bone_rotation,
+                    direction_or(new_position - parent, rest_direction),
+                    rest_direction,
+                    &param.param,
+                );
+                new_position = parent + direction * length;
+
+                let size = param.param.collisionsizetip;
+                let mut collided = false;
+                // Keeping the bone length can move the joint back into a collision.
+                for _ in 0..COLLISION_ITERATIONS {
+                    let mut pushed_out = false;
+                    for collision in param.collisions.iter().filter_map(|i| collisions.get(*i)) {
+                        if let Some(pushed) =
+                            collision.push_out(new_position, size, world_transforms)
+                        {
+                            new_position = pushed;
+                            pushed_out = true;
+                        }
+                    }
+                    if !pushed_out {
+                        break;
+                    }
+                    collided = true;
+                    new_position = parent + direction_or(new_position - parent, direction) * length;
+                }
+
+                self.previous_positions[k] = if collided {
+                    // Friction reduces the velocity for the next step.
+                    let friction = param.param.frictionrate.clamp(0.0, 1.0);
+                    position.lerp(new_position, friction)
+                } else {
+                    position
+                };
+                self.positions[k] = new_position;
+            }
+        }
+    }
+}
+
+impl Collision {
+    fn push_out(
+        &self,
+        point: glam::Vec3,
+        size: f32,
+        world_transforms: &[glam::Mat4],
+    ) -> Option<glam::Vec3> {
+        let bone_point = |bone: &usize, offset: &glam::Vec3| {
+            world_transforms
+                .get(*bone)
+                .map(|t| t.transform_point3(*offset))
+        };
+
+        match self {
+            Collision::Sphere {
+                bone,
+                center,
+                radius,
+            } => push_out_sphere(point, bone_point(bone, center)?, radius + size),
+            Collision::Oval {
+                start_bone,
+                end_bone,
+                start_offset,
+                end_offset,
+                radius,
+            } => push_out_oval(
+                point,
+                bone_point(start_bone, start_offset)?,
+                bone_point(end_bone, end_offset)?,
+                radius + size,
+                size,
+            ),
+            Collision::Ellipsoid {
+                bone,
+                center,
+                scale,
+            } => {
+                let transform = *world_transforms.get(*bone)?
+                    * glam::Mat4::from_translation(*center)
+                    * glam::Mat4::from_scale(*scale + size);
+
+                // Push the point out of the unit sphere in the ellipsoid's space.
+                let local = transform.inverse().transform_point3(point);
+                let pushed = push_out_sphere(local, glam::Vec3::ZERO, 1.0)?;
+                Some(transform.transform_point3(pushed))
+            }
+            Collision::Capsule {
+                start_bone,
+                end_bone,
+                start_offset,
+                end_offset,
+                start_radius,
+                end_radius,
+            } => push_out_capsule(
+                point,
+                bone_point(start_bone, start_offset)?,
+                bone_point(end_bone, end_offset)?,
+                start_radius + size,
+                end_radius + size,
+            ),
+            Collision::Plane {
+                bone,
+                normal,
+                distance,
+            } => {
+                let transform = world_transforms.get(*bone)?;
+                let normal = transform.transform_vector3(*normal).normalize_or_zero();
+                let origin = transform.col(3).xyz() + normal * *distance;
+                push_out_plane(point, origin, normal, size)
+            }
+        }
+    }
+}
+
+fn collisions(
+    swing_prc: &SwingPrc,
+    bone_hashes: &[Hash40],
+) -> (Vec<Collision>, HashMap<u64, usize>) {
+    let bone = |name: Hash40| bone_position(bone_hashes, name);
+
+    // Params refer to collisions by name.
+    let spheres = swing_prc.spheres.iter().map(|s| {
+        let collision = Collision::Sphere {
+            bone: bone(s.bonename)?,
+            center: glam::vec3(s.cx, s.cy, s.cz),
+            radius: s.radius,
+        };
+        Some((s.name, collision))
+    });
+
+    let ovals = swing_prc.ovals.iter().map(|o| {
+        let collision = Collision::Oval {
+            start_bone: bone(o.start_bonename)?,
+            end_bone: bone(o.end_bonename)?,
+            start_offset: glam::vec3(o.start_offset_x, o.start_offset_y, o.start_offset_z),
+            end_offset: glam::vec3(o.end_offset_x, o.end_offset_y, o.end_offset_z),
+            radius: o.radius,
+        };
+        Some((o.name, collision))
+    });
+
+    let ellipsoids = swing_prc.ellipsoids.iter().map(|e| {
+        // TODO: Is r rotation since it's usually 0?
+        let collision = Collision::Ellipsoid {
+            bone: bone(e.bonename)?,
+            center: glam::vec3(e.cx, e.cy, e.cz),
+            scale: glam::vec3(e.sx, e.sy, e.sz),
+        };
+        Some((e.name, collision))
+    });
+
+    let capsules = swing_prc.capsules.iter().map(|c| {
+        let collision = Collision::Capsule {
+            start_bone: bone(c.start_bonename)?,
+            end_bone: bone(c.end_bonename)?,
+            start_offset: glam::vec3(c.start_offset_x, c.start_offset_y, c.start_offset_z),
+            end_offset: glam::vec3(c.end_offset_x, c.end_offset_y, c.end_offset_z),
+            start_radius: c.start_radius,
+            end_radius: c.end_radius,
+        };
+        Some((c.name, collision))
+    });
+
+    let planes = swing_prc.planes.iter().map(|p| {
+        let collision = Collision::Plane {
+            bone: bone(p.bonename)?,
+            normal: glam::vec3(p.nx, p.ny, p.nz),
+            distance: p.distance,
+        };
+        Some((p.name, collision))
+    });
+
+    let mut collisions = Vec::new();
+    let mut indices = HashMap::new();
+    for (name, collision) in spheres
+        .chain(ovals)
+        .chain(ellipsoids)
+        .chain(capsules)
+        .chain(planes)
+        .flatten()
+    {
+        indices.insert(name.0, collisions.len());
+        collisions.push(collision);
+    }
+
+    (collisions, indices)
+}
+
+fn bone_position(bone_hashes: &[Hash40], name: Hash40) -> Option<usize> {
+    bone_hashes.iter().position(|h| *h == name)
+}
+
+fn direction_or(v: glam::Vec3, default: glam::Vec3) -> glam::Vec3 {
+    let direction = v.normalize_or_zero();
+    if direction == glam::Vec3::ZERO {
+        default
+    } else {
+        direction
+    }
+}
+
+fn limit_angles(
+    bone_rotation: glam::Quat,
+    direction: glam::Vec3,
+    rest_direction: glam::Vec3,
+    param: &Param,
+) -> glam::Vec3 {
+    // Compare angles in the bone's space since the limits are relative to the bone's axes.
+    let (rest_z, rest_y) = direction_angles(bone_rotation.inverse() * rest_direction);
+    let (z, y) = direction_angles(bone_rotation.inverse() * direction);
+
+    // Avoid panics with clamp if the limits are in the wrong order.
+    let limit = |angle: f32, min: f32, max: f32| {
+        angle
+            .max(min.to_radians())
+            .min(max.to_radians().max(min.to_radians()))
+    };
+    let z = rest_z + limit(wrap_angle(z - rest_z), param.minanglez, param.maxanglez);
+    let y = rest_y + limit(wrap_angle(y - rest_y), param.minangley, param.maxangley);
+
+    bone_rotation * direction_from_angles(z, y)
+}
+
+// The rotation about the Z-axis and then the Y-axis that points the X-axis along `direction`.
+fn direction_angles(direction: glam::Vec3) -> (f32, f32) {
+    let z = direction.y.atan2(direction.x);
+    let y = (-direction.z).atan2(direction.truncate().length());
+    (z, y)
+}
+
+fn direction_from_angles(z: f32, y: f32) -> glam::Vec3 {
+    glam::vec3(y.cos() * z.cos(), y.cos() * z.sin(), -y.sin())
+}
+
+fn wrap_angle(angle: f32) -> f32 {
+    // Wrap to the range -PI to PI.
+    let tau = std::f32::consts::TAU;
+    angle - tau * ((angle + std::f32::consts::PI) / tau).floor()
+}
+
+fn closest_point_on_segment(
+    point: glam::Vec3,
+    start: glam::Vec3,
+    end: glam::Vec3,
+) -> (glam::Vec3, f32) {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+    if length_squared == 0.0 {
+        return (start, 0.0);
+    }
+
+    let t = ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0);
+    (start + segment * t, t)
+}
+
+fn push_out_sphere(point: glam::Vec3, center: glam::Vec3, radius: f32) -> Option<glam::Vec3> {
+    let offset = point - center;
+    if offset.length() >= radius {
+        return None;
+    }
+
+    Some(center + direction_or(offset, glam::Vec3::Y) * radius)
+}
+
+fn push_out_capsule(
+    point: glam::Vec3,
+    start: glam::Vec3,
+    end: glam::Vec3,
+    start_radius: f32,
+    end_radius: f32,
+) -> Option<glam::Vec3> {
+    // The radius is interpolated along the segment for tapered capsules.
+    let (closest, t) = closest_point_on_segment(point, start, end);
+    let radius = start_radius + (end_radius - start_radius) * t;
+    push_out_sphere(point, closest, radius)
+}
+
+fn push_out_oval(
+    point: glam::Vec3,
+    start: glam::Vec3,
+    end: glam::Vec3,
+    radius: f32,
+    size: f32,
+) -> Option<glam::Vec3> {
+    // Ovals match the rendered shape with tips at the start and end points.
+    let center = (start + end) / 2.0;
+    let axis = direction_or(end - start, glam::Vec3::Z);
+    let half_length = start.distance(end) / 2.0 + size;
+    if radius <= 0.0 || half_length <= 0.0 {
+        return None;
+    }
+
+    // Push the point out of the unit circle in the oval's space.
+    let offset = point - center;
+    let along = offset.dot(axis);
+    let perpendicular = offset - axis * along;
+    let local = glam::vec2(perpendicular.length() / radius, along / half_length);
+    if local.length() >= 1.0 {
+        return None;
+    }
+
+    let local = local.try_normalize().unwrap_or(glam::Vec2::X);
+    let perpendicular_direction = direction_or(perpendicular, axis.any_orthonormal_vector());
+    Some(center + perpendicular_direction * local.x * radius + axis * local.y * half_length)
+}
+
+fn push_out_plane(
+    point: glam::Vec3,
+    origin: glam::Vec3,
+    normal: glam::Vec3,
+    size: f32,
+) -> Option<glam::Vec3> {
+    let distance = (point - origin).dot(normal) - size;
+    (distance < 0.0).then_some(point - normal * distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swing::{RotateOrder, Sphere};
+    use crate::{assert_matrix_relative_eq, assert_vector_relative_eq};
+
+    fn param(collisions: Vec<Hash40>) -> Param {
+        Param {
+            airresistance: 0.0,
+            waterresistance: 0.0,
+            minanglez: -180.0,
+            maxanglez: 180.0,
+            minangley: -90.0,
+            maxangley: 90.0,
+            collisionsizetip: 0.0,
+            collisionsizeroot: 0.0,
+            frictionrate: 0.0,
+            goalstrength: 0.0,
+            unk: 0.0,
+            localgravity: 1.0,
+            fallspeedscale: 1.0,
+            groundhit: 0,
+            windaffect: 0.0,
+            collisions,
+        }
+    }
+
+    fn swing_prc(params: Vec<Param>, spheres: Vec<Sphere>) -> SwingPrc {
+        SwingPrc {
+            swingbones: vec![SwingBone {
+                name: hash40("swing"),
+                start_bonename: hash40("a"),
+                end_bonename: hash40("b"),
+                params,
+                isskirt: 0,
+                rotateorder: RotateOrder::I32(0),
+                curverotatex: 0,
+                unk: None,
+            }],
+            spheres,
+            ovals: Vec::new(),
+            ellipsoids: Vec::new(),
+            capsules: Vec::new(),
+            planes: Vec::new(),
+        }
+    }
+
+    // A horizontal chain root -> a -> b -> c along the X-axis.
+    fn simulator(swing_prc: &SwingPrc) -> (Simulator, AnimationTransforms) {
+        let bone_hashes = vec![hash40("root"), hash40("a"), hash40("b"), hash40("c")];
+        let parent_indices = vec![None, Some(0), Some(1), Some(2)];
+        let simulator = Simulator::from_bones(swing_prc, &bone_hashes, parent_indices);
+
+        let mut transforms = AnimationTransforms::identity();
+        transforms.resize(4);
+        for (i, transform) in transforms.world_transforms.iter_mut().enumerate() {
+            *transform = glam::Mat4::from_translation(glam::vec3(i as f32, 0.0, 0.0));
+        }
+        (simulator, transforms)
+    }
+
+    fn position(transforms: &AnimationTransforms, i: usize) -> [f32; 3] {
+        transforms.world_transforms[i].col(3).xyz().to_array()
+    }
+
+    #[test]
+    fn chain_bones_and_tip() {
+        let (simulator, _) = simulator(&swing_prc(vec![param(Vec::new())], Vec::new()));
+        assert_eq!(1, simulator.chains.len());
+        assert_eq!(vec![1, 2], simulator.chains[0].bone_indices);
+        assert_eq!(Some(3), simulator.chains[0].tip_index);
+        assert_eq!(2, simulator.chains[0].params.len());
+    }
+
+    #[test]
+    fn chain_missing_bones() {
+        let mut prc = swing_prc(vec![param(Vec::new())], Vec::new());
+        prc.swingbones[0].end_bonename = hash40("d");
+        let (simulator, _) = simulator(&prc);
+        assert!(simulator.chains.is_empty());
+    }
+
+    #[test]
+    fn first_update_keeps_animated_pose() {
+        let (mut simulator, mut transforms) =
+            simulator(&swing_prc(vec![param(Vec::new())], Vec::new()));
+        let expected = transforms.world_transforms.clone();
+
+        simulator.update(&mut transforms, 0.0);
+        for (expected, actual) in expected.iter().zip(transforms.world_transforms.iter()) {
+            assert_matrix_relative_eq!(expected.to_cols_array_2d(), actual.to_cols_array_2d());
+        }
+    }
+
+    #[test]
+    fn gravity_preserves_lengths() {
+        let (mut simulator, transforms) =
+            simulator(&swing_prc(vec![param(Vec::new())], Vec::new()));
+
+        let mut result = transforms.world_transforms.clone();
+        for frame in 0..20 {
+            let mut frame_transforms = transforms.clone();
+            simulator.update(&mut frame_transforms, frame as f32);
+            result = frame_transforms.world_transforms;
+        }
+
+        // The root and start of the chain follow the animation.
+        assert_vector_relative_eq!([0.0, 0.0, 0.0], result[0].col(3).xyz().to_array());
+        assert_vector_relative_eq!([1.0, 0.0, 0.0], result[1].col(3).xyz().to_array());
+
+        // The rest of the chain falls while keeping the bone lengths.
+        let b = result[2].col(3).xyz();
+        let c = result[3].col(3).xyz();
+        assert!(b.y < 0.0);
+        assert!((b.distance(glam::vec3(1.0, 0.0, 0.0)) - 1.0).abs() < 0.0001);
+        assert!((c.distance(b) - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn update_animated_world_transforms() {
+        let (mut simulator, mut transforms) =
+            simulator(&swing_prc(vec![param(Vec::new())], Vec::new()));
+        let world = transforms.world_transforms.clone();
+
+        simulator.update(&mut transforms, 0.0);
+        simulator.update(&mut transforms, 1.0);
+
+        // The skinning transforms should include the same change as the world transforms.
+        let animated_transforms = &transforms.animated_world_transforms.transforms;
+        for ((world, previous_world), animated) in transforms
+            .world_transforms
+            .iter()
+            .zip(world.iter())
+            .zip(animated_transforms.iter())
+        {
+            let delta = *world * previous_world.inverse();
+            assert_matrix_relative_eq!(delta.to_cols_array_2d(), animated.to_cols_array_2d());
+        }
+    }
+
+    #[test]
+    fn goal_strength_follows_animation() {
+        let mut p = param(Vec::new());
+        p.goalstrength = 1000000.0;
+        let (mut simulator, transforms) = simulator(&swing_prc(vec![p], Vec::new()));
+
+        for frame in 0..5 {
+            let mut frame_transforms = transforms.clone();
+            simulator.update(&mut frame_transforms, frame as f32);
+            assert_vector_relative_eq!([3.0, 0.0, 0.0], position(&frame_transforms, 3));
+        }
+    }
+
+    #[test]
+    fn sphere_collision_blocks_falling() {
+        let sphere = Sphere {
+            name: hash40("sphere"),
+            bonename: hash40("root"),
+            cx: 2.0,
+            cy: -1.0,
+            cz: 0.0,
+            radius: 0.9,
+        };
+        let (mut simulator, transforms) = simulator(&swing_prc(
+            vec![param(vec![hash40("sphere")])],
+            vec![sphere],
+        ));
+        assert_eq!(1, simulator.collisions.len());
+
+        for frame in 0..20 {
+            let mut frame_transforms = transforms.clone();
+            simulator.update(&mut frame_transforms, frame as f32);
+
+            let b = frame_transforms.world_transforms[2].col(3).xyz();
+            assert!(b.distance(glam::vec3(2.0, -1.0, 0.0)) >= 0.9 - 0.0001);
+        }
+    }
+
+    #[test]
+    fn reset_when_looping() {
+        let (mut simulator, transforms) =
+            simulator(&swing_prc(vec![param(Vec::new())], Vec::new()));
+
+        for frame in [0.0, 1.0, 2.0, 0.0] {
+            let mut frame_transforms = transforms.clone();
+            simulator.update(&mut frame_transforms, frame);
+            if frame == 0.0 {
+                assert_vector_relative_eq!([3.0, 0.0, 0.0], position(&frame_transforms, 3));
+            }
+        }
+    }
+
+    #[test]
+    fn reset_when_skipping_frames() {
+        let (mut simulator, transforms) =
+            simulator(&swing_prc(vec![param(Vec::new())], Vec::new()));
+
+        for frame in [0.0, 1.0, 2.0, 20.0] {
+            let mut frame_transforms = AnimationTransforms::identity();
+            frame_transforms.world_transforms = transforms.world_transforms;
+            simulator.update(&mut frame_transforms, frame);
+            if frame == 20.0 {
+                assert_vector_relative_eq!([3.0, 0.0, 0.0], position(&frame_transforms, 3));
+            }
+        }
+    }
+
+    #[test]
+    fn limit_angles_within_limits() {
+        let p = param(Vec::new());
+        let direction = glam::vec3(1.0, 1.0, 0.0).normalize();
+        assert_vector_relative_eq!(
+            direction.to_array(),
+            limit_angles(glam::Quat::IDENTITY, direction, glam::Vec3::X, &p).to_array()
+        );
+    }
+
+    #[test]
+    fn limit_angles_clamp_z() {
+        let mut p = param(Vec::new());
+        p.minanglez = -10.0;
+        p.maxanglez = 10.0;
+        let direction = limit_angles(
+            glam::Quat::IDENTITY,
+            glam::vec3(0.0, 1.0, 0.0),
+            glam::Vec3::X,
+            &p,
+        );
+        let angle = 10f32.to_radians();
+        assert_vector_relative_eq!([angle.cos(), angle.sin(), 0.0], direction.to_array());
+    }
+
+    #[test]
+    fn limit_angles_clamp_y_rotated_bone() {
+        let mut p = param(Vec::new());
+        p.minangley = 0.0;
+        p.maxangley = 0.0;
+
+        // The bone's X-axis points along the world Y-axis.
+        let rotation = glam::Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let direction = limit_angles(
+            rotation,
+            glam::vec3(0.0, 1.0, 1.0).normalize(),
+            glam::Vec3::Y,
+            &p,
+        );
+        assert_vector_relative_eq!([0.0, 1.0, 0.0], direction.to_array());
+    }
+
+    #[test]
+    fn push_out_sphere_inside_outside() {
+        assert_eq!(
+            None,
+            push_out_sphere(glam::vec3(2.0, 0.0, 0.0), glam::Vec3::ZERO, 1.0)
+        );
+        assert_eq!(
+            Some(glam::vec3(0.0, 2.0, 0.0)),
+            push_out_sphere(glam::vec3(0.0, 0.5, 0.0), glam::Vec3::ZERO, 2.0)
+        );
+    }
+
+    #[test]
+    fn closest_point_on_segment_middle() {
+        let (closest, t) = closest_point_on_segment(
+            glam::vec3(1.0, 0.5, 0.0),
+            glam::Vec3::ZERO,
+            glam::vec3(4.0, 0.0, 0.0),
+        );
+        assert_eq!(glam::vec3(1.0, 0.0, 0.0), closest);
+        assert_eq!(0.25, t);
+    }
+
+    #[test]
+    fn push_out_capsule_tapered() {
+        let start = glam::Vec3::ZERO;
+        let end = glam::vec3(4.0, 0.0, 0.0);
+
+        let pushed = push_out_capsule(glam::vec3(2.0, 0.5, 0.0), start, end, 1.0, 3.0).unwrap();
+        assert_vector_relative_eq!([2.0, 2.0, 0.0], pushed.to_array());
+
+        let pushed = push_out_capsule(glam::vec3(5.0, 0.0, 0.0), start, end, 1.0, 3.0).unwrap();
+        assert_vector_relative_eq!([7.0, 0.0, 0.0], pushed.to_array());
+
+        assert_eq!(
+            None,
+            push_out_capsule(glam::vec3(2.0, 2.5, 0.0), start, end, 1.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn push_out_oval_side_and_tip() {
+        let start = glam::vec3(0.0, 0.0, -2.0);
+        let end = glam::vec3(0.0, 0.0, 2.0);
+
+        let pushed = push_out_oval(glam::vec3(0.5, 0.0, 0.0), start, end, 1.0, 0.0).unwrap();
+        assert_vector_relative_eq!([1.0, 0.0, 0.0], pushed.to_array());
+
+        let pushed = push_out_oval(glam::vec3(0.0, 0.0, 1.5), start, end, 1.0, 0.0).unwrap();
+        assert_vector_relative_eq!([0.0, 0.0, 2.0], pushed.to_array());
+
+        assert_eq!(
+            None,
+            push_out_oval(glam::vec3(0.9, 0.0, 1.9), start, end, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn push_out_plane_below() {
+        assert_eq!(
+            Some(glam::vec3(1.0, 0.5, 0.0)),
+            push_out_plane(
+                glam::vec3(1.0, -1.0, 0.0),
+                glam::Vec3::ZERO,
+                glam::Vec3::Y,
+                0.5
+            )
+        );
+        assert_eq!(
+            None,
+            push_out_plane(
+                glam::vec3(1.0, 1.0, 0.0),
+                glam::Vec3::ZERO,
+                glam::Vec3::Y,
+                0.5
+            )
+        );
+    }
+}
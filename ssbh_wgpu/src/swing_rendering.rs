@@ -6,8 +6,8 @@ use ssbh_data::skel_data::SkelData;
 
 use crate::{
     shape::{
-        capsule_mesh_buffers, capsule_vertices, plane_mesh_buffers, sphere_mesh_buffers,
-        IndexedMeshBuffers,
+        capsule_mesh_buffers, capsule_vertices, oval_mesh_buffers, oval_vertices,
+        plane_mesh_buffers, sphere_mesh_buffers, IndexedMeshBuffers,
     },
     swing::*,
     DeviceBufferExt, QueueExt,
@@ -73,7 +73,11 @@ impl CollisionData {
 
 // TODO: Figure out which objects don't need to be recreated every frame.
 impl SwingRenderData {
-    pub fn new(device: &wgpu::Device, bone_world_transforms_buffer: &wgpu::Buffer) -> Self {
+    pub fn new(
+        device: &wgpu::Device,
+        bone_world_transforms_buffer: &wgpu::Buffer,
+        per_model_buffer: &wgpu::Buffer,
+    ) -> Self {
         let sphere_buffers = sphere_mesh_buffers(device);
         let plane_buffers = plane_mesh_buffers(device);
 
@@ -81,6 +85,7 @@ impl SwingRenderData {
             device,
             crate::shader::swing::bind_groups::BindGroupLayout1 {
                 world_transforms: bone_world_transforms_buffer.as_entire_buffer_binding(),
+                per_model: per_model_buffer.as_entire_buffer_binding(),
             },
         );
 
@@ -138,24 +143,8 @@ impl SwingRenderData {
         {
             // TODO: Find a way to avoid specifying this logic in multiple places.
             // TODO: How to reduce repeated logic for buffer creation and writing?
-            // TODO: Implement proper oval rendering.
-            // Use capsules for now since they both use a start/end bone.
-            let capsule = Capsule {
-                name: o.name,
-                start_bonename: o.start_bonename,
-                end_bonename: o.end_bonename,
-                start_offset_x: o.start_offset_x,
-                start_offset_y: o.start_offset_y,
-                start_offset_z: o.start_offset_z,
-                end_offset_x: o.end_offset_x,
-                end_offset_y: o.end_offset_y,
-                end_offset_z: o.end_offset_z,
-                start_radius: o.radius,
-                end_radius: o.radius,
-            };
-            let (height, per_shape) =
-                capsules_per_shape(skel, &capsule, world_transforms, OVAL_COLOR);
-            let data = capsule_vertices(8, 8, height, o.radius, o.radius);
+            let (length, per_shape) = ovals_per_shape(skel, o, world_transforms);
+            let data = oval_vertices(8, 8, length, o.radius);
             queue.write_data(&buffers.vertex_buffer, &data);
 
             // TODO: Find a way to avoid needing the swing_prc again.
@@ -256,24 +245,8 @@ fn ovals(
     ovals
         .iter()
         .map(|o| {
-            // TODO: Implement proper oval rendering.
-            // Use capsules for now since they both use a start/end bone.
-            let capsule = Capsule {
-                name: o.name,
-                start_bonename: o.start_bonename,
-                end_bonename: o.end_bonename,
-                start_offset_x: o.start_offset_x,
-                start_offset_y: o.start_offset_y,
-                start_offset_z: o.start_offset_z,
-                end_offset_x: o.end_offset_x,
-                end_offset_y: o.end_offset_y,
-                end_offset_z: o.end_offset_z,
-                start_radius: o.radius,
-                end_radius: o.radius,
-            };
-            let (height, per_shape) =
-                capsules_per_shape(skel, &capsule, world_transforms, OVAL_COLOR);
-            let mesh_buffers = capsule_mesh_buffers(device, height, o.radius, o.radius);
+            let (length, per_shape) = ovals_per_shape(skel, o, world_transforms);
+            let mesh_buffers = oval_mesh_buffers(device, length, o.radius);
 
             (
                 mesh_buffers,
@@ -337,21 +310,62 @@ fn capsule_transform(
     let end_bone_pos = world_transforms.get(end_i)?.col(3).xyz();
     let _end_offset = glam::vec3(c.end_offset_x, c.end_offset_y, c.end_offset_z);
 
-    // Assume the shape is along the Z-axis and has unit dimensions.
-    let direction = end_bone_pos - start_bone_pos;
-
     // TODO: How to include the offsets?
-    // Rotate the shape to point to both bones.
+    Some(segment_transform(start_bone_pos, end_bone_pos))
+}
+
+fn ovals_per_shape(
+    skel: Option<&SkelData>,
+    o: &Oval,
+    world_transforms: &[glam::Mat4],
+) -> (f32, crate::shader::swing::PerShape) {
+    let (length, transform) =
+        oval_transform(o, skel, world_transforms).unwrap_or((1.0, glam::Mat4::IDENTITY));
+
+    let per_shape = crate::shader::swing::PerShape {
+        bone_indices: glam::IVec4::splat(-1),
+        start_transform: transform,
+        color: OVAL_COLOR,
+    };
+
+    (length, per_shape)
+}
+
+fn oval_transform(
+    o: &Oval,
+    skel: Option<&SkelData>,
+    world_transforms: &[glam::Mat4],
+) -> Option<(f32, glam::Mat4)> {
+    // The oval spans the offset positions relative to each bone.
+    let start_i = bone_position(skel, o.start_bonename)?;
+    let end_i = bone_position(skel, o.end_bonename)?;
+
+    let start_offset = glam::vec3(o.start_offset_x, o.start_offset_y, o.start_offset_z);
+    let start = world_transforms
+        .get(start_i)?
+        .transform_point3(start_offset);
+
+    let end_offset = glam::vec3(o.end_offset_x, o.end_offset_y, o.end_offset_z);
+    let end = world_transforms.get(end_i)?.transform_point3(end_offset);
+
+    Some(segment_transform(start, end))
+}
+
+fn segment_transform(start: glam::Vec3, end: glam::Vec3) -> (f32, glam::Mat4) {
+    // Assume the shape is along the Z-axis and centered at the origin.
+    let direction = end - start;
+
+    // Rotate the shape to point from start to end.
     // Handle the case where start equals end and the direction vector is zero.
     let rotation = glam::Quat::from_rotation_arc(glam::Vec3::Z, direction.normalize_or_zero());
 
-    // Translate the bone in between the two bones.
-    let center = (end_bone_pos + start_bone_pos) / 2.0;
+    // Translate the shape in between the two points.
+    let center = (end + start) / 2.0;
 
-    Some((
+    (
         direction.length(),
         glam::Mat4::from_translation(center) * glam::Mat4::from_quat(rotation),
-    ))
+    )
 }
 
 fn planes(
@@ -547,3 +561,36 @@ pub fn swing_pipeline(device: &wgpu::Device) -> wgpu::RenderPipeline {
         multiview: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_matrix_relative_eq, assert_vector_relative_eq};
+
+    #[test]
+    fn segment_transform_along_x() {
+        let (length, transform) =
+            segment_transform(glam::vec3(1.0, 2.0, 3.0), glam::vec3(5.0, 2.0, 3.0));
+        assert_eq!(4.0, length);
+
+        // The tips of the shape on the Z-axis should line up with the end points.
+        assert_vector_relative_eq!(
+            [5.0, 2.0, 3.0],
+            transform.transform_point3(glam::vec3(0.0, 0.0, 2.0)).to_array()
+        );
+        assert_vector_relative_eq!(
+            [1.0, 2.0, 3.0],
+            transform.transform_point3(glam::vec3(0.0, 0.0, -2.0)).to_array()
+        );
+    }
+
+    #[test]
+    fn segment_transform_same_points() {
+        let (length, transform) = segment_transform(glam::Vec3::ONE, glam::Vec3::ONE);
+        assert_eq!(0.0, length);
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_translation(glam::Vec3::ONE).to_cols_array_2d(),
+            transform.to_cols_array_2d()
+        );
+    }
+}
@@ -7,6 +7,7 @@ use wgpu::{
     TextureViewDimension,
 };
 
+#[derive(Debug, PartialEq, Eq)]
 pub enum LoadTextureError {
     PathNotFound,
     DimensionMismatch {
@@ -31,12 +32,7 @@ pub fn load_texture(
     let (_, t, d) = textures
         .iter()
         .chain(default_textures)
-        .find(|(p, _, _)| {
-            Path::new(&p)
-                .with_extension("")
-                .as_os_str()
-                .eq_ignore_ascii_case(material_path)
-        })
+        .find(|(p, _, _)| matches_material_path(p, material_path))
         .ok_or(LoadTextureError::PathNotFound)?;
 
     if *d == dimension {
@@ -52,6 +48,42 @@ pub fn load_texture(
     }
 }
 
+/// Checks if [load_texture] would succeed using only the texture names and dimensions.
+pub fn check_texture<'a>(
+    material_path: &str,
+    textures: impl IntoIterator<Item = &'a (String, TextureViewDimension)>,
+    dimension: TextureViewDimension,
+) -> Result<(), LoadTextureError> {
+    let (_, d) = textures
+        .into_iter()
+        .find(|(p, _)| matches_material_path(p, material_path))
+        .ok_or(LoadTextureError::PathNotFound)?;
+
+    if *d == dimension {
+        Ok(())
+    } else {
+        Err(LoadTextureError::DimensionMismatch {
+            expected: dimension,
+            actual: *d,
+        })
+    }
+}
+
+pub fn matches_material_path(file_name: &str, material_path: &str) -> bool {
+    Path::new(file_name)
+        .with_extension("")
+        .as_os_str()
+        .eq_ignore_ascii_case(material_path)
+}
+
+/// The texture dimension the shaders expect for the texture `param_id`.
+pub fn expected_dimension(param_id: ParamId) -> TextureViewDimension {
+    match param_id {
+        ParamId::Texture2 | ParamId::Texture7 | ParamId::Texture8 => TextureViewDimension::Cube,
+        _ => TextureViewDimension::D2,
+    }
+}
+
 pub fn create_sampler(device: &Device, param_id: ParamId, data: &SamplerData) -> Sampler {
     device.create_sampler(&SamplerDescriptor {
         label: Some(&param_id.to_string()),
@@ -72,8 +104,8 @@ pub fn load_default(
     stage_cube: &Texture,
     default_white: &Texture,
 ) -> TextureView {
-    match param_id {
-        ParamId::Texture2 | ParamId::Texture7 | ParamId::Texture8 => {
+    match expected_dimension(param_id) {
+        TextureViewDimension::Cube => {
             // TODO: Diffuse cube maps seem to load a different stage cube map?
             // TODO: Investigate irradiance cube maps.
             stage_cube.create_view(&TextureViewDescriptor {
@@ -428,5 +460,44 @@ fn create_default_lut() -> Vec<u8> {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     // TODO: Add tests cases for handling of paths and special paths like "#replace_cubemap".
+    fn textures() -> Vec<(String, TextureViewDimension)> {
+        vec![
+            ("def_001_col.nutexb".to_string(), TextureViewDimension::D2),
+            ("#replace_cubemap".to_string(), TextureViewDimension::Cube),
+        ]
+    }
+
+    #[test]
+    fn check_texture_ignore_extension_case() {
+        assert_eq!(
+            Ok(()),
+            check_texture("DEF_001_col", &textures(), TextureViewDimension::D2)
+        );
+        assert_eq!(
+            Ok(()),
+            check_texture("#replace_cubemap", &textures(), TextureViewDimension::Cube)
+        );
+    }
+
+    #[test]
+    fn check_texture_missing() {
+        assert_eq!(
+            Err(LoadTextureError::PathNotFound),
+            check_texture("def_002_col", &textures(), TextureViewDimension::D2)
+        );
+    }
+
+    #[test]
+    fn check_texture_dimension_mismatch() {
+        assert_eq!(
+            Err(LoadTextureError::DimensionMismatch {
+                expected: TextureViewDimension::Cube,
+                actual: TextureViewDimension::D2
+            }),
+            check_texture("def_001_col", &textures(), TextureViewDimension::Cube)
+        );
+    }
 }
@@ -1,10 +1,11 @@
 use std::str::FromStr;
 
 use crate::{
+    animation::AnimatedMaterial,
     model::SamplerCache,
     shader::model::PerMaterial,
     split_param,
-    texture::{create_sampler, load_default, load_texture, LoadTextureError},
+    texture::{create_sampler, expected_dimension, load_default, load_texture, LoadTextureError},
     DeviceBufferExt, ShaderDatabase,
 };
 use log::warn;
@@ -33,7 +34,7 @@ pub fn material_uniforms_bind_group(
         .unwrap()
         .1;
 
-    let load_texture = |texture_id, dim| {
+    let load_texture = |texture_id| {
         // TODO: Add proper path and parameter handling.
         // TODO: Find a way to test texture path loading.
         // This should also handle paths like "../texture.nutexb" and "/render/shader/bin/texture.nutexb".
@@ -42,7 +43,7 @@ pub fn material_uniforms_bind_group(
                     .find(|t| t.param_id == texture_id)
                     .map(|t| t.data.as_str())
             .and_then(|material_path| {
-                load_texture(material_path, textures, default_textures, dim).map_err(|e| {
+                load_texture(material_path, textures, default_textures, expected_dimension(texture_id)).map_err(|e| {
                     match e {
                         LoadTextureError::PathNotFound => {
                             warn!("Missing texture {:?} assigned to {}. Applying default texture.", material_path, texture_id)
@@ -75,35 +76,35 @@ pub fn material_uniforms_bind_group(
     crate::shader::model::bind_groups::BindGroup2::from_bindings(
         device,
         crate::shader::model::bind_groups::BindGroupLayout2 {
-            texture0: &load_texture(ParamId::Texture0, wgpu::TextureViewDimension::D2),
+            texture0: &load_texture(ParamId::Texture0),
             sampler0: load_sampler(ParamId::Sampler0),
-            texture1: &load_texture(ParamId::Texture1, wgpu::TextureViewDimension::D2),
+            texture1: &load_texture(ParamId::Texture1),
             sampler1: load_sampler(ParamId::Sampler1),
-            texture2: &load_texture(ParamId::Texture2, wgpu::TextureViewDimension::Cube),
+            texture2: &load_texture(ParamId::Texture2),
             sampler2: load_sampler(ParamId::Sampler2),
-            texture3: &load_texture(ParamId::Texture3, wgpu::TextureViewDimension::D2),
+            texture3: &load_texture(ParamId::Texture3),
             sampler3: load_sampler(ParamId::Sampler3),
-            texture4: &load_texture(ParamId::Texture4, wgpu::TextureViewDimension::D2),
+            texture4: &load_texture(ParamId::Texture4),
             sampler4: load_sampler(ParamId::Sampler4),
-            texture5: &load_texture(ParamId::Texture5, wgpu::TextureViewDimension::D2),
+            texture5: &load_texture(ParamId::Texture5),
             sampler5: load_sampler(ParamId::Sampler5),
-            texture6: &load_texture(ParamId::Texture6, wgpu::TextureViewDimension::D2),
+            texture6: &load_texture(ParamId::Texture6),
             sampler6: load_sampler(ParamId::Sampler6),
-            texture7: &load_texture(ParamId::Texture7, wgpu::TextureViewDimension::Cube),
+            texture7: &load_texture(ParamId::Texture7),
             sampler7: load_sampler(ParamId::Sampler7),
-            texture8: &load_texture(ParamId::Texture8, wgpu::TextureViewDimension::Cube),
+            texture8: &load_texture(ParamId::Texture8),
             sampler8: load_sampler(ParamId::Sampler8),
-            texture9: &load_texture(ParamId::Texture9, wgpu::TextureViewDimension::D2),
+            texture9: &load_texture(ParamId::Texture9),
             sampler9: load_sampler(ParamId::Sampler9),
-            texture10: &load_texture(ParamId::Texture10, wgpu::TextureViewDimension::D2),
+            texture10: &load_texture(ParamId::Texture10),
             sampler10: load_sampler(ParamId::Sampler10),
-            texture11: &load_texture(ParamId::Texture11, wgpu::TextureViewDimension::D2),
+            texture11: &load_texture(ParamId::Texture11),
             sampler11: load_sampler(ParamId::Sampler11),
-            texture12: &load_texture(ParamId::Texture12, wgpu::TextureViewDimension::D2),
+            texture12: &load_texture(ParamId::Texture12),
             sampler12: load_sampler(ParamId::Sampler12),
-            texture13: &load_texture(ParamId::Texture13, wgpu::TextureViewDimension::D2),
+            texture13: &load_texture(ParamId::Texture13),
             sampler13: load_sampler(ParamId::Sampler13),
-            texture14: &load_texture(ParamId::Texture14, wgpu::TextureViewDimension::D2),
+            texture14: &load_texture(ParamId::Texture14),
             sampler14: load_sampler(ParamId::Sampler14),
             per_material: uniforms_buffer.as_entire_buffer_binding(),
         },
@@ -310,9 +311,35 @@ pub fn per_material(material: &MatlEntryData, database: &ShaderDatabase) -> PerM
         shader_settings,
         lighting_settings,
         shader_complexity,
+        anim_uv_transforms: [glam::Vec4::ZERO; 4],
+        anim_uv_rotations: glam::Vec4::ZERO,
+        has_anim_uv_transforms: glam::UVec4::ZERO,
     }
 }
 
+/// Calculates the uniforms for `material` with the values from a material animation applied.
+pub fn animated_per_material(
+    material: &AnimatedMaterial,
+    database: &ShaderDatabase,
+) -> PerMaterial {
+    let mut uniforms = per_material(&material.material, database);
+
+    for (param, uv_transform) in &material.uv_transforms {
+        if let Some(index) = uv_transform_index(*param) {
+            uniforms.anim_uv_transforms[index] = glam::vec4(
+                uv_transform.scale_u,
+                uv_transform.scale_v,
+                uv_transform.translate_u,
+                uv_transform.translate_v,
+            );
+            uniforms.anim_uv_rotations[index] = uv_transform.rotation;
+            uniforms.has_anim_uv_transforms[index] = 1;
+        }
+    }
+
+    uniforms
+}
+
 pub const DEFAULT_PER_MATERIAL: PerMaterial =
     // Missing values are always set to zero.
     PerMaterial {
@@ -328,6 +355,9 @@ pub const DEFAULT_PER_MATERIAL: PerMaterial =
         shader_settings: glam::UVec4::ZERO,
         lighting_settings: glam::UVec4::ZERO,
         shader_complexity: glam::Vec4::ZERO,
+        anim_uv_transforms: [glam::Vec4::ZERO; 4],
+        anim_uv_rotations: glam::Vec4::ZERO,
+        has_anim_uv_transforms: glam::UVec4::ZERO,
     };
 
 // TODO: Make this an extension trait?
@@ -427,6 +457,28 @@ pub fn float_index(param: ParamId) -> Option<usize> {
     }
 }
 
+/// The index of the UV transform for map1, uvSet, uvSet1, or the dual normal map.
+pub fn uv_transform_index(param: ParamId) -> Option<usize> {
+    // Animations can use the material's UV transform parameter or the texture parameter.
+    // TODO: Find a more accurate way to determine the UV attribute for each texture.
+    match param {
+        ParamId::CustomVector6
+        | ParamId::Texture0
+        | ParamId::Texture4
+        | ParamId::Texture5
+        | ParamId::Texture6
+        | ParamId::Texture10
+        | ParamId::Texture13
+        | ParamId::Texture16 => Some(0),
+        ParamId::CustomVector31 | ParamId::Texture1 | ParamId::Texture11 | ParamId::Texture14 => {
+            Some(1)
+        }
+        ParamId::CustomVector32 | ParamId::Texture12 => Some(2),
+        ParamId::CustomVector34 => Some(3),
+        _ => None,
+    }
+}
+
 pub fn texture_index(param: ParamId) -> Option<usize> {
     match param {
         ParamId::Texture0 => Some(0),
@@ -484,7 +536,7 @@ mod tests {
     use crate::ShaderProgram;
 
     use super::*;
-    use ssbh_data::Vector4;
+    use ssbh_data::{anim_data::UvTransform, Vector4};
 
     #[test]
     fn create_default_uniforms() {
@@ -501,7 +553,10 @@ mod tests {
                 has_color_set567: glam::UVec4::ZERO,
                 shader_settings: glam::UVec4::ZERO,
                 lighting_settings: glam::UVec4::ZERO,
-                shader_complexity: glam::Vec4::ZERO
+                shader_complexity: glam::Vec4::ZERO,
+                anim_uv_transforms: [glam::Vec4::ZERO; 4],
+                anim_uv_rotations: glam::Vec4::ZERO,
+                has_anim_uv_transforms: glam::UVec4::ZERO
             },
             DEFAULT_PER_MATERIAL
         );
@@ -522,7 +577,10 @@ mod tests {
                 has_color_set567: glam::UVec4::ZERO,
                 shader_settings: glam::UVec4::ZERO,
                 lighting_settings: glam::UVec4::ZERO,
-                shader_complexity: glam::Vec4::ZERO
+                shader_complexity: glam::Vec4::ZERO,
+                anim_uv_transforms: [glam::Vec4::ZERO; 4],
+                anim_uv_rotations: glam::Vec4::ZERO,
+                has_anim_uv_transforms: glam::UVec4::ZERO
             },
             per_material(
                 &MatlEntryData {
@@ -557,7 +615,10 @@ mod tests {
                 has_color_set567: glam::UVec4::ZERO,
                 shader_settings: glam::UVec4::ZERO,
                 lighting_settings: glam::UVec4::ZERO,
-                shader_complexity: glam::Vec4::ZERO
+                shader_complexity: glam::Vec4::ZERO,
+                anim_uv_transforms: [glam::Vec4::ZERO; 4],
+                anim_uv_rotations: glam::Vec4::ZERO,
+                has_anim_uv_transforms: glam::UVec4::ZERO
             },
             per_material(
                 &MatlEntryData {
@@ -612,6 +673,9 @@ mod tests {
             shader_settings: glam::UVec4::new(1, 0, 0, 0),
             lighting_settings: glam::UVec4::ZERO,
             shader_complexity: glam::Vec4::ZERO,
+            anim_uv_transforms: [glam::Vec4::ZERO; 4],
+            anim_uv_rotations: glam::Vec4::ZERO,
+            has_anim_uv_transforms: glam::UVec4::ZERO,
         };
         expected.custom_vector[0] = glam::vec4(1.0, 2.0, 3.0, 4.0);
         expected.custom_vector[8] = glam::Vec4::splat(1.0);
@@ -699,4 +763,52 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn create_uniforms_animated_uv_transforms() {
+        let material = MatlEntryData {
+            material_label: String::new(),
+            shader_label: String::new(),
+            blend_states: Vec::new(),
+            floats: Vec::new(),
+            booleans: Vec::new(),
+            vectors: Vec::new(),
+            rasterizer_states: Vec::new(),
+            samplers: Vec::new(),
+            textures: Vec::new(),
+        };
+        let uv_transform = UvTransform {
+            scale_u: 1.0,
+            scale_v: 2.0,
+            rotation: 0.5,
+            translate_u: 3.0,
+            translate_v: 4.0,
+        };
+
+        let uniforms = animated_per_material(
+            &AnimatedMaterial {
+                material,
+                uv_transforms: vec![
+                    (ParamId::Texture1, uv_transform.clone()),
+                    (ParamId::BlendState0, uv_transform),
+                ],
+            },
+            &ShaderDatabase::from_iter(std::iter::empty()),
+        );
+
+        assert_eq!(
+            [
+                glam::Vec4::ZERO,
+                glam::vec4(1.0, 2.0, 3.0, 4.0),
+                glam::Vec4::ZERO,
+                glam::Vec4::ZERO
+            ],
+            uniforms.anim_uv_transforms
+        );
+        assert_eq!(glam::vec4(0.0, 0.5, 0.0, 0.0), uniforms.anim_uv_rotations);
+        assert_eq!(
+            glam::UVec4::new(0, 1, 0, 0),
+            uniforms.has_anim_uv_transforms
+        );
+    }
 }
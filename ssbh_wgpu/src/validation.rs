@@ -0,0 +1,780 @@
+use std::fmt::Display;
+
+use ssbh_data::{
+    matl_data::{MatlEntryData, ParamId},
+    mesh_data::MeshObjectData,
+    prelude::*,
+};
+use wgpu::TextureViewDimension;
+
+use crate::{
+    animation::{constraint_cycles, SkelValidationError, ValidatedSkel},
+    texture::{check_texture, expected_dimension, LoadTextureError},
+    ShaderDatabase,
+};
+
+// Allow for some precision loss in the stored weights.
+const WEIGHT_SUM_TOLERANCE: f32 = 0.01;
+
+/// The problems found while creating a [RenderModel](crate::RenderModel).
+///
+/// Models with errors still render but may look different than in game.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct ModelValidationReport {
+    pub errors: Vec<ModelValidationError>,
+}
+
+impl ModelValidationReport {
+    /// Returns `true` if there are no errors.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// A problem with the files in a [ModelFolder](crate::ModelFolder).
+#[derive(Debug, PartialEq, Clone)]
+pub enum ModelValidationError {
+    /// The nutexb could not be read or converted to a texture.
+    InvalidTexture { file_name: String, message: String },
+    /// The vertex data could not be converted to vertex buffers.
+    /// The mesh object will not be rendered.
+    InvalidVertexData {
+        mesh_name: String,
+        subindex: u64,
+        message: String,
+    },
+    /// The texture path is not in the model folder or the default textures.
+    /// A default texture is used instead.
+    MissingTexture {
+        material_label: String,
+        param_id: ParamId,
+        path: String,
+    },
+    /// The texture has the wrong dimensions like a 2D texture assigned to a cube map.
+    /// A default texture is used instead.
+    TextureDimensionMismatch {
+        material_label: String,
+        param_id: ParamId,
+        path: String,
+        expected: TextureViewDimension,
+        actual: TextureViewDimension,
+    },
+    /// The shader label does not match any shader in the [ShaderDatabase].
+    /// Meshes with this material use the invalid shader pipeline.
+    UnrecognizedShaderLabel {
+        material_label: String,
+        shader_label: String,
+    },
+    /// The mesh is missing vertex attributes required by its shader.
+    /// The mesh uses the invalid attributes pipeline.
+    MissingRequiredAttributes {
+        mesh_name: String,
+        subindex: u64,
+        material_label: String,
+        attributes: Vec<String>,
+    },
+    /// The vertices have more than 4 bone influences.
+    /// Additional influences are ignored.
+    TooManyInfluences {
+        mesh_name: String,
+        subindex: u64,
+        vertex_indices: Vec<u32>,
+    },
+    /// The bone influence has weights for vertices not in the mesh.
+    /// These weights are ignored.
+    VertexWeightOutOfRange {
+        mesh_name: String,
+        subindex: u64,
+        bone_name: String,
+        vertex_indices: Vec<u32>,
+        vertex_count: usize,
+    },
+    /// The vertices have weights outside the range 0.0 to 1.0
+    /// or weights that do not sum to 1.0.
+    /// The skinned vertices may be scaled or offset from their expected positions.
+    InvalidVertexWeights {
+        mesh_name: String,
+        subindex: u64,
+        vertex_indices: Vec<u32>,
+    },
+    /// The skel has invalid bones or parents.
+    /// See [ValidatedSkel] for how the skel is corrected for animation.
+    InvalidSkel(SkelValidationError),
+    /// The constraint depends on its own target bone through parenting or other constraints.
+    /// The constraint uses the unconstrained world transform of its source bone.
+    ConstraintCycle { constraint_name: String },
+}
+
+impl Display for ModelValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelValidationError::InvalidTexture { file_name, message } => {
+                write!(f, "Error loading nutexb texture {file_name}: {message}")
+            }
+            ModelValidationError::InvalidVertexData {
+                mesh_name,
+                subindex,
+                message,
+            } => write!(
+                f,
+                "Error accessing vertex data for mesh {mesh_name} subindex {subindex}: {message}"
+            ),
+            ModelValidationError::MissingTexture {
+                material_label,
+                param_id,
+                path,
+            } => write!(
+                f,
+                "Missing texture {path:?} assigned to {param_id} for material {material_label}."
+            ),
+            ModelValidationError::TextureDimensionMismatch {
+                material_label,
+                param_id,
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Texture {path:?} assigned to {param_id} for material {material_label} has invalid dimensions. Expected {expected:?} but found {actual:?}."
+            ),
+            ModelValidationError::UnrecognizedShaderLabel {
+                material_label,
+                shader_label,
+            } => write!(
+                f,
+                "Shader label {shader_label:?} for material {material_label} is not a recognized shader."
+            ),
+            ModelValidationError::MissingRequiredAttributes {
+                mesh_name,
+                subindex,
+                material_label,
+                attributes,
+            } => write!(
+                f,
+                "Mesh {mesh_name} subindex {subindex} is missing attributes {attributes:?} required by material {material_label}."
+            ),
+            ModelValidationError::TooManyInfluences {
+                mesh_name,
+                subindex,
+                vertex_indices,
+            } => write!(
+                f,
+                "{} vertices for mesh {mesh_name} subindex {subindex} have more than 4 weights.",
+                vertex_indices.len()
+            ),
+            ModelValidationError::VertexWeightOutOfRange {
+                mesh_name,
+                subindex,
+                bone_name,
+                vertex_indices,
+                vertex_count,
+            } => write!(
+                f,
+                "{} vertex weights for bone {bone_name} are out of range for mesh {mesh_name} subindex {subindex} with {vertex_count} vertices.",
+                vertex_indices.len()
+            ),
+            ModelValidationError::InvalidVertexWeights {
+                mesh_name,
+                subindex,
+                vertex_indices,
+            } => write!(
+                f,
+                "{} vertices for mesh {mesh_name} subindex {subindex} have weights outside 0.0 to 1.0 or weights that do not sum to 1.0.",
+                vertex_indices.len()
+            ),
+            ModelValidationError::InvalidSkel(error) => write!(f, "Invalid skel: {error}"),
+            ModelValidationError::ConstraintCycle { constraint_name } => write!(
+                f,
+                "Constraint {constraint_name} depends on its own target bone."
+            ),
+        }
+    }
+}
+
+/// Finds errors for the materials and mesh objects using the texture file names and dimensions in `textures`.
+pub fn validate_model(
+    mesh: Option<&MeshData>,
+    skel: Option<&ValidatedSkel>,
+    hlpb: Option<&HlpbData>,
+    matl: Option<&MatlData>,
+    modl: Option<&ModlData>,
+    textures: &[(String, TextureViewDimension)],
+    database: &ShaderDatabase,
+) -> Vec<ModelValidationError> {
+    let mut errors = Vec::new();
+
+    if let Some(skel) = skel {
+        errors.extend(
+            skel.errors()
+                .iter()
+                .cloned()
+                .map(ModelValidationError::InvalidSkel),
+        );
+
+        if let Some(hlpb) = hlpb {
+            errors.extend(
+                constraint_cycles(skel, hlpb)
+                    .into_iter()
+                    .map(|constraint_name| ModelValidationError::ConstraintCycle {
+                        constraint_name,
+                    }),
+            );
+        }
+    }
+
+    if let Some(matl) = matl {
+        for entry in &matl.entries {
+            validate_material(entry, textures, database, &mut errors);
+        }
+    }
+
+    if let Some(mesh) = mesh {
+        for mesh_object in &mesh.objects {
+            validate_attributes(mesh_object, matl, modl, database, &mut errors);
+            validate_weights(mesh_object, skel.map(|s| s.skel()), &mut errors);
+        }
+    }
+
+    errors
+}
+
+fn validate_material(
+    entry: &MatlEntryData,
+    textures: &[(String, TextureViewDimension)],
+    database: &ShaderDatabase,
+    errors: &mut Vec<ModelValidationError>,
+) {
+    if database.get(&entry.shader_label).is_none() {
+        errors.push(ModelValidationError::UnrecognizedShaderLabel {
+            material_label: entry.material_label.clone(),
+            shader_label: entry.shader_label.clone(),
+        });
+    }
+
+    for texture in &entry.textures {
+        match check_texture(
+            &texture.data,
+            textures,
+            expected_dimension(texture.param_id),
+        ) {
+            Ok(()) => (),
+            Err(LoadTextureError::PathNotFound) => {
+                errors.push(ModelValidationError::MissingTexture {
+                    material_label: entry.material_label.clone(),
+                    param_id: texture.param_id,
+                    path: texture.data.clone(),
+                })
+            }
+            Err(LoadTextureError::DimensionMismatch { expected, actual }) => {
+                errors.push(ModelValidationError::TextureDimensionMismatch {
+                    material_label: entry.material_label.clone(),
+                    param_id: texture.param_id,
+                    path: texture.data.clone(),
+                    expected,
+                    actual,
+                })
+            }
+        }
+    }
+}
+
+fn validate_attributes(
+    mesh_object: &MeshObjectData,
+    matl: Option<&MatlData>,
+    modl: Option<&ModlData>,
+    database: &ShaderDatabase,
+    errors: &mut Vec<ModelValidationError>,
+) {
+    // Use the same material assignment as rendering.
+    let material = modl
+        .and_then(|modl| {
+            modl.entries.iter().find(|e| {
+                e.mesh_object_name == mesh_object.name
+                    && e.mesh_object_subindex == mesh_object.subindex
+            })
+        })
+        .and_then(|entry| {
+            matl?
+                .entries
+                .iter()
+                .find(|e| e.material_label == entry.material_label)
+        });
+
+    // Unrecognized shaders are already reported for the material.
+    if let Some((material, program)) =
+        material.and_then(|m| Some((m, database.get(&m.shader_label)?)))
+    {
+        let attribute_names = attribute_names(mesh_object);
+        let attributes = program.missing_required_attributes(&attribute_names);
+        if !attributes.is_empty() {
+            errors.push(ModelValidationError::MissingRequiredAttributes {
+                mesh_name: mesh_object.name.clone(),
+                subindex: mesh_object.subindex,
+                material_label: material.material_label.clone(),
+                attributes,
+            });
+        }
+    }
+}
+
+fn attribute_names(mesh_object: &MeshObjectData) -> Vec<String> {
+    mesh_object
+        .positions
+        .iter()
+        .chain(&mesh_object.normals)
+        .chain(&mesh_object.tangents)
+        .chain(&mesh_object.texture_coordinates)
+        .chain(&mesh_object.color_sets)
+        .map(|a| a.name.clone())
+        .collect()
+}
+
+fn validate_weights(
+    mesh_object: &MeshObjectData,
+    skel: Option<&SkelData>,
+    errors: &mut Vec<ModelValidationError>,
+) {
+    let vertex_count = match mesh_object.vertex_count() {
+        Ok(count) => count,
+        // Invalid vertex data is reported when creating the vertex buffers.
+        Err(_) => return,
+    };
+
+    // Match the influences used for skinning.
+    let skel = match skel {
+        Some(skel) => skel,
+        None => return,
+    };
+
+    let mut influence_counts = vec![0usize; vertex_count];
+    let mut weight_sums = vec![0.0f32; vertex_count];
+    let mut has_invalid_weight = vec![false; vertex_count];
+    for influence in mesh_object
+        .bone_influences
+        .iter()
+        .filter(|i| skel.bones.iter().any(|b| b.name == i.bone_name))
+    {
+        let mut out_of_range_indices = Vec::new();
+        for w in &influence.vertex_weights {
+            let i = w.vertex_index as usize;
+            match influence_counts.get_mut(i) {
+                Some(count) => {
+                    *count += 1;
+                    weight_sums[i] += w.vertex_weight;
+                    if !(0.0..=1.0).contains(&w.vertex_weight) {
+                        has_invalid_weight[i] = true;
+                    }
+                }
+                None => out_of_range_indices.push(w.vertex_index),
+            }
+        }
+
+        if !out_of_range_indices.is_empty() {
+            errors.push(ModelValidationError::VertexWeightOutOfRange {
+                mesh_name: mesh_object.name.clone(),
+                subindex: mesh_object.subindex,
+                bone_name: influence.bone_name.clone(),
+                vertex_indices: out_of_range_indices,
+                vertex_count,
+            });
+        }
+    }
+
+    let vertex_indices: Vec<_> = influence_counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 4)
+        .map(|(i, _)| i as u32)
+        .collect();
+    if !vertex_indices.is_empty() {
+        errors.push(ModelValidationError::TooManyInfluences {
+            mesh_name: mesh_object.name.clone(),
+            subindex: mesh_object.subindex,
+            vertex_indices,
+        });
+    }
+
+    // Vertices without influences aren't skinned.
+    // Vertices with too many influences are already reported.
+    let vertex_indices: Vec<_> = influence_counts
+        .iter()
+        .zip(weight_sums.iter().zip(&has_invalid_weight))
+        .enumerate()
+        .filter(|(_, (count, (sum, is_invalid)))| {
+            (1..=4).contains(*count) && (**is_invalid || (**sum - 1.0).abs() > WEIGHT_SUM_TOLERANCE)
+        })
+        .map(|(i, _)| i as u32)
+        .collect();
+    if !vertex_indices.is_empty() {
+        errors.push(ModelValidationError::InvalidVertexWeights {
+            mesh_name: mesh_object.name.clone(),
+            subindex: mesh_object.subindex,
+            vertex_indices,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ShaderProgram;
+    use ssbh_data::{
+        hlpb_data::OrientConstraintData,
+        matl_data::TextureParam,
+        mesh_data::{AttributeData, BoneInfluence, VectorData, VertexWeight},
+        modl_data::ModlEntryData,
+        skel_data::{BillboardType, BoneData},
+        Vector3, Vector4,
+    };
+
+    const SHADER_LABEL: &str = "SFX_PBS_0100000008008269_opaque";
+
+    fn database() -> ShaderDatabase {
+        ShaderDatabase::from_iter([(
+            "SFX_PBS_0100000008008269".to_string(),
+            ShaderProgram {
+                vertex_attributes: vec!["Position0".to_string(), "map1.xy".to_string()],
+                ..Default::default()
+            },
+        )])
+    }
+
+    fn material(label: &str, shader_label: &str, textures: Vec<TextureParam>) -> MatlEntryData {
+        MatlEntryData {
+            material_label: label.to_string(),
+            shader_label: shader_label.to_string(),
+            blend_states: Vec::new(),
+            floats: Vec::new(),
+            booleans: Vec::new(),
+            vectors: Vec::new(),
+            rasterizer_states: Vec::new(),
+            samplers: Vec::new(),
+            textures,
+        }
+    }
+
+    fn texture(param_id: ParamId, data: &str) -> TextureParam {
+        TextureParam {
+            param_id,
+            data: data.to_string(),
+        }
+    }
+
+    fn mesh_object(vertex_count: usize, bone_influences: Vec<BoneInfluence>) -> MeshObjectData {
+        MeshObjectData {
+            name: "a".to_string(),
+            positions: vec![AttributeData {
+                name: "Position0".to_string(),
+                data: VectorData::Vector3(vec![[0.0; 3]; vertex_count]),
+            }],
+            bone_influences,
+            ..Default::default()
+        }
+    }
+
+    fn influence(name: &str, vertex_indices: &[u32]) -> BoneInfluence {
+        let weights: Vec<_> = vertex_indices.iter().map(|i| (*i, 0.25)).collect();
+        weighted_influence(name, &weights)
+    }
+
+    fn weighted_influence(name: &str, weights: &[(u32, f32)]) -> BoneInfluence {
+        BoneInfluence {
+            bone_name: name.to_string(),
+            vertex_weights: weights
+                .iter()
+                .map(|(i, w)| VertexWeight {
+                    vertex_index: *i,
+                    vertex_weight: *w,
+                })
+                .collect(),
+        }
+    }
+
+    fn skel(names: &[&str]) -> ValidatedSkel<'static> {
+        ValidatedSkel::new(&SkelData {
+            major_version: 1,
+            minor_version: 0,
+            bones: names
+                .iter()
+                .map(|name| BoneData {
+                    name: name.to_string(),
+                    transform: [[0.0; 4]; 4],
+                    parent_index: None,
+                    billboard_type: BillboardType::Disabled,
+                })
+                .collect(),
+        })
+        .into_owned()
+    }
+
+    #[test]
+    fn validate_empty_model() {
+        assert!(validate_model(None, None, None, None, None, &[], &database()).is_empty());
+    }
+
+    #[test]
+    fn validate_material_textures() {
+        let matl = MatlData {
+            major_version: 1,
+            minor_version: 6,
+            entries: vec![material(
+                "mat",
+                SHADER_LABEL,
+                vec![
+                    texture(ParamId::Texture0, "def_col"),
+                    texture(ParamId::Texture1, "missing"),
+                    texture(ParamId::Texture7, "def_col"),
+                ],
+            )],
+        };
+        let textures = vec![("def_col.nutexb".to_string(), TextureViewDimension::D2)];
+
+        assert_eq!(
+            vec![
+                ModelValidationError::MissingTexture {
+                    material_label: "mat".to_string(),
+                    param_id: ParamId::Texture1,
+                    path: "missing".to_string()
+                },
+                ModelValidationError::TextureDimensionMismatch {
+                    material_label: "mat".to_string(),
+                    param_id: ParamId::Texture7,
+                    path: "def_col".to_string(),
+                    expected: TextureViewDimension::Cube,
+                    actual: TextureViewDimension::D2
+                }
+            ],
+            validate_model(None, None, None, Some(&matl), None, &textures, &database())
+        );
+    }
+
+    #[test]
+    fn validate_unrecognized_shader_label() {
+        let matl = MatlData {
+            major_version: 1,
+            minor_version: 6,
+            entries: vec![material("mat", "SFX_PBS_invalid", Vec::new())],
+        };
+
+        assert_eq!(
+            vec![ModelValidationError::UnrecognizedShaderLabel {
+                material_label: "mat".to_string(),
+                shader_label: "SFX_PBS_invalid".to_string()
+            }],
+            validate_model(None, None, None, Some(&matl), None, &[], &database())
+        );
+    }
+
+    #[test]
+    fn validate_missing_required_attributes() {
+        let mesh = MeshData {
+            major_version: 1,
+            minor_version: 10,
+            objects: vec![mesh_object(3, Vec::new())],
+        };
+        let matl = MatlData {
+            major_version: 1,
+            minor_version: 6,
+            entries: vec![material("mat", SHADER_LABEL, Vec::new())],
+        };
+        let modl = ModlData {
+            major_version: 1,
+            minor_version: 0,
+            model_name: String::new(),
+            skeleton_file_name: String::new(),
+            material_file_names: Vec::new(),
+            animation_file_name: None,
+            mesh_file_name: String::new(),
+            entries: vec![ModlEntryData {
+                mesh_object_name: "a".to_string(),
+                mesh_object_subindex: 0,
+                material_label: "mat".to_string(),
+            }],
+        };
+
+        assert_eq!(
+            vec![ModelValidationError::MissingRequiredAttributes {
+                mesh_name: "a".to_string(),
+                subindex: 0,
+                material_label: "mat".to_string(),
+                attributes: vec!["map1".to_string()]
+            }],
+            validate_model(
+                Some(&mesh),
+                None,
+                None,
+                Some(&matl),
+                Some(&modl),
+                &[],
+                &database()
+            )
+        );
+    }
+
+    #[test]
+    fn validate_too_many_influences() {
+        let mesh = MeshData {
+            major_version: 1,
+            minor_version: 10,
+            objects: vec![mesh_object(
+                3,
+                vec![
+                    influence("a", &[0, 2]),
+                    influence("b", &[0, 2]),
+                    influence("c", &[0, 2]),
+                    influence("d", &[0, 2]),
+                    influence("e", &[2]),
+                    // Influences for bones not in the skel are ignored.
+                    influence("f", &[0]),
+                ],
+            )],
+        };
+
+        assert_eq!(
+            vec![ModelValidationError::TooManyInfluences {
+                mesh_name: "a".to_string(),
+                subindex: 0,
+                vertex_indices: vec![2]
+            }],
+            validate_model(
+                Some(&mesh),
+                Some(&skel(&["a", "b", "c", "d", "e"])),
+                None,
+                None,
+                None,
+                &[],
+                &database()
+            )
+        );
+    }
+
+    #[test]
+    fn validate_weights_out_of_range() {
+        let mesh = MeshData {
+            major_version: 1,
+            minor_version: 10,
+            objects: vec![mesh_object(
+                3,
+                vec![weighted_influence("a", &[(0, 1.0), (3, 1.0), (4, 1.0)])],
+            )],
+        };
+
+        assert_eq!(
+            vec![ModelValidationError::VertexWeightOutOfRange {
+                mesh_name: "a".to_string(),
+                subindex: 0,
+                bone_name: "a".to_string(),
+                vertex_indices: vec![3, 4],
+                vertex_count: 3
+            }],
+            validate_model(
+                Some(&mesh),
+                Some(&skel(&["a"])),
+                None,
+                None,
+                None,
+                &[],
+                &database()
+            )
+        );
+    }
+
+    #[test]
+    fn validate_invalid_vertex_weights() {
+        let mesh = MeshData {
+            major_version: 1,
+            minor_version: 10,
+            objects: vec![mesh_object(
+                5,
+                vec![
+                    weighted_influence("a", &[(0, 0.5), (1, 0.5), (2, 1.5), (3, -0.5)]),
+                    weighted_influence("b", &[(0, 0.5), (1, 0.2), (3, 1.5)]),
+                    // Influences for bones not in the skel are ignored.
+                    weighted_influence("c", &[(1, 0.3)]),
+                ],
+            )],
+        };
+
+        // Vertex 4 has no influences and isn't skinned.
+        assert_eq!(
+            vec![ModelValidationError::InvalidVertexWeights {
+                mesh_name: "a".to_string(),
+                subindex: 0,
+                vertex_indices: vec![1, 2, 3]
+            }],
+            validate_model(
+                Some(&mesh),
+                Some(&skel(&["a", "b"])),
+                None,
+                None,
+                None,
+                &[],
+                &database()
+            )
+        );
+    }
+
+    #[test]
+    fn validate_invalid_skel() {
+        assert_eq!(
+            vec![ModelValidationError::InvalidSkel(
+                SkelValidationError::DuplicateBoneName {
+                    bone_name: "a".to_string(),
+                    bone_index: 1
+                }
+            )],
+            validate_model(
+                None,
+                Some(&skel(&["a", "a"])),
+                None,
+                None,
+                None,
+                &[],
+                &database()
+            )
+        );
+    }
+
+    fn orient_constraint(name: &str, source: &str, target: &str) -> OrientConstraintData {
+        OrientConstraintData {
+            name: name.to_string(),
+            parent_bone_name1: source.to_string(),
+            parent_bone_name2: source.to_string(),
+            source_bone_name: source.to_string(),
+            target_bone_name: target.to_string(),
+            unk_type: 2,
+            constraint_axes: Vector3::new(1.0, 1.0, 1.0),
+            quat1: Vector4::new(0.0, 0.0, 0.0, 1.0),
+            quat2: Vector4::new(0.0, 0.0, 0.0, 1.0),
+            range_min: Vector3::new(-180.0, -180.0, -180.0),
+            range_max: Vector3::new(180.0, 180.0, 180.0),
+        }
+    }
+
+    #[test]
+    fn validate_constraint_cycle() {
+        let hlpb = HlpbData {
+            major_version: 1,
+            minor_version: 0,
+            aim_constraints: Vec::new(),
+            orient_constraints: vec![
+                orient_constraint("constraint1", "a", "b"),
+                orient_constraint("constraint2", "b", "a"),
+            ],
+        };
+        assert_eq!(
+            vec![ModelValidationError::ConstraintCycle {
+                constraint_name: "constraint2".to_string()
+            }],
+            validate_model(
+                None,
+                Some(&skel(&["a", "b"])),
+                Some(&hlpb),
+                None,
+                None,
+                &[],
+                &database()
+            )
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use glam::Vec4Swizzles;
 
+use crate::{BoundingSphere, CameraTransforms, RenderModel};
+
 // TODO: Document what the input and output value ranges should be.
 // TODO: Add tests.
 pub fn world_to_screen(point: glam::Vec3, mvp: glam::Mat4, width: u32, height: u32) -> (f32, f32) {
@@ -27,3 +29,456 @@ pub fn screen_to_world(point: (f32, f32), mvp: glam::Mat4, width: u32, height: u
     let world_y = world.y * world.z;
     (world_x, world_y)
 }
+
+/// A half line in world space used for picking.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Ray {
+    pub origin: glam::Vec3,
+    /// The normalized direction of the ray.
+    pub direction: glam::Vec3,
+}
+
+impl Ray {
+    /// The point at `distance` along the ray.
+    pub fn at(&self, distance: f32) -> glam::Vec3 {
+        self.origin + self.direction * distance
+    }
+}
+
+/// Calculates the ray in world space under `point` in physical pixels
+/// with `(0, 0)` as the top left corner of the viewport.
+///
+/// The ray starts on the near plane and points away from the camera.
+/// The viewport dimensions are taken from the `screen_dimensions` of `camera`.
+pub fn screen_to_ray(point: (f32, f32), camera: &CameraTransforms) -> Ray {
+    let (x_pixels, y_pixels) = point;
+    let width = camera.screen_dimensions.x;
+    let height = camera.screen_dimensions.y;
+
+    // Map from screen space to clip space in the range [-1,1].
+    // Flip y vertically to match world_to_screen.
+    let x_clip = 2.0 * x_pixels / width - 1.0;
+    let y_clip = 1.0 - 2.0 * y_pixels / height;
+
+    // The wgpu depth range is [0,1].
+    let near = camera.mvp_inv_matrix * glam::vec4(x_clip, y_clip, 0.0, 1.0);
+    let far = camera.mvp_inv_matrix * glam::vec4(x_clip, y_clip, 1.0, 1.0);
+    let near = near.xyz() / near.w;
+    let far = far.xyz() / far.w;
+
+    Ray {
+        origin: near,
+        direction: (far - near).normalize(),
+    }
+}
+
+/// A windowing agnostic camera that rotates around a target point.
+///
+/// Input handling is left to the application.
+/// Convert window events like mouse drags and scrolling to calls to
+/// [OrbitCamera::orbit], [OrbitCamera::pan], and [OrbitCamera::dolly].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct OrbitCamera {
+    /// The point in world space the camera rotates around.
+    pub target: glam::Vec3,
+    /// The distance from the camera to [target](#structfield.target).
+    pub distance: f32,
+    /// The rotation about the X-axis in radians.
+    pub pitch: f32,
+    /// The rotation about the Y-axis in radians.
+    pub yaw: f32,
+    /// The vertical field of view in radians.
+    /// Orthographic cameras use this to match the perspective scale at the target.
+    pub fov_y: f32,
+    pub near_clip: f32,
+    pub far_clip: f32,
+    /// Use an orthographic projection instead of a perspective projection when `true`.
+    pub is_orthographic: bool,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        // Frame a typical fighter model.
+        Self {
+            target: glam::vec3(0.0, 8.0, 0.0),
+            distance: 60.0,
+            pitch: 0.0,
+            yaw: 0.0,
+            fov_y: 0.5,
+            near_clip: 1.0,
+            // Use a large far clip distance to include stage skyboxes.
+            far_clip: 400000.0,
+            is_orthographic: false,
+        }
+    }
+}
+
+impl OrbitCamera {
+    /// Rotates the camera around the target by the given angles in radians.
+    pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        self.yaw += delta_yaw;
+        self.pitch += delta_pitch;
+    }
+
+    /// Moves the target parallel to the screen so the point at the target
+    /// follows a cursor that moved by `delta_x` and `delta_y` in pixels.
+    /// The `height` is the height of the viewport in pixels.
+    pub fn pan(&mut self, delta_x: f32, delta_y: f32, height: u32) {
+        let world_per_pixel = 2.0 * self.half_height() / height as f32;
+
+        // Find the screen space axes in world space.
+        let rotation_inv = self.rotation().inverse();
+        let right = rotation_inv.transform_vector3(glam::Vec3::X);
+        let up = rotation_inv.transform_vector3(glam::Vec3::Y);
+
+        // Screen space Y points down.
+        self.target -= (right * delta_x - up * delta_y) * world_per_pixel;
+    }
+
+    /// Moves the camera towards the target by `amount` times the current distance.
+    /// Negative values move away from the target.
+    ///
+    /// Scaling by distance makes it easier to zoom out large scenes.
+    /// The distance is clamped to [near_clip](#structfield.near_clip) to avoid moving through the target.
+    pub fn dolly(&mut self, amount: f32) {
+        self.distance = (self.distance * (1.0 - amount)).max(self.near_clip);
+    }
+
+    /// Rotates the camera around `point` without changing the distance or angles.
+    pub fn focus(&mut self, point: glam::Vec3) {
+        self.target = point;
+    }
+
+    /// Focuses on the center of `sphere` and adjusts the distance to fit the sphere in view.
+    pub fn frame(&mut self, sphere: BoundingSphere, aspect_ratio: f32) {
+        self.target = sphere.center;
+        self.distance = framing_distance(sphere.radius, self.fov_y, aspect_ratio);
+    }
+
+    /// The model view matrix for transforming world space to view space.
+    pub fn model_view_matrix(&self) -> glam::Mat4 {
+        glam::Mat4::from_translation(glam::vec3(0.0, 0.0, -self.distance))
+            * self.rotation()
+            * glam::Mat4::from_translation(-self.target)
+    }
+
+    /// The perspective or orthographic projection matrix.
+    pub fn projection_matrix(&self, aspect_ratio: f32) -> glam::Mat4 {
+        if self.is_orthographic {
+            let half_height = self.half_height();
+            let half_width = half_height * aspect_ratio;
+            glam::Mat4::orthographic_rh(
+                -half_width,
+                half_width,
+                -half_height,
+                half_height,
+                self.near_clip,
+                self.far_clip,
+            )
+        } else {
+            glam::Mat4::perspective_rh(self.fov_y, aspect_ratio, self.near_clip, self.far_clip)
+        }
+    }
+
+    /// Calculates the transforms for [SsbhRenderer::update_camera](crate::SsbhRenderer::update_camera).
+    ///
+    /// The `scale_factor` should usually match the value used for [SsbhRenderer](crate::SsbhRenderer).
+    pub fn camera_transforms(
+        &self,
+        width: u32,
+        height: u32,
+        scale_factor: f32,
+    ) -> CameraTransforms {
+        let model_view_matrix = self.model_view_matrix();
+        let mvp_matrix = self.projection_matrix(width as f32 / height as f32) * model_view_matrix;
+
+        CameraTransforms {
+            model_view_matrix,
+            mvp_matrix,
+            mvp_inv_matrix: mvp_matrix.inverse(),
+            camera_pos: model_view_matrix.inverse().col(3),
+            screen_dimensions: glam::vec4(width as f32, height as f32, scale_factor, 0.0),
+        }
+    }
+
+    fn rotation(&self) -> glam::Mat4 {
+        glam::Mat4::from_rotation_x(self.pitch) * glam::Mat4::from_rotation_y(self.yaw)
+    }
+
+    fn half_height(&self) -> f32 {
+        // The half height of the view at the target distance.
+        self.distance * (self.fov_y / 2.0).tan()
+    }
+}
+
+/// Calculates the camera translation that fits `sphere` in view
+/// for the given `rotation_xyz` in radians, vertical `fov_y` in radians, and `aspect_ratio`.
+///
+/// The model view matrix is assumed to be `translation * rotation_x * rotation_y`.
+/// The Z rotation is ignored.
+pub fn frame_bounding_sphere(
+    sphere: BoundingSphere,
+    rotation_xyz: glam::Vec3,
+    fov_y: f32,
+    aspect_ratio: f32,
+) -> glam::Vec3 {
+    let rotation =
+        glam::Mat4::from_rotation_x(rotation_xyz.x) * glam::Mat4::from_rotation_y(rotation_xyz.y);
+    let center = rotation.transform_point3(sphere.center);
+    let distance = framing_distance(sphere.radius, fov_y, aspect_ratio);
+
+    // Move the sphere center in front of the camera.
+    glam::vec3(-center.x, -center.y, -center.z - distance)
+}
+
+/// Calculates camera transforms that fit the visible meshes of `render_models` in view
+/// or `None` if there are no visible meshes.
+///
+/// See [OrbitCamera::frame] and [RenderModel::bounding_sphere].
+pub fn frame_models(
+    render_models: &[RenderModel],
+    rotation_xyz: glam::Vec3,
+    fov_y: f32,
+    width: u32,
+    height: u32,
+    scale_factor: f32,
+) -> Option<CameraTransforms> {
+    let sphere =
+        BoundingSphere::from_spheres(render_models.iter().filter_map(|m| m.bounding_sphere()))?;
+
+    let mut camera = OrbitCamera {
+        pitch: rotation_xyz.x,
+        yaw: rotation_xyz.y,
+        fov_y,
+        ..Default::default()
+    };
+    camera.frame(sphere, width as f32 / height as f32);
+
+    // Avoid clipping small models while keeping enough depth precision for stages.
+    camera.near_clip = (camera.distance - sphere.radius).clamp(0.01, 1.0);
+
+    Some(camera.camera_transforms(width, height, scale_factor))
+}
+
+fn framing_distance(radius: f32, fov_y: f32, aspect_ratio: f32) -> f32 {
+    // Use the smaller field of view to fit the sphere horizontally and vertically.
+    let half_fov_x = ((fov_y / 2.0).tan() * aspect_ratio).atan();
+    let half_fov = (fov_y / 2.0).min(half_fov_x);
+    radius / half_fov.sin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_matrix_relative_eq, assert_vector_relative_eq};
+    use std::f32::consts::{FRAC_PI_2, SQRT_2};
+
+    #[test]
+    fn frame_unit_sphere_square() {
+        let translation = frame_bounding_sphere(
+            BoundingSphere {
+                center: glam::Vec3::ZERO,
+                radius: 1.0,
+            },
+            glam::Vec3::ZERO,
+            FRAC_PI_2,
+            1.0,
+        );
+        assert_vector_relative_eq!([0.0, 0.0, -SQRT_2], translation.to_array());
+    }
+
+    #[test]
+    fn frame_unit_sphere_tall() {
+        // The horizontal field of view is smaller than the vertical field of view.
+        let translation = frame_bounding_sphere(
+            BoundingSphere {
+                center: glam::Vec3::ZERO,
+                radius: 1.0,
+            },
+            glam::Vec3::ZERO,
+            FRAC_PI_2,
+            0.5,
+        );
+        assert_vector_relative_eq!([0.0, 0.0, -(5.0f32.sqrt())], translation.to_array());
+    }
+
+    #[test]
+    fn frame_rotated_sphere_centered() {
+        let sphere = BoundingSphere {
+            center: glam::vec3(1.0, 2.0, 3.0),
+            radius: 2.0,
+        };
+        let rotation_xyz = glam::vec3(0.5, 1.0, 0.0);
+        let translation = frame_bounding_sphere(sphere, rotation_xyz, 0.5, 16.0 / 9.0);
+
+        let mvp = glam::Mat4::perspective_rh(0.5, 16.0 / 9.0, 1.0, 100.0)
+            * glam::Mat4::from_translation(translation)
+            * glam::Mat4::from_rotation_x(0.5)
+            * glam::Mat4::from_rotation_y(1.0);
+        let (x, y) = world_to_screen(sphere.center, mvp, 1920, 1080);
+        assert_vector_relative_eq!([960.0, 540.0], [x, y]);
+    }
+
+    #[test]
+    fn orbit_camera_default_matches_viewer() {
+        // The previous hard coded viewer camera translation.
+        let camera = OrbitCamera::default();
+        assert_matrix_relative_eq!(
+            glam::Mat4::from_translation(glam::vec3(0.0, -8.0, -60.0)).to_cols_array_2d(),
+            camera.model_view_matrix().to_cols_array_2d()
+        );
+    }
+
+    #[test]
+    fn orbit_camera_position() {
+        let mut camera = OrbitCamera {
+            target: glam::vec3(1.0, 2.0, 3.0),
+            distance: 5.0,
+            ..Default::default()
+        };
+        camera.orbit(FRAC_PI_2, 0.0);
+
+        // Rotating the scene counterclockwise moves the camera clockwise around the target.
+        let transforms = camera.camera_transforms(512, 512, 1.0);
+        assert_vector_relative_eq!([-4.0, 2.0, 3.0, 1.0], transforms.camera_pos.to_array());
+    }
+
+    #[test]
+    fn orbit_camera_target_centered() {
+        let mut camera = OrbitCamera {
+            target: glam::vec3(1.0, 2.0, 3.0),
+            ..Default::default()
+        };
+        camera.orbit(0.5, -0.25);
+
+        for is_orthographic in [false, true] {
+            camera.is_orthographic = is_orthographic;
+            let transforms = camera.camera_transforms(1920, 1080, 1.0);
+            let (x, y) = world_to_screen(camera.target, transforms.mvp_matrix, 1920, 1080);
+            assert_vector_relative_eq!([960.0, 540.0], [x, y]);
+        }
+    }
+
+    #[test]
+    fn orbit_camera_pan_follows_cursor() {
+        let mut camera = OrbitCamera {
+            target: glam::vec3(1.0, 2.0, 3.0),
+            ..Default::default()
+        };
+        camera.orbit(0.5, -0.25);
+
+        for is_orthographic in [false, true] {
+            camera.is_orthographic = is_orthographic;
+
+            // The point at the previous target should move with the cursor.
+            let point = camera.target;
+            camera.pan(100.0, -50.0, 1080);
+
+            let transforms = camera.camera_transforms(1920, 1080, 1.0);
+            let (x, y) = world_to_screen(point, transforms.mvp_matrix, 1920, 1080);
+            assert_vector_relative_eq!([1060.0, 490.0], [x, y]);
+        }
+    }
+
+    #[test]
+    fn orbit_camera_dolly() {
+        let mut camera = OrbitCamera {
+            distance: 10.0,
+            near_clip: 1.0,
+            ..Default::default()
+        };
+        camera.dolly(0.5);
+        assert_eq!(5.0, camera.distance);
+
+        camera.dolly(-1.0);
+        assert_eq!(10.0, camera.distance);
+
+        // Don't move through the target.
+        camera.dolly(2.0);
+        assert_eq!(1.0, camera.distance);
+    }
+
+    #[test]
+    fn orbit_camera_focus() {
+        let mut camera = OrbitCamera::default();
+        camera.focus(glam::vec3(1.0, 2.0, 3.0));
+        assert_eq!(glam::vec3(1.0, 2.0, 3.0), camera.target);
+        assert_eq!(60.0, camera.distance);
+    }
+
+    #[test]
+    fn orbit_camera_frame_matches_translation() {
+        let sphere = BoundingSphere {
+            center: glam::vec3(1.0, 2.0, 3.0),
+            radius: 2.0,
+        };
+        let mut camera = OrbitCamera {
+            pitch: 0.5,
+            yaw: 1.0,
+            ..Default::default()
+        };
+        camera.frame(sphere, 16.0 / 9.0);
+
+        let translation = frame_bounding_sphere(sphere, glam::vec3(0.5, 1.0, 0.0), 0.5, 16.0 / 9.0);
+        assert_vector_relative_eq!(
+            translation.to_array(),
+            camera.model_view_matrix().col(3).xyz().to_array()
+        );
+    }
+
+    #[test]
+    fn orbit_camera_orthographic_scale() {
+        // Orthographic cameras should match the perspective size at the target.
+        let mut camera = OrbitCamera {
+            target: glam::Vec3::ZERO,
+            distance: 10.0,
+            fov_y: FRAC_PI_2,
+            ..Default::default()
+        };
+        let point = glam::vec3(0.0, 5.0, 0.0);
+
+        let perspective = camera.camera_transforms(512, 512, 1.0);
+        camera.is_orthographic = true;
+        let orthographic = camera.camera_transforms(512, 512, 1.0);
+
+        let (_, y1) = world_to_screen(point, perspective.mvp_matrix, 512, 512);
+        let (_, y2) = world_to_screen(point, orthographic.mvp_matrix, 512, 512);
+        assert_vector_relative_eq!([128.0], [y1]);
+        assert_vector_relative_eq!([128.0], [y2]);
+    }
+
+    #[test]
+    fn screen_to_ray_center() {
+        let camera = OrbitCamera {
+            target: glam::vec3(1.0, 2.0, 3.0),
+            distance: 10.0,
+            ..Default::default()
+        };
+        let ray = screen_to_ray((256.0, 256.0), &camera.camera_transforms(512, 512, 1.0));
+
+        // The ray should start on the near plane and point towards the target.
+        assert_vector_relative_eq!([1.0, 2.0, 12.0], ray.origin.to_array());
+        assert_vector_relative_eq!([0.0, 0.0, -1.0], ray.direction.to_array());
+    }
+
+    #[test]
+    fn screen_to_ray_matches_world_to_screen() {
+        for is_orthographic in [false, true] {
+            let camera = OrbitCamera {
+                pitch: 0.25,
+                yaw: 1.5,
+                is_orthographic,
+                ..Default::default()
+            };
+            let transforms = camera.camera_transforms(640, 480, 1.0);
+
+            let point = glam::vec3(3.0, 12.0, -2.0);
+            let screen = world_to_screen(point, transforms.mvp_matrix, 640, 480);
+            let ray = screen_to_ray(screen, &transforms);
+
+            // The point should be on the ray.
+            let distance = (point - ray.origin).dot(ray.direction);
+            assert_vector_relative_eq!(point.to_array(), ray.at(distance).to_array());
+        }
+    }
+}
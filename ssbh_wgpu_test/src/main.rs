@@ -1,39 +1,14 @@
-use std::{
-    num::NonZeroU32,
-    path::{Path, PathBuf},
-};
+use std::path::{Path, PathBuf};
 
 use futures::executor::block_on;
-use image::ImageBuffer;
 use ssbh_data::prelude::*;
 use ssbh_wgpu::{
-    load_render_models, CameraTransforms, ModelFolder, ModelRenderOptions, SharedRenderData,
-    SsbhRenderer, REQUIRED_FEATURES, RGBA_COLOR_FORMAT,
-};
-use wgpu::{
-    DeviceDescriptor, Extent3d, Limits, PowerPreference, RequestAdapterOptions, TextureDescriptor,
-    TextureDimension, TextureUsages,
+    load_render_models,
+    viewport::{frame_models, OrbitCamera},
+    ModelFolder, ModelRenderOptions, OffscreenTarget, SharedRenderData, SsbhRenderer,
+    REQUIRED_FEATURES, RGBA_COLOR_FORMAT,
 };
-
-fn calculate_camera_pos_mvp(
-    translation: glam::Vec3,
-    rotation: glam::Vec3,
-) -> (glam::Vec4, glam::Mat4, glam::Mat4) {
-    let aspect = 1.0;
-    let model_view_matrix = glam::Mat4::from_translation(translation)
-        * glam::Mat4::from_rotation_x(rotation.x)
-        * glam::Mat4::from_rotation_y(rotation.y);
-    // Use a large far clip distance to include stage skyboxes.
-    let perspective_matrix = glam::Mat4::perspective_rh(0.5, aspect, 1.0, 400000.0);
-
-    let camera_pos = model_view_matrix.inverse().col(3);
-
-    (
-        camera_pos,
-        model_view_matrix,
-        perspective_matrix * model_view_matrix,
-    )
-}
+use wgpu::{DeviceDescriptor, Limits, PowerPreference, RequestAdapterOptions};
 
 fn main() {
     // TODO: use pico-args for this.
@@ -75,10 +50,7 @@ fn main() {
     let shared_data = SharedRenderData::new(&device, &queue, surface_format);
     let mut renderer = SsbhRenderer::new(&device, &queue, 512, 512, 1.0, [0.0; 3], &[]);
 
-    // TODO: Share camera code with ssbh_wgpu?
     // TODO: Document the screen_dimensions struct.
-    // TODO: Frame each model individually?
-
     let rotation = if fighter_anim {
         // Match the in game orientation.
         glam::vec3(0.0, 50.0f32.to_radians(), 0.0)
@@ -86,40 +58,13 @@ fn main() {
         glam::Vec3::ZERO
     };
 
-    let (camera_pos, model_view_matrix, mvp_matrix) =
-        calculate_camera_pos_mvp(glam::vec3(0.0, -8.0, -60.0), rotation);
-    let transforms = CameraTransforms {
-        model_view_matrix,
-        mvp_matrix,
-        mvp_inv_matrix: mvp_matrix.inverse(),
-        camera_pos,
-        screen_dimensions: glam::vec4(512.0, 512.0, 1.0, 0.0),
-    };
-    renderer.update_camera(&queue, transforms);
-
-    let texture_desc = TextureDescriptor {
-        size: Extent3d {
-            width: 512,
-            height: 512,
-            depth_or_array_layers: 1,
-        },
-        mip_level_count: 1,
-        sample_count: 1,
-        dimension: TextureDimension::D2,
-        format: surface_format,
-        usage: TextureUsages::COPY_SRC | TextureUsages::RENDER_ATTACHMENT,
-        label: None,
-        view_formats: &[],
-    };
-    let output = device.create_texture(&texture_desc);
-    let output_view = output.create_view(&Default::default());
+    let default_transforms = OrbitCamera {
+        yaw: rotation.y,
+        ..Default::default()
+    }
+    .camera_transforms(512, 512, 1.0);
 
-    let output_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        size: 512 * 512 * 4,
-        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-        label: None,
-        mapped_at_creation: false,
-    });
+    let target = OffscreenTarget::new(&device, 512, 512);
 
     // Load and render folders individually to save on memory.
     let source_folder = Path::new(source_folder);
@@ -172,89 +117,28 @@ fn main() {
                         &shared_data,
                         0.0,
                     );
+                    render_model.update_material_textures(&device, &shared_data);
                 }
             }
         }
 
-        render_screenshot(
-            &device,
-            &renderer,
-            &output_view,
-            &render_models,
-            &shared_data,
-            &output,
-            &output_buffer,
-            texture_desc.size,
-            &queue,
-            output_path,
-        );
+        // Frame each model individually.
+        let transforms = frame_models(&render_models, rotation, 0.5, 512, 512, 1.0)
+            .unwrap_or(default_transforms);
+        renderer.update_camera(&queue, transforms);
+
+        let image = renderer
+            .render_to_image(
+                &device,
+                &queue,
+                &target,
+                &render_models,
+                shared_data.database(),
+                &ModelRenderOptions::default(),
+            )
+            .unwrap();
+        image.save(output_path).unwrap();
     }
 
     println!("Completed in {:?}", start.elapsed());
 }
-
-fn render_screenshot(
-    device: &wgpu::Device,
-    renderer: &SsbhRenderer,
-    output_view: &wgpu::TextureView,
-    render_models: &[ssbh_wgpu::RenderModel],
-    shared_data: &SharedRenderData,
-    output: &wgpu::Texture,
-    output_buffer: &wgpu::Buffer,
-    size: wgpu::Extent3d,
-    queue: &wgpu::Queue,
-    output_path: std::path::PathBuf,
-) {
-    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-        label: Some("Render Encoder"),
-    });
-    renderer.render_models(
-        &mut encoder,
-        output_view,
-        render_models,
-        shared_data.database(),
-        &ModelRenderOptions::default(),
-    );
-    encoder.copy_texture_to_buffer(
-        wgpu::ImageCopyTexture {
-            aspect: wgpu::TextureAspect::All,
-            texture: output,
-            mip_level: 0,
-            origin: wgpu::Origin3d::ZERO,
-        },
-        wgpu::ImageCopyBuffer {
-            buffer: output_buffer,
-            layout: wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: NonZeroU32::new(512 * 4),
-                rows_per_image: NonZeroU32::new(512),
-            },
-        },
-        size,
-    );
-    queue.submit([encoder.finish()]);
-    // TODO: Move this functionality to ssbh_wgpu for taking screenshots?
-    // Save the output texture.
-    // Adapted from WGPU Example https://github.com/gfx-rs/wgpu/tree/master/wgpu/examples/capture
-    {
-        // TODO: Find ways to optimize this?
-        let buffer_slice = output_buffer.slice(..);
-
-        // TODO: Reuse the channel?
-        let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            tx.send(result).unwrap();
-        });
-        device.poll(wgpu::Maintain::Wait);
-        block_on(rx.receive()).unwrap().unwrap();
-
-        let data = buffer_slice.get_mapped_range();
-        let mut buffer =
-            ImageBuffer::<image::Rgba<u8>, _>::from_raw(512, 512, data.to_owned()).unwrap();
-        // Convert BGRA to RGBA.
-        buffer.pixels_mut().for_each(|p| p.0.swap(0, 2));
-
-        buffer.save(output_path).unwrap();
-    }
-    output_buffer.unmap();
-}
@@ -3,10 +3,11 @@ use ssbh_data::prelude::*;
 use ssbh_wgpu::animation::camera::animate_camera;
 use ssbh_wgpu::next_frame;
 use ssbh_wgpu::swing::SwingPrc;
-use ssbh_wgpu::viewport::screen_to_world;
-use ssbh_wgpu::CameraTransforms;
+use ssbh_wgpu::viewport::OrbitCamera;
+use ssbh_wgpu::BoundingSphere;
 use ssbh_wgpu::DebugMode;
 use ssbh_wgpu::ModelFolder;
+use ssbh_wgpu::ModelFolderWatcher;
 use ssbh_wgpu::ModelRenderOptions;
 use ssbh_wgpu::NutexbFile;
 use ssbh_wgpu::RenderModel;
@@ -14,7 +15,8 @@ use ssbh_wgpu::RenderSettings;
 use ssbh_wgpu::SharedRenderData;
 use ssbh_wgpu::TransitionMaterial;
 use ssbh_wgpu::REQUIRED_FEATURES;
-use ssbh_wgpu::{load_model_folders, load_render_models, SsbhRenderer};
+use ssbh_wgpu::{load_model_folders, load_render_models_with_reports, SsbhRenderer};
+use ssbh_wgpu::{PickResult, PickTarget};
 use std::collections::HashSet;
 use std::path::PathBuf;
 use winit::{
@@ -28,28 +30,6 @@ const DEFAULT_FOV: f32 = 0.5;
 const DEFAULT_NEAR_CLIP: f32 = 1.0;
 const DEFAULT_FAR_CLIP: f32 = 400000.0;
 
-fn calculate_camera_pos_mvp(
-    size: winit::dpi::PhysicalSize<u32>,
-    translation: glam::Vec3,
-    rotation: glam::Vec3,
-) -> (glam::Vec4, glam::Mat4, glam::Mat4) {
-    let aspect = size.width as f32 / size.height as f32;
-    let model_view_matrix = glam::Mat4::from_translation(translation)
-        * glam::Mat4::from_rotation_x(rotation.x)
-        * glam::Mat4::from_rotation_y(rotation.y);
-    // Use a large far clip distance to include stage skyboxes.
-    let perspective_matrix =
-        glam::Mat4::perspective_rh(DEFAULT_FOV, aspect, DEFAULT_NEAR_CLIP, DEFAULT_FAR_CLIP);
-
-    let camera_pos = model_view_matrix.inverse().col(3);
-
-    (
-        camera_pos,
-        model_view_matrix,
-        perspective_matrix * model_view_matrix,
-    )
-}
-
 struct State {
     surface: wgpu::Surface,
     device: wgpu::Device,
@@ -69,8 +49,7 @@ struct State {
     previous_cursor_position: PhysicalPosition<f64>,
     is_mouse_left_clicked: bool,
     is_mouse_right_clicked: bool,
-    translation_xyz: glam::Vec3,
-    rotation_xyz: glam::Vec3,
+    camera: OrbitCamera,
 
     // Animations
     animation: Option<AnimData>,
@@ -87,6 +66,10 @@ struct State {
     is_playing: bool,
 
     render: RenderSettings,
+
+    // Reload files modified while the viewer is open.
+    watchers: Vec<ModelFolderWatcher>,
+    previous_reload_check: std::time::Instant,
 }
 
 impl State {
@@ -136,7 +119,6 @@ impl State {
         };
         surface.configure(&device, &config);
 
-        // TODO: Frame bounding spheres?
         let animation = anim.map(|anim_path| AnimData::from_file(anim_path).unwrap());
         let swing_prc = prc.and_then(|prc_path| SwingPrc::from_file(prc_path));
         let camera_animation =
@@ -163,16 +145,46 @@ impl State {
         }
 
         let models = load_model_folders(folder);
-        let mut render_models =
-            load_render_models(&device, &queue, models.iter().map(|(_, m)| m), &shared_data);
+        let mut render_models: Vec<_> = load_render_models_with_reports(
+            &device,
+            &queue,
+            models.iter().map(|(_, m)| m),
+            &shared_data,
+        )
+        .into_iter()
+        .map(|(render_model, report)| {
+            for error in report.errors {
+                log::error!("{error}");
+            }
+            render_model
+        })
+        .collect();
+        let watchers = models
+            .iter()
+            .map(|(path, _)| ModelFolderWatcher::new(path))
+            .collect();
 
         // Assume only one folder is loaded and apply the swing prc to every folder.
         if let Some(swing_prc) = &swing_prc {
             for (render_model, (_, model)) in render_models.iter_mut().zip(models.iter()) {
                 render_model.recreate_swing_collisions(&device, swing_prc, model.find_skel());
+                render_model.set_swing_simulation(Some(swing_prc), model.find_skel());
             }
         }
 
+        // Frame the models to avoid rendering small models or stages off screen.
+        let mut camera = OrbitCamera {
+            fov_y: DEFAULT_FOV,
+            near_clip: DEFAULT_NEAR_CLIP,
+            far_clip: DEFAULT_FAR_CLIP,
+            ..Default::default()
+        };
+        if let Some(sphere) =
+            BoundingSphere::from_spheres(render_models.iter().filter_map(|m| m.bounding_sphere()))
+        {
+            camera.frame(sphere, size.width as f32 / size.height as f32);
+        }
+
         let mut renderer = SsbhRenderer::new(
             &device,
             &queue,
@@ -207,8 +219,7 @@ impl State {
             previous_cursor_position: PhysicalPosition { x: 0.0, y: 0.0 },
             is_mouse_left_clicked: false,
             is_mouse_right_clicked: false,
-            translation_xyz: glam::vec3(0.0, -8.0, -60.0),
-            rotation_xyz: glam::vec3(0.0, 0.0, 0.0),
+            camera,
             animation,
             camera_animation,
             light_animation,
@@ -217,6 +228,8 @@ impl State {
             shared_data,
             is_playing: false,
             render: RenderSettings::default(),
+            watchers,
+            previous_reload_check: std::time::Instant::now(),
         }
     }
 
@@ -260,6 +273,7 @@ impl State {
                     (MouseButton::Right, ElementState::Released) => {
                         self.is_mouse_right_clicked = false
                     }
+                    (MouseButton::Middle, ElementState::Pressed) => self.select_under_cursor(),
                     _ => (),
                 }
                 true
@@ -270,39 +284,14 @@ impl State {
                     let delta_y = position.y - self.previous_cursor_position.y;
 
                     // Swap XY so that dragging left right rotates left right.
-                    self.rotation_xyz.x += (delta_y * 0.01) as f32;
-                    self.rotation_xyz.y += (delta_x * 0.01) as f32;
+                    self.camera
+                        .orbit((delta_x * 0.01) as f32, (delta_y * 0.01) as f32);
                 } else if self.is_mouse_right_clicked {
-                    // TODO: Avoid recalculating the matrix?
-                    // TODO: Does ignoring rotation like this work in general?
-                    let (_, _, mvp) = calculate_camera_pos_mvp(
-                        self.size,
-                        self.translation_xyz,
-                        self.rotation_xyz * 0.0,
-                    );
-
-                    let (current_x_world, current_y_world) = screen_to_world(
-                        (position.x as f32, position.y as f32),
-                        mvp,
-                        self.size.width,
-                        self.size.height,
-                    );
-                    let (previous_x_world, previous_y_world) = screen_to_world(
-                        (
-                            self.previous_cursor_position.x as f32,
-                            self.previous_cursor_position.y as f32,
-                        ),
-                        mvp,
-                        self.size.width,
-                        self.size.height,
-                    );
-
-                    let delta_x_world = current_x_world - previous_x_world;
-                    let delta_y_world = current_y_world - previous_y_world;
-
-                    // Negate y so that dragging up "drags" the model up.
-                    self.translation_xyz.x += delta_x_world;
-                    self.translation_xyz.y -= delta_y_world;
+                    let delta_x = position.x - self.previous_cursor_position.x;
+                    let delta_y = position.y - self.previous_cursor_position.y;
+
+                    self.camera
+                        .pan(delta_x as f32, delta_y as f32, self.size.height);
                 }
                 // Always update the position to avoid jumps when moving between clicks.
                 self.previous_cursor_position = *position;
@@ -310,17 +299,12 @@ impl State {
                 true
             }
             WindowEvent::MouseWheel { delta, .. } => {
-                // TODO: Add tests for handling scroll events properly?
                 // Scale zoom speed with distance to make it easier to zoom out large scenes.
-                let delta_z = match delta {
-                    MouseScrollDelta::LineDelta(_x, y) => *y * self.translation_xyz.z.abs() * 0.1,
-                    MouseScrollDelta::PixelDelta(p) => {
-                        p.y as f32 * self.translation_xyz.z.abs() * 0.005
-                    }
+                let amount = match delta {
+                    MouseScrollDelta::LineDelta(_x, y) => *y * 0.1,
+                    MouseScrollDelta::PixelDelta(p) => p.y as f32 * 0.005,
                 };
-
-                // Clamp to prevent the user from zooming through the origin.
-                self.translation_xyz.z = (self.translation_xyz.z + delta_z).min(-1.0);
+                self.camera.dolly(amount);
                 true
             }
             WindowEvent::KeyboardInput { input, .. } => {
@@ -328,8 +312,8 @@ impl State {
                     // Don't handle the release event to avoid duplicate events.
                     if matches!(input.state, winit::event::ElementState::Pressed) {
                         match keycode {
-                            VirtualKeyCode::Up => self.translation_xyz.z += 10.0,
-                            VirtualKeyCode::Down => self.translation_xyz.z -= 10.0,
+                            VirtualKeyCode::Up => self.camera.dolly(0.1),
+                            VirtualKeyCode::Down => self.camera.dolly(-0.1),
                             VirtualKeyCode::Space => self.is_playing = !self.is_playing,
                             VirtualKeyCode::Key1 => self.render.debug_mode = DebugMode::Shaded,
                             VirtualKeyCode::Key2 => self.render.debug_mode = DebugMode::ColorSet1,
@@ -398,26 +382,78 @@ impl State {
         }
     }
 
-    // TODO: Module and tests for a viewport camera.
+    fn select_under_cursor(&mut self) {
+        // The scale factor doesn't affect picking.
+        let camera = self
+            .camera
+            .camera_transforms(self.size.width, self.size.height, 1.0);
+        let point = (
+            self.previous_cursor_position.x as f32,
+            self.previous_cursor_position.y as f32,
+        );
+
+        // Select only the closest mesh from all models.
+        let closest = self
+            .render_models
+            .iter()
+            .enumerate()
+            .filter_map(|(i, m)| Some((i, m.pick_mesh(point, &camera)?)))
+            .reduce(|a, b| if b.1.distance < a.1.distance { b } else { a });
+
+        for (i, render_model) in self.render_models.iter_mut().enumerate() {
+            for mesh in &mut render_model.meshes {
+                mesh.is_selected = match &closest {
+                    Some((
+                        index,
+                        PickResult {
+                            target: PickTarget::Mesh { name, subindex },
+                            ..
+                        },
+                    )) => *index == i && *name == mesh.name && *subindex == mesh.subindex,
+                    _ => false,
+                };
+            }
+        }
+    }
 
     fn update_camera(&mut self, scale_factor: f64) {
-        let (camera_pos, model_view_matrix, mvp_matrix) =
-            calculate_camera_pos_mvp(self.size, self.translation_xyz, self.rotation_xyz);
-        let transforms = CameraTransforms {
-            model_view_matrix,
-            mvp_matrix,
-            mvp_inv_matrix: mvp_matrix.inverse(),
-            camera_pos,
-            screen_dimensions: glam::vec4(
-                self.size.width as f32,
-                self.size.height as f32,
-                scale_factor as f32,
-                0.0,
-            ),
-        };
+        let transforms =
+            self.camera
+                .camera_transforms(self.size.width, self.size.height, scale_factor as f32);
         self.renderer.update_camera(&self.queue, transforms);
     }
 
+    fn reload_changed_files(&mut self) {
+        // Avoid checking the file system every frame.
+        if self.previous_reload_check.elapsed() < std::time::Duration::from_secs(1) {
+            return;
+        }
+        self.previous_reload_check = std::time::Instant::now();
+
+        for ((watcher, (path, model)), render_model) in self
+            .watchers
+            .iter_mut()
+            .zip(self.models.iter_mut())
+            .zip(self.render_models.iter_mut())
+        {
+            let diff = watcher.changes();
+            if !diff.is_empty() {
+                log::info!("Reloading {:?} in {:?}", diff.file_names, path);
+                model.reload_files(path, &diff);
+                let report = render_model.update_from_changes(
+                    &self.device,
+                    &self.queue,
+                    model,
+                    &self.shared_data,
+                    &diff,
+                );
+                for error in report.errors {
+                    log::error!("{error}");
+                }
+            }
+        }
+    }
+
     fn update_render_settings(&mut self) {
         self.renderer
             .update_render_settings(&self.queue, &self.render);
@@ -478,6 +514,7 @@ impl State {
                     &self.shared_data,
                     self.current_frame,
                 );
+                model.update_material_textures(&self.device, &self.shared_data);
             }
 
             if let Some(anim) = &self.camera_animation {
@@ -529,8 +566,10 @@ impl State {
 
         drop(final_pass);
 
-        let (_, _, mvp) =
-            calculate_camera_pos_mvp(self.size, self.translation_xyz, self.rotation_xyz);
+        let mvp = self
+            .camera
+            .camera_transforms(self.size.width, self.size.height, scale_factor as f32)
+            .mvp_matrix;
 
         if let Some(text_commands) = self.renderer.render_skeleton_names(
             &self.device,
@@ -643,6 +682,8 @@ fn main() {
                 }
             }
             Event::MainEventsCleared => {
+                state.reload_changed_files();
+
                 // RedrawRequested will only trigger once, unless we manually
                 // request it.
                 window.request_redraw();